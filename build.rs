@@ -0,0 +1,53 @@
+use std::fmt::Write as _;
+use std::path::Path;
+
+/// Embed every `dbinit/NNNN_name.sql` script, ordered by version, as `MIGRATIONS`.
+fn main() {
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR is set");
+    let dbinit = Path::new(&manifest_dir).join("dbinit");
+    println!("cargo:rerun-if-changed={}", dbinit.display());
+
+    let mut scripts = Vec::new();
+    for entry in std::fs::read_dir(&dbinit).expect("dbinit/ is readable") {
+        let path = entry.expect("dbinit/ entry is readable").path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("sql") {
+            continue;
+        }
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .expect("migration file name is UTF-8")
+            .to_owned();
+        let version: i32 = name
+            .split_once('_')
+            .and_then(|(prefix, _)| prefix.parse().ok())
+            .unwrap_or_else(|| panic!("migration {name} must be named NNNN_description.sql"));
+        println!("cargo:rerun-if-changed={}", path.display());
+        scripts.push((version, name, path));
+    }
+    scripts.sort_by_key(|(version, _, _)| *version);
+    for pair in scripts.windows(2) {
+        assert!(
+            pair[0].0 != pair[1].0,
+            "migrations {} and {} share version {}",
+            pair[0].1,
+            pair[1].1,
+            pair[0].0
+        );
+    }
+
+    let mut generated = String::from("pub(crate) const MIGRATIONS: &[Migration] = &[\n");
+    for (version, name, path) in &scripts {
+        writeln!(
+            generated,
+            "    Migration {{ version: {version}, name: {name:?}, sql: include_str!({:?}) }},",
+            path.display().to_string()
+        )
+        .expect("writing to a String cannot fail");
+    }
+    generated.push_str("];\n");
+
+    let out_dir = std::env::var("OUT_DIR").expect("OUT_DIR is set");
+    std::fs::write(Path::new(&out_dir).join("migrations.rs"), generated)
+        .expect("generated migrations are writable");
+}
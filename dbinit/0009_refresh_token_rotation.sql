@@ -0,0 +1,14 @@
+-- Refresh token rotation feature.
+-- Every refresh consumes its session row and issues a new one in the same
+-- family. Presenting a consumed token again revokes the whole family.
+BEGIN;
+
+ALTER TABLE sessions
+    ADD COLUMN IF NOT EXISTS family_id UUID NOT NULL DEFAULT gen_random_uuid();
+
+ALTER TABLE sessions
+    ADD COLUMN IF NOT EXISTS consumed_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_sessions_family_id ON sessions(family_id);
+
+COMMIT;
@@ -0,0 +1,9 @@
+-- Access-token revocation feature.
+-- Every access token carries the user's token version. Bumping it invalidates
+-- all outstanding access tokens for that user at once.
+BEGIN;
+
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS token_version INTEGER NOT NULL DEFAULT 0;
+
+COMMIT;
@@ -0,0 +1,7 @@
+-- Password policy feature.
+BEGIN;
+
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS password_changed_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
+
+COMMIT;
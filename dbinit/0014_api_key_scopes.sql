@@ -0,0 +1,22 @@
+-- Scoped API keys feature.
+BEGIN;
+
+-- NULL scopes keep a key limited to the Subsonic API, as before.
+ALTER TABLE api_keys ADD COLUMN IF NOT EXISTS scopes TEXT[];
+ALTER TABLE api_keys ADD COLUMN IF NOT EXISTS allowed_ips TEXT[];
+ALTER TABLE api_keys ADD COLUMN IF NOT EXISTS expires_at TIMESTAMPTZ;
+ALTER TABLE api_keys ADD COLUMN IF NOT EXISTS last_used_ip TEXT;
+
+CREATE TABLE IF NOT EXISTS api_key_usage (
+    id BIGSERIAL PRIMARY KEY,
+    api_key_id UUID NOT NULL REFERENCES api_keys(id) ON DELETE CASCADE,
+    ip_address TEXT,
+    method TEXT NOT NULL,
+    path TEXT NOT NULL,
+    used_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_api_key_usage_key_used_at
+    ON api_key_usage(api_key_id, used_at DESC);
+
+COMMIT;
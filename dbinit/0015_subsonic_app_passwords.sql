@@ -0,0 +1,18 @@
+-- Subsonic app passwords feature.
+BEGIN;
+
+-- Sealed with the server's app secret key rather than hashed, so token
+-- authentication can recompute md5(password + salt).
+CREATE TABLE IF NOT EXISTS subsonic_app_passwords (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    label TEXT,
+    sealed_password BYTEA NOT NULL,
+    last_used_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_subsonic_app_passwords_user_id
+    ON subsonic_app_passwords(user_id);
+
+COMMIT;
@@ -0,0 +1,29 @@
+-- Admin impersonation feature.
+BEGIN;
+
+INSERT INTO permissions (name, display_name, group_name) VALUES
+    ('impersonate_users', 'Sign in as lower-ranked users', 'users')
+ON CONFLICT (name) DO NOTHING;
+
+INSERT INTO role_permissions (role_id, permission_id)
+SELECT r.id, p.id
+FROM roles r
+JOIN permissions p ON p.name = 'impersonate_users'
+WHERE r.name = 'admin'
+ON CONFLICT DO NOTHING;
+
+CREATE TABLE IF NOT EXISTS impersonation_events (
+    id BIGSERIAL PRIMARY KEY,
+    impersonator_user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    impersonator_username VARCHAR(255) NOT NULL,
+    target_user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    target_username VARCHAR(255) NOT NULL,
+    action VARCHAR(10) NOT NULL CHECK (action IN ('start', 'stop')),
+    ip_address TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_impersonation_events_created_at
+    ON impersonation_events(created_at DESC);
+
+COMMIT;
@@ -0,0 +1,37 @@
+-- Audit log feature.
+BEGIN;
+
+INSERT INTO permissions (name, display_name, group_name) VALUES
+    ('view_audit_log', 'View and export the audit log', 'users')
+ON CONFLICT (name) DO NOTHING;
+
+INSERT INTO role_permissions (role_id, permission_id)
+SELECT r.id, p.id
+FROM roles r
+JOIN permissions p ON p.name = 'view_audit_log'
+WHERE r.name = 'admin'
+ON CONFLICT DO NOTHING;
+
+-- Rows are append-only and reference nothing, so deleting a user cannot rewrite them.
+-- Each hash covers the row and the previous row's hash.
+CREATE TABLE IF NOT EXISTS audit_events (
+    id BIGSERIAL PRIMARY KEY,
+    occurred_at TIMESTAMPTZ NOT NULL,
+    actor_user_id UUID,
+    actor_username VARCHAR(255),
+    impersonator_username VARCHAR(255),
+    action VARCHAR(100) NOT NULL,
+    target_type VARCHAR(50) NOT NULL,
+    target_id TEXT NOT NULL,
+    ip_address TEXT,
+    before JSONB,
+    after JSONB,
+    prev_hash CHAR(64),
+    hash CHAR(64) NOT NULL UNIQUE
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_events_occurred_at ON audit_events(occurred_at DESC);
+CREATE INDEX IF NOT EXISTS idx_audit_events_action ON audit_events(action);
+CREATE INDEX IF NOT EXISTS idx_audit_events_target ON audit_events(target_type, target_id);
+
+COMMIT;
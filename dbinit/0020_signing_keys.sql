@@ -0,0 +1,29 @@
+-- Asymmetric token signing feature.
+BEGIN;
+
+INSERT INTO permissions (name, display_name, group_name) VALUES
+    ('manage_signing_keys', 'Rotate token signing keys', 'users')
+ON CONFLICT (name) DO NOTHING;
+
+INSERT INTO role_permissions (role_id, permission_id)
+SELECT r.id, p.id
+FROM roles r
+JOIN permissions p ON p.name = 'manage_signing_keys'
+WHERE r.name = 'admin'
+ON CONFLICT DO NOTHING;
+
+-- Private keys are sealed with the server's app secret key. Retired keys keep
+-- verifying tokens until the grace period after retired_at has passed.
+CREATE TABLE IF NOT EXISTS signing_keys (
+    kid VARCHAR(64) PRIMARY KEY,
+    algorithm VARCHAR(16) NOT NULL,
+    sealed_private_key BYTEA NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    retired_at TIMESTAMPTZ
+);
+
+-- At most one key signs new tokens.
+CREATE UNIQUE INDEX IF NOT EXISTS idx_signing_keys_active
+    ON signing_keys ((retired_at IS NULL)) WHERE retired_at IS NULL;
+
+COMMIT;
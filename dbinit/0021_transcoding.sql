@@ -0,0 +1,39 @@
+-- Transcoding profiles for OpenSubsonic streaming, and each user's defaults.
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS transcoding_profiles (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(64) NOT NULL UNIQUE,
+    source_formats TEXT[] NOT NULL DEFAULT '{}',   -- empty: any source format
+    target_format VARCHAR(16) NOT NULL
+        CHECK (target_format IN ('mp3', 'opus', 'ogg', 'aac', 'flac')),
+    bitrate_kbps INTEGER NOT NULL CHECK (bitrate_kbps BETWEEN 32 AND 640),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Profiles applied, in order, when a client does not ask for a format.
+CREATE TABLE IF NOT EXISTS user_transcoding_profiles (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    profile_id UUID NOT NULL REFERENCES transcoding_profiles(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    PRIMARY KEY (user_id, profile_id)
+);
+
+INSERT INTO transcoding_profiles (name, source_formats, target_format, bitrate_kbps) VALUES
+    ('flac-to-opus-128', '{flac,wav}', 'opus', 128),
+    ('mp3-320',          '{}',         'mp3',  320)
+ON CONFLICT (name) DO NOTHING;
+
+INSERT INTO permissions (name, display_name, group_name) VALUES
+    ('manage_transcoding', 'Manage transcoding profiles', 'music')
+ON CONFLICT (name) DO NOTHING;
+
+INSERT INTO role_permissions (role_id, permission_id)
+SELECT r.id, p.id
+FROM roles r
+JOIN permissions p ON p.name = 'manage_transcoding'
+WHERE r.name = 'admin'
+ON CONFLICT DO NOTHING;
+
+COMMIT;
@@ -0,0 +1,20 @@
+-- Subsonic play queue sync: each user's saved queue, current item and position.
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS play_queues (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    current_position INTEGER,                 -- play_queue_entries.position of the current item
+    position_ms BIGINT NOT NULL DEFAULT 0 CHECK (position_ms >= 0),
+    changed_by TEXT,
+    changed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- A song may appear more than once; entries of deleted songs simply disappear.
+CREATE TABLE IF NOT EXISTS play_queue_entries (
+    user_id UUID NOT NULL REFERENCES play_queues(user_id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    song_id UUID NOT NULL REFERENCES songs(id) ON DELETE CASCADE,
+    PRIMARY KEY (user_id, position)
+);
+
+COMMIT;
@@ -0,0 +1,24 @@
+-- Internet radio stations, shared by every user and listed to Subsonic clients.
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS internet_radio_stations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name TEXT NOT NULL,
+    stream_url TEXT NOT NULL,
+    homepage_url TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+INSERT INTO permissions (name, display_name, group_name) VALUES
+    ('manage_radio', 'Manage internet radio stations', 'music')
+ON CONFLICT (name) DO NOTHING;
+
+INSERT INTO role_permissions (role_id, permission_id)
+SELECT r.id, p.id
+FROM roles r
+JOIN permissions p ON p.name = 'manage_radio'
+WHERE r.name = 'admin'
+ON CONFLICT DO NOTHING;
+
+COMMIT;
@@ -0,0 +1,36 @@
+-- Public share links to songs, albums and playlists (Subsonic shares API).
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS shares (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    token_hash CHAR(64) NOT NULL UNIQUE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    description TEXT,
+    expires_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_visited_at TIMESTAMPTZ,
+    visit_count INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE INDEX IF NOT EXISTS idx_shares_user_id ON shares(user_id);
+
+-- Albums and playlists are expanded to their songs when the share is created.
+CREATE TABLE IF NOT EXISTS share_entries (
+    share_id UUID NOT NULL REFERENCES shares(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    song_id UUID NOT NULL REFERENCES songs(id) ON DELETE CASCADE,
+    PRIMARY KEY (share_id, position)
+);
+
+INSERT INTO permissions (name, display_name, group_name) VALUES
+    ('share_music', 'Share music through public links', 'music')
+ON CONFLICT (name) DO NOTHING;
+
+INSERT INTO role_permissions (role_id, permission_id)
+SELECT r.id, p.id
+FROM roles r
+JOIN permissions p ON p.name = 'share_music'
+WHERE r.name = 'admin'
+ON CONFLICT DO NOTHING;
+
+COMMIT;
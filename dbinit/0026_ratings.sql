@@ -0,0 +1,37 @@
+-- Per-user 1-5 ratings of songs, albums and artists (Subsonic setRating).
+-- Albums and artists have no table of their own, so they are keyed by name like starred.
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS song_ratings (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    song_id UUID NOT NULL REFERENCES songs(id) ON DELETE CASCADE,
+    rating SMALLINT NOT NULL CHECK (rating BETWEEN 1 AND 5),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, song_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_song_ratings_song_id ON song_ratings(song_id);
+
+CREATE TABLE IF NOT EXISTS album_ratings (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    artist_name TEXT NOT NULL,
+    album_name TEXT NOT NULL,
+    rating SMALLINT NOT NULL CHECK (rating BETWEEN 1 AND 5),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, artist_name, album_name)
+);
+
+CREATE INDEX IF NOT EXISTS idx_album_ratings_album
+    ON album_ratings(artist_name, album_name);
+
+CREATE TABLE IF NOT EXISTS artist_ratings (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    artist_name TEXT NOT NULL,
+    rating SMALLINT NOT NULL CHECK (rating BETWEEN 1 AND 5),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, artist_name)
+);
+
+CREATE INDEX IF NOT EXISTS idx_artist_ratings_artist ON artist_ratings(artist_name);
+
+COMMIT;
@@ -0,0 +1,16 @@
+-- Lyrics read from song tags (USLT, Vorbis LYRICS, SYLT) or an `.lrc` file next to the track.
+-- A song has at most one unsynced and one synced set; `lines` is a JSON array of
+-- {"start": milliseconds, "value": text} objects, `start` only present for synced lyrics.
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS lyrics (
+    song_id UUID NOT NULL REFERENCES songs(id) ON DELETE CASCADE,
+    synced BOOLEAN NOT NULL,
+    source TEXT NOT NULL CHECK (source IN ('tag', 'lrc')),
+    lang TEXT NOT NULL DEFAULT 'und',
+    lines JSONB NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (song_id, synced)
+);
+
+COMMIT;
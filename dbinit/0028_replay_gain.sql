@@ -0,0 +1,14 @@
+-- EBU R128 based ReplayGain 2.0 values, filled in by the background analyser.
+-- `replay_gain_analyzed_at` is also set when a file cannot be decoded, so it is not retried.
+BEGIN;
+
+ALTER TABLE songs ADD COLUMN IF NOT EXISTS replay_gain_track_gain REAL;
+ALTER TABLE songs ADD COLUMN IF NOT EXISTS replay_gain_track_peak REAL;
+ALTER TABLE songs ADD COLUMN IF NOT EXISTS replay_gain_album_gain REAL;
+ALTER TABLE songs ADD COLUMN IF NOT EXISTS replay_gain_album_peak REAL;
+ALTER TABLE songs ADD COLUMN IF NOT EXISTS replay_gain_analyzed_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_songs_replay_gain_pending
+    ON songs(artist, album) WHERE replay_gain_analyzed_at IS NULL;
+
+COMMIT;
@@ -0,0 +1,12 @@
+-- Audio stream properties read by the tag scanner. `codec` is the decoded codec
+-- (e.g. `aac` or `alac` inside `.m4a`), unlike `format`, which is the file extension.
+BEGIN;
+
+ALTER TABLE songs ADD COLUMN IF NOT EXISTS sample_rate INTEGER;
+ALTER TABLE songs ADD COLUMN IF NOT EXISTS bit_depth SMALLINT;
+ALTER TABLE songs ADD COLUMN IF NOT EXISTS channel_count SMALLINT;
+ALTER TABLE songs ADD COLUMN IF NOT EXISTS codec TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_songs_codec ON songs(codec);
+
+COMMIT;
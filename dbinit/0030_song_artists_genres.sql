@@ -0,0 +1,52 @@
+-- Normalised artists and genres, filled from multi-value tags by the scanner.
+-- `songs.artist` and `songs.genre` keep the raw tag text that the tag editor round-trips.
+-- Existing songs get their single artist, album artist and genre; a rescan splits them.
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS artists (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name TEXT NOT NULL UNIQUE
+);
+
+CREATE TABLE IF NOT EXISTS song_artists (
+    song_id UUID NOT NULL REFERENCES songs(id) ON DELETE CASCADE,
+    artist_id UUID NOT NULL REFERENCES artists(id) ON DELETE CASCADE,
+    role TEXT NOT NULL CHECK (role IN (
+        'artist', 'featured', 'albumartist', 'composer', 'performer', 'conductor', 'lyricist'
+    )),
+    position SMALLINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (song_id, artist_id, role)
+);
+
+CREATE INDEX IF NOT EXISTS idx_song_artists_artist ON song_artists(artist_id, role);
+
+CREATE TABLE IF NOT EXISTS song_genres (
+    song_id UUID NOT NULL REFERENCES songs(id) ON DELETE CASCADE,
+    genre TEXT NOT NULL,
+    position SMALLINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (song_id, genre)
+);
+
+CREATE INDEX IF NOT EXISTS idx_song_genres_genre ON song_genres(genre);
+
+INSERT INTO artists (name)
+SELECT name FROM (
+    SELECT artist AS name FROM songs
+    UNION
+    SELECT album_artist FROM songs WHERE album_artist IS NOT NULL AND album_artist <> ''
+) names
+ON CONFLICT (name) DO NOTHING;
+
+INSERT INTO song_artists (song_id, artist_id, role)
+SELECT s.id, a.id, 'artist' FROM songs s JOIN artists a ON a.name = s.artist
+ON CONFLICT DO NOTHING;
+
+INSERT INTO song_artists (song_id, artist_id, role)
+SELECT s.id, a.id, 'albumartist' FROM songs s JOIN artists a ON a.name = s.album_artist
+ON CONFLICT DO NOTHING;
+
+INSERT INTO song_genres (song_id, genre)
+SELECT id, genre FROM songs WHERE genre IS NOT NULL AND genre <> ''
+ON CONFLICT DO NOTHING;
+
+COMMIT;
@@ -0,0 +1,59 @@
+-- Impersonation sessions: each start names its session and token expiry, and the session
+-- ends with an explicit stop, a refresh back to the admin's own account, or token expiry.
+BEGIN;
+
+ALTER TABLE impersonation_events ADD COLUMN IF NOT EXISTS session_id UUID;
+ALTER TABLE impersonation_events ADD COLUMN IF NOT EXISTS expires_at TIMESTAMPTZ;
+
+ALTER TABLE impersonation_events DROP CONSTRAINT IF EXISTS impersonation_events_action_check;
+ALTER TABLE impersonation_events ADD CONSTRAINT impersonation_events_action_check
+    CHECK (action IN ('start', 'stop', 'refresh', 'expire'));
+
+-- Earlier impersonation tokens lasted 30 minutes; pair each earlier stop with its start.
+UPDATE impersonation_events
+SET session_id = gen_random_uuid(), expires_at = created_at + INTERVAL '30 minutes'
+WHERE action = 'start' AND session_id IS NULL;
+
+UPDATE impersonation_events stop
+SET session_id = (
+    SELECT start.session_id FROM impersonation_events start
+    WHERE start.action = 'start'
+      AND start.impersonator_user_id = stop.impersonator_user_id
+      AND start.target_user_id = stop.target_user_id
+      AND start.created_at <= stop.created_at
+    ORDER BY start.created_at DESC
+    LIMIT 1
+)
+WHERE stop.action = 'stop' AND stop.session_id IS NULL;
+
+CREATE INDEX IF NOT EXISTS idx_impersonation_events_session_id
+    ON impersonation_events(session_id);
+
+-- `ended_at` is NULL while the token is still usable.
+CREATE OR REPLACE VIEW impersonation_sessions AS
+SELECT s.session_id,
+       s.impersonator_user_id,
+       s.impersonator_username,
+       s.target_user_id,
+       s.target_username,
+       s.ip_address,
+       s.created_at AS started_at,
+       s.expires_at,
+       CASE
+           WHEN e.created_at IS NOT NULL THEN e.created_at
+           WHEN s.expires_at <= NOW() THEN s.expires_at
+       END AS ended_at,
+       CASE
+           WHEN e.action IS NOT NULL THEN e.action
+           WHEN s.expires_at <= NOW() THEN 'expire'
+       END AS end_reason
+FROM impersonation_events s
+LEFT JOIN LATERAL (
+    SELECT e.action, e.created_at FROM impersonation_events e
+    WHERE e.session_id = s.session_id AND e.action <> 'start'
+    ORDER BY e.created_at
+    LIMIT 1
+) e ON TRUE
+WHERE s.action = 'start';
+
+COMMIT;
@@ -1,7 +1,8 @@
 import logoSrc from '@local/assets/icons8-folder-color-120.png'
 import { UploadPanel } from '@local/components/UploadPanel'
+import { api } from '@local/hooks/api'
 import { useAuth } from '@local/hooks/authContext'
-import { Badge, Burger, Group, Text } from '@mantine/core'
+import { Badge, Burger, Button, Group, Text } from '@mantine/core'
 import { Link, useRouterState } from '@tanstack/react-router'
 
 interface HeaderBarProps {
@@ -9,6 +10,11 @@ interface HeaderBarProps {
   onToggle?: () => void
 }
 
+async function stopImpersonating() {
+  await api.delete('/auth/impersonation')
+  window.location.assign('/admin/users')
+}
+
 export function HeaderBar({ mobileOpened, onToggle }: HeaderBarProps) {
   const { user } = useAuth()
   const pathname = useRouterState({ select: (s) => s.location.pathname })
@@ -52,7 +58,19 @@ export function HeaderBar({ mobileOpened, onToggle }: HeaderBarProps) {
         )}
       </Group>
 
-      <Group gap="xs">{!isLoginRoute && user && <UploadPanel />}</Group>
+      <Group gap="xs">
+        {user?.impersonated_by && (
+          <>
+            <Badge size="sm" variant="light" color="orange">
+              Viewing as {user.username}
+            </Badge>
+            <Button size="compact-xs" variant="light" color="orange" onClick={stopImpersonating}>
+              Stop
+            </Button>
+          </>
+        )}
+        {!isLoginRoute && user && <UploadPanel />}
+      </Group>
     </Group>
   )
 }
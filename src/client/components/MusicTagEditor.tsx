@@ -1,3 +1,4 @@
+import { useMusicSongLyrics } from '@local/hooks/useMusic'
 import { musicCoverUrl } from '@local/lib/musicCover'
 import type { MusicSong, MusicTagFormValues, MusicTagUpdate } from '@local/types/music'
 import {
@@ -9,6 +10,7 @@ import {
   Modal,
   NumberInput,
   Stack,
+  Textarea,
   TextInput,
 } from '@mantine/core'
 import { useForm } from '@mantine/form'
@@ -58,6 +60,8 @@ export function MusicTagEditor({ song, opened, saving, onClose, onSave }: MusicT
       year: song.year ?? '',
       track_number: song.track_number ?? '',
       disc_number: song.disc_number ?? '',
+      lyrics: '',
+      synced_lyrics: '',
     },
     validateInputOnBlur: true,
     validate: {
@@ -70,6 +74,19 @@ export function MusicTagEditor({ song, opened, saving, onClose, onSave }: MusicT
     },
   })
 
+  const lyricsQuery = useMusicSongLyrics(song.id)
+  const currentLyrics = lyricsQuery.data
+  // biome-ignore lint/correctness/useExhaustiveDependencies: the form instance is stable
+  useEffect(() => {
+    if (!currentLyrics) return
+    const lyricsValues = {
+      lyrics: currentLyrics.lyrics ?? '',
+      synced_lyrics: currentLyrics.synced_lyrics ?? '',
+    }
+    form.setInitialValues({ ...form.getInitialValues(), ...lyricsValues })
+    form.setValues(lyricsValues)
+  }, [currentLyrics])
+
   return (
     <Modal opened={opened} onClose={onClose} title={`Edit tags - ${song.title}`} size="lg">
       <form
@@ -84,6 +101,11 @@ export function MusicTagEditor({ song, opened, saving, onClose, onSave }: MusicT
               year: values.year === '' ? null : Number(values.year),
               track_number: values.track_number === '' ? null : Number(values.track_number),
               disc_number: values.disc_number === '' ? null : Number(values.disc_number),
+              // Lyrics are only sent once loaded so a failed lookup never clears them.
+              ...(currentLyrics && {
+                lyrics: values.lyrics.trim(),
+                synced_lyrics: values.synced_lyrics.trim(),
+              }),
             },
             cover
           )
@@ -196,6 +218,28 @@ export function MusicTagEditor({ song, opened, saving, onClose, onSave }: MusicT
               />
             </Grid.Col>
           </Grid>
+          <Textarea
+            key={form.key('lyrics')}
+            label="Lyrics"
+            autosize
+            minRows={3}
+            maxRows={10}
+            size="sm"
+            disabled={!currentLyrics}
+            {...form.getInputProps('lyrics')}
+          />
+          <Textarea
+            key={form.key('synced_lyrics')}
+            label="Synced lyrics"
+            description="LRC format, for example [00:12.50] First line. Saved next to the track."
+            autosize
+            minRows={3}
+            maxRows={10}
+            size="sm"
+            disabled={!currentLyrics}
+            styles={{ input: { fontFamily: 'var(--mantine-font-family-monospace)' } }}
+            {...form.getInputProps('synced_lyrics')}
+          />
           <Group justify="flex-end" mt="xs">
             <Button type="button" variant="default" onClick={onClose} disabled={saving}>
               Cancel
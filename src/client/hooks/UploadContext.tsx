@@ -1,3 +1,4 @@
+import { CSRF_HEADER, csrfToken } from '@local/hooks/api'
 import { queryKeys } from '@local/hooks/queryKeys'
 import { musicQueryKeys } from '@local/hooks/useMusic'
 import { useQueryClient } from '@tanstack/react-query'
@@ -47,7 +48,7 @@ interface UploadContextValue {
 }
 
 const TUS_CHUNK_SIZE = 8 * 1024 * 1024
-export const MUSIC_UPLOAD_FILE_TYPES = ['audio/*', '.mp3', '.flac', '.m4a', '.ogg', '.oga', '.wav']
+export const MUSIC_UPLOAD_FILE_TYPES = ['audio/*', '.mp3', '.flac', '.m4a', '.ogg', '.oga', '.wav', '.lrc']
 
 function createUploader(endpoint: string, allowedFileTypes?: Array<string>) {
   return new Uppy({
@@ -65,6 +66,10 @@ function createUploader(endpoint: string, allowedFileTypes?: Array<string>) {
       return defaultOnShouldRetry(error)
     },
     removeFingerprintOnSuccess: true,
+    onBeforeRequest: (req) => {
+      const token = csrfToken()
+      if (token) req.setHeader(CSRF_HEADER, token)
+    },
   })
 }
 
@@ -101,10 +106,15 @@ function deleteTusUpload(uploadUrl: string) {
   void fetch(uploadUrl, {
     method: 'DELETE',
     credentials: 'same-origin',
-    headers: { 'Tus-Resumable': '1.0.0' },
+    headers: { 'Tus-Resumable': '1.0.0', ...csrfHeaders() },
   }).catch(() => undefined)
 }
 
+function csrfHeaders(): Record<string, string> {
+  const token = csrfToken()
+  return token ? { [CSRF_HEADER]: token } : {}
+}
+
 const UploadContext = createContext<UploadContextValue | null>(null)
 
 function isPendingTusUpload(value: unknown): value is PendingTusUpload {
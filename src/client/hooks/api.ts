@@ -24,6 +24,26 @@ export const api = axios.create({
   headers: { 'Content-Type': 'application/json' },
 })
 
+const CSRF_COOKIE = 'csrfToken'
+export const CSRF_HEADER = 'X-CSRF-Token'
+
+// The server only accepts cookie-authenticated mutations that echo this cookie
+// back in `X-CSRF-Token`.
+export function csrfToken(): string | undefined {
+  const prefix = `${CSRF_COOKIE}=`
+  const entry = document.cookie.split('; ').find((cookie) => cookie.startsWith(prefix))
+  return entry ? decodeURIComponent(entry.slice(prefix.length)) : undefined
+}
+
+api.interceptors.request.use((cfg) => {
+  const method = (cfg.method || 'get').toLowerCase()
+  const token = csrfToken()
+  if (token && !['get', 'head', 'options'].includes(method)) {
+    cfg.headers.set(CSRF_HEADER, token)
+  }
+  return cfg
+})
+
 let logoutCallback: (() => Promise<void>) | null = null
 let activeSession = false
 
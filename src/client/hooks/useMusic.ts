@@ -4,6 +4,7 @@ import type {
   MusicListParams,
   MusicSong,
   MusicSongListResponse,
+  MusicSongLyrics,
   MusicSongMutation,
   MusicSongSelectionResponse,
 } from '@local/types/music'
@@ -26,12 +27,17 @@ export const musicQueryKeys = {
     all: ['music', 'personal'] as const,
     list: (params: MusicListParams) => ['music', 'personal', 'list', params] as const,
   },
+  lyrics: {
+    all: ['music', 'lyrics'] as const,
+    song: (songId: string) => ['music', 'lyrics', songId] as const,
+  },
 } as const
 
 async function invalidateMusicQueries(queryClient: QueryClient): Promise<void> {
   await Promise.all([
     queryClient.invalidateQueries({ queryKey: musicQueryKeys.global.all }),
     queryClient.invalidateQueries({ queryKey: musicQueryKeys.personal.all }),
+    queryClient.invalidateQueries({ queryKey: musicQueryKeys.lyrics.all }),
   ])
 }
 
@@ -145,6 +151,16 @@ export function useKnownPersonalSongIds(currentSongs: Array<MusicSong>) {
   }, [currentSongs, queryClient])
 }
 
+export function useMusicSongLyrics(songId: string) {
+  return useQuery({
+    queryKey: musicQueryKeys.lyrics.song(songId),
+    queryFn: async () => {
+      const response = await api.get<MusicSongLyrics>(`/music/songs/${songId}/lyrics`)
+      return response.data
+    },
+  })
+}
+
 export function useUpdateMusicSong() {
   const queryClient = useQueryClient()
   return useMutation({
@@ -125,6 +125,12 @@ export function useUsers() {
     await deleteMutation.mutateAsync(userId)
   }
 
+  async function handleImpersonate(userId: string) {
+    await api.post(`/auth/impersonation/${userId}`)
+    // Reload so every cached query is refetched as the impersonated user.
+    window.location.assign('/browse')
+  }
+
   return {
     currentUser,
     authLoading,
@@ -155,11 +161,13 @@ export function useUsers() {
     handleCreate,
     handlePasswordUpdate,
     handleDelete,
+    handleImpersonate,
 
     // Derived
     isAdminUser: currentUser ? currentUser.role_name === 'admin' : false,
     canCreate: currentUser ? currentUser.role_name === 'admin' : false,
     canEdit: currentUser ? currentUser.role_name === 'admin' : false,
     canDelete: currentUser ? currentUser.role_name === 'admin' : false,
+    canImpersonate: currentUser?.permissions?.includes('impersonate_users') ?? false,
   }
 }
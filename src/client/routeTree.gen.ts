@@ -17,6 +17,8 @@ import { Route as IndexRouteImport } from './routes/index'
 import { Route as SettingsIndexRouteImport } from './routes/settings/index'
 import { Route as AdminIndexRouteImport } from './routes/admin/index'
 import { Route as UploadTokenRouteImport } from './routes/upload.$token'
+import { Route as ShareTokenRouteImport } from './routes/share.$token'
+import { Route as ResetPasswordTokenRouteImport } from './routes/reset-password.$token'
 import { Route as SettingsGeneralRouteImport } from './routes/settings/general'
 import { Route as SettingsApiKeysRouteImport } from './routes/settings/api-keys'
 import { Route as AdminUsersRouteImport } from './routes/admin/users'
@@ -63,6 +65,16 @@ const UploadTokenRoute = UploadTokenRouteImport.update({
   path: '/upload/$token',
   getParentRoute: () => rootRouteImport,
 } as any)
+const ShareTokenRoute = ShareTokenRouteImport.update({
+  id: '/share/$token',
+  path: '/share/$token',
+  getParentRoute: () => rootRouteImport,
+} as any)
+const ResetPasswordTokenRoute = ResetPasswordTokenRouteImport.update({
+  id: '/reset-password/$token',
+  path: '/reset-password/$token',
+  getParentRoute: () => rootRouteImport,
+} as any)
 const SettingsGeneralRoute = SettingsGeneralRouteImport.update({
   id: '/settings/general',
   path: '/settings/general',
@@ -100,6 +112,8 @@ export interface FileRoutesByFullPath {
   '/admin/users': typeof AdminUsersRoute
   '/settings/api-keys': typeof SettingsApiKeysRoute
   '/settings/general': typeof SettingsGeneralRoute
+  '/reset-password/$token': typeof ResetPasswordTokenRoute
+  '/share/$token': typeof ShareTokenRoute
   '/upload/$token': typeof UploadTokenRoute
   '/admin/': typeof AdminIndexRoute
   '/settings/': typeof SettingsIndexRoute
@@ -115,6 +129,8 @@ export interface FileRoutesByTo {
   '/admin/users': typeof AdminUsersRoute
   '/settings/api-keys': typeof SettingsApiKeysRoute
   '/settings/general': typeof SettingsGeneralRoute
+  '/reset-password/$token': typeof ResetPasswordTokenRoute
+  '/share/$token': typeof ShareTokenRoute
   '/upload/$token': typeof UploadTokenRoute
   '/admin': typeof AdminIndexRoute
   '/settings': typeof SettingsIndexRoute
@@ -131,6 +147,8 @@ export interface FileRoutesById {
   '/admin/users': typeof AdminUsersRoute
   '/settings/api-keys': typeof SettingsApiKeysRoute
   '/settings/general': typeof SettingsGeneralRoute
+  '/reset-password/$token': typeof ResetPasswordTokenRoute
+  '/share/$token': typeof ShareTokenRoute
   '/upload/$token': typeof UploadTokenRoute
   '/admin/': typeof AdminIndexRoute
   '/settings/': typeof SettingsIndexRoute
@@ -148,6 +166,8 @@ export interface FileRouteTypes {
     | '/admin/users'
     | '/settings/api-keys'
     | '/settings/general'
+    | '/reset-password/$token'
+    | '/share/$token'
     | '/upload/$token'
     | '/admin/'
     | '/settings/'
@@ -163,6 +183,8 @@ export interface FileRouteTypes {
     | '/admin/users'
     | '/settings/api-keys'
     | '/settings/general'
+    | '/reset-password/$token'
+    | '/share/$token'
     | '/upload/$token'
     | '/admin'
     | '/settings'
@@ -178,6 +200,8 @@ export interface FileRouteTypes {
     | '/admin/users'
     | '/settings/api-keys'
     | '/settings/general'
+    | '/reset-password/$token'
+    | '/share/$token'
     | '/upload/$token'
     | '/admin/'
     | '/settings/'
@@ -194,6 +218,8 @@ export interface RootRouteChildren {
   AdminUsersRoute: typeof AdminUsersRoute
   SettingsApiKeysRoute: typeof SettingsApiKeysRoute
   SettingsGeneralRoute: typeof SettingsGeneralRoute
+  ResetPasswordTokenRoute: typeof ResetPasswordTokenRoute
+  ShareTokenRoute: typeof ShareTokenRoute
   UploadTokenRoute: typeof UploadTokenRoute
   AdminIndexRoute: typeof AdminIndexRoute
   SettingsIndexRoute: typeof SettingsIndexRoute
@@ -250,6 +276,13 @@ declare module '@tanstack/react-router' {
       preLoaderRoute: typeof AdminIndexRouteImport
       parentRoute: typeof rootRouteImport
     }
+    '/share/$token': {
+      id: '/share/$token'
+      path: '/share/$token'
+      fullPath: '/share/$token'
+      preLoaderRoute: typeof ShareTokenRouteImport
+      parentRoute: typeof rootRouteImport
+    }
     '/upload/$token': {
       id: '/upload/$token'
       path: '/upload/$token'
@@ -257,6 +290,13 @@ declare module '@tanstack/react-router' {
       preLoaderRoute: typeof UploadTokenRouteImport
       parentRoute: typeof rootRouteImport
     }
+    '/reset-password/$token': {
+      id: '/reset-password/$token'
+      path: '/reset-password/$token'
+      fullPath: '/reset-password/$token'
+      preLoaderRoute: typeof ResetPasswordTokenRouteImport
+      parentRoute: typeof rootRouteImport
+    }
     '/settings/general': {
       id: '/settings/general'
       path: '/settings/general'
@@ -306,6 +346,8 @@ const rootRouteChildren: RootRouteChildren = {
   AdminUsersRoute: AdminUsersRoute,
   SettingsApiKeysRoute: SettingsApiKeysRoute,
   SettingsGeneralRoute: SettingsGeneralRoute,
+  ResetPasswordTokenRoute: ResetPasswordTokenRoute,
+  ShareTokenRoute: ShareTokenRoute,
   UploadTokenRoute: UploadTokenRoute,
   AdminIndexRoute: AdminIndexRoute,
   SettingsIndexRoute: SettingsIndexRoute,
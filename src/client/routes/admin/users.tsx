@@ -16,7 +16,14 @@ import {
   Title,
   Tooltip,
 } from '@mantine/core'
-import { IconAlertCircle, IconEdit, IconKey, IconTrash, IconUserPlus } from '@tabler/icons-react'
+import {
+  IconAlertCircle,
+  IconEdit,
+  IconEye,
+  IconKey,
+  IconTrash,
+  IconUserPlus,
+} from '@tabler/icons-react'
 import { createFileRoute } from '@tanstack/react-router'
 import type { DataTableColumn } from 'mantine-datatable'
 import { DataTable } from 'mantine-datatable'
@@ -43,11 +50,13 @@ function UsersPage() {
     handleCreate,
     handlePasswordUpdate,
     handleDelete,
+    handleImpersonate,
 
     isAdminUser,
     canCreate,
     canEdit,
     canDelete,
+    canImpersonate,
 
     usernameFilter,
     setUsernameFilter,
@@ -125,7 +134,7 @@ function UsersPage() {
     {
       accessor: 'actions',
       title: '',
-      width: 100,
+      width: 120,
       render: (u) => {
         if (u.id === currentUser.id || u.username === 'admin') return null
         return (
@@ -148,6 +157,13 @@ function UsersPage() {
                 </Tooltip>
               </>
             )}
+            {canImpersonate && (
+              <Tooltip label="View as this user">
+                <ActionIcon variant="subtle" size="sm" onClick={() => handleImpersonate(u.id)}>
+                  <IconEye size={15} />
+                </ActionIcon>
+              </Tooltip>
+            )}
             {canDelete && (
               <Tooltip label="Delete user">
                 <ActionIcon
@@ -1,10 +1,11 @@
 import { api } from '@local/hooks/api'
 import { useAuth } from '@local/hooks/authContext'
 import type { LoginRequest, LoginResponse } from '@local/types/auth.ts'
-import { Button, Container, Paper, Stack, TextInput, Title } from '@mantine/core'
+import { Anchor, Button, Container, Paper, Stack, TextInput, Title } from '@mantine/core'
 import { useForm } from '@mantine/form'
 import { notifications } from '@mantine/notifications'
 import { createFileRoute, useNavigate } from '@tanstack/react-router'
+import axios from 'axios'
 import { useEffect } from 'react'
 
 export const Route = createFileRoute('/login')({
@@ -32,7 +33,19 @@ function LoginPage() {
       await api.post<LoginResponse>('/auth/login', values, { _silent: true })
       notifications.show({ title: 'Welcome back', message: 'Signed in', color: 'green' })
       navigate({ to: '/browse' })
-    } catch {
+    } catch (err) {
+      const data = axios.isAxiosError<{ code?: string; details?: { reset_token?: string } }>(err)
+        ? err.response?.data
+        : undefined
+      if (data?.code === 'password_expired' && data.details?.reset_token) {
+        notifications.show({
+          title: 'Password expired',
+          message: 'Choose a new password to continue',
+          color: 'yellow',
+        })
+        navigate({ to: '/reset-password/$token', params: { token: data.details.reset_token } })
+        return
+      }
       notifications.show({
         title: 'Login failed',
         message: 'Invalid username or password',
@@ -41,6 +54,32 @@ function LoginPage() {
     }
   }
 
+  async function handleForgotPassword() {
+    const login = form.values.username.trim()
+    if (!login) {
+      form.setFieldError('username', 'Enter your username or email')
+      return
+    }
+    const response = await fetch('/api/public/password-resets', {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ login }),
+    })
+    if (response.ok) {
+      notifications.show({
+        title: 'Check your email',
+        message: 'If the account has an email address, a reset link is on its way',
+        color: 'green',
+      })
+    } else {
+      notifications.show({
+        title: 'Password reset unavailable',
+        message: 'Ask an administrator for a reset link',
+        color: 'red',
+      })
+    }
+  }
+
   return (
     <Container size="xs" mt={100}>
       <Paper withBorder shadow="md" p="xl">
@@ -64,6 +103,9 @@ function LoginPage() {
             <Button type="submit" fullWidth mt="sm">
               Sign in
             </Button>
+            <Anchor component="button" type="button" size="sm" ta="center" onClick={handleForgotPassword}>
+              Forgot password?
+            </Anchor>
           </Stack>
         </form>
       </Paper>
@@ -0,0 +1,98 @@
+import { Alert, Button, Container, Paper, PasswordInput, Stack, Text, Title } from '@mantine/core'
+import { useForm } from '@mantine/form'
+import { createFileRoute, useNavigate } from '@tanstack/react-router'
+import { useEffect, useState } from 'react'
+
+interface PasswordResetStatus {
+  username: string
+  expires_at: string
+}
+
+export const Route = createFileRoute('/reset-password/$token')({
+  component: ResetPasswordPage,
+})
+
+function ResetPasswordPage() {
+  const { token } = Route.useParams()
+  const navigate = useNavigate()
+  const endpoint = `/api/public/password-resets/${encodeURIComponent(token)}`
+  const [status, setStatus] = useState<PasswordResetStatus | null>(null)
+  const [invalid, setInvalid] = useState(false)
+  const [error, setError] = useState<string | null>(null)
+  const [submitting, setSubmitting] = useState(false)
+
+  const form = useForm({
+    initialValues: { password: '', confirm: '' },
+    validate: {
+      password: (v) => (!v ? 'Required' : null),
+      confirm: (v, values) => (v !== values.password ? 'Passwords do not match' : null),
+    },
+  })
+
+  useEffect(() => {
+    let active = true
+    fetch(endpoint)
+      .then(async (response) => {
+        if (!response.ok) throw new Error()
+        const data = (await response.json()) as PasswordResetStatus
+        if (active) setStatus(data)
+      })
+      .catch(() => {
+        if (active) setInvalid(true)
+      })
+    return () => {
+      active = false
+    }
+  }, [endpoint])
+
+  async function handleSubmit(values: { password: string }) {
+    setSubmitting(true)
+    setError(null)
+    try {
+      const response = await fetch(endpoint, {
+        method: 'POST',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify({ password: values.password }),
+      })
+      if (!response.ok) {
+        const body = (await response.json().catch(() => null)) as { error?: string } | null
+        throw new Error(body?.error ?? 'Could not reset password')
+      }
+      navigate({ to: '/login' })
+    } catch (e) {
+      setError(e instanceof Error ? e.message : 'Could not reset password')
+    } finally {
+      setSubmitting(false)
+    }
+  }
+
+  return (
+    <Container size="xs" mt={100}>
+      <Paper withBorder shadow="md" p="xl">
+        <Title order={2} ta="center" mb="lg">
+          Reset password
+        </Title>
+        {invalid && (
+          <Alert color="red" title="Reset link unavailable">
+            This link is invalid, has already been used, or has expired.
+          </Alert>
+        )}
+        {status && (
+          <form onSubmit={form.onSubmit(handleSubmit)}>
+            <Stack gap="sm">
+              <Text size="sm" c="dimmed">
+                Choose a new password for {status.username}. You will be signed out everywhere.
+              </Text>
+              <PasswordInput label="New password" autoFocus {...form.getInputProps('password')} />
+              <PasswordInput label="Confirm password" {...form.getInputProps('confirm')} />
+              {error && <Alert color="red">{error}</Alert>}
+              <Button type="submit" fullWidth mt="sm" loading={submitting}>
+                Set password
+              </Button>
+            </Stack>
+          </form>
+        )}
+      </Paper>
+    </Container>
+  )
+}
@@ -0,0 +1,113 @@
+import { Alert, Container, Group, Paper, Stack, Text, Title, UnstyledButton } from '@mantine/core'
+import { IconPlayerPlay } from '@tabler/icons-react'
+import { createFileRoute } from '@tanstack/react-router'
+import { useEffect, useState } from 'react'
+
+interface PublicShareSong {
+  id: string
+  title: string
+  artist: string
+  album: string
+  duration_seconds: number | null
+}
+
+interface PublicShare {
+  description: string | null
+  username: string
+  expires_at: string | null
+  songs: PublicShareSong[]
+}
+
+export const Route = createFileRoute('/share/$token')({
+  component: PublicSharePage,
+})
+
+function formatDuration(seconds: number | null) {
+  if (seconds === null) return ''
+  const total = Math.round(seconds)
+  return `${Math.floor(total / 60)}:${String(total % 60).padStart(2, '0')}`
+}
+
+function PublicSharePage() {
+  const { token } = Route.useParams()
+  const endpoint = `/api/public/shares/${encodeURIComponent(token)}`
+  const [share, setShare] = useState<PublicShare | null>(null)
+  const [invalid, setInvalid] = useState(false)
+  const [current, setCurrent] = useState(0)
+
+  useEffect(() => {
+    let active = true
+    fetch(endpoint)
+      .then(async (response) => {
+        if (!response.ok) throw new Error()
+        const data = (await response.json()) as PublicShare
+        if (active) setShare(data)
+      })
+      .catch(() => {
+        if (active) setInvalid(true)
+      })
+    return () => {
+      active = false
+    }
+  }, [endpoint])
+
+  const song = share?.songs[current]
+
+  return (
+    <Container size="sm" mt={60}>
+      <Paper withBorder shadow="md" p="xl">
+        {invalid && (
+          <Alert color="red" title="Share unavailable">
+            This link is invalid or has expired.
+          </Alert>
+        )}
+        {share && (
+          <Stack gap="md">
+            <div>
+              <Title order={2}>{share.description ?? 'Shared music'}</Title>
+              <Text size="sm" c="dimmed">
+                Shared by {share.username}
+                {share.expires_at &&
+                  ` · available until ${new Date(share.expires_at).toLocaleString()}`}
+              </Text>
+            </div>
+            {song ? (
+              <audio
+                key={song.id}
+                controls
+                autoPlay={current > 0}
+                style={{ width: '100%' }}
+                src={`${endpoint}/songs/${song.id}`}
+                onEnded={() => setCurrent((index) => Math.min(index + 1, share.songs.length - 1))}
+              />
+            ) : (
+              <Text c="dimmed">No songs are available in this share anymore.</Text>
+            )}
+            <Stack gap={4}>
+              {share.songs.map((entry, index) => (
+                <UnstyledButton key={entry.id} onClick={() => setCurrent(index)} p={6}>
+                  <Group justify="space-between" wrap="nowrap">
+                    <Group gap="xs" wrap="nowrap">
+                      {index === current ? <IconPlayerPlay size={14} /> : <Text size="sm" w={14} />}
+                      <div>
+                        <Text size="sm" fw={index === current ? 600 : 400}>
+                          {entry.title}
+                        </Text>
+                        <Text size="xs" c="dimmed">
+                          {entry.artist} · {entry.album}
+                        </Text>
+                      </div>
+                    </Group>
+                    <Text size="xs" c="dimmed">
+                      {formatDuration(entry.duration_seconds)}
+                    </Text>
+                  </Group>
+                </UnstyledButton>
+              ))}
+            </Stack>
+          </Stack>
+        )}
+      </Paper>
+    </Container>
+  )
+}
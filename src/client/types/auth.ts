@@ -4,11 +4,21 @@ export interface User {
   password_hash?: string
   role_id: number
   role_name: string
+  email?: string | null
+  disabled_at?: string | null
+  disabled_reason?: string | null
+  expires_at?: string | null
   permissions?: Array<string>
+  impersonated_by?: Impersonator | null
   created_at: string
   updated_at: string
 }
 
+export interface Impersonator {
+  id: string
+  username: string
+}
+
 export interface LoginRequest {
   username: string
   password: string
@@ -18,6 +28,22 @@ export interface LoginResponse {
   user: User
 }
 
+export interface PermissionSource {
+  kind: 'role' | 'group'
+  name: string
+}
+
+export interface EffectivePermission {
+  name: string
+  sources: Array<PermissionSource>
+}
+
+export interface MeResponse {
+  user: User
+  permissions: Array<EffectivePermission>
+  impersonated_by: Impersonator | null
+}
+
 export interface RoleWithPermissions {
   id: number
   name: string
@@ -36,6 +62,27 @@ export interface Permission {
   group_name: string
 }
 
+export interface Group {
+  id: number
+  name: string
+  display_name: string
+  description: string | null
+  permissions: Array<string>
+  member_count: number
+  created_at: string
+  updated_at: string
+}
+
+export interface GroupMember {
+  user_id: string
+  username: string
+  added_at: string
+}
+
+export interface GroupWithMembers extends Group {
+  members: Array<GroupMember>
+}
+
 export interface UploadLink {
   id: string
   target_path: string
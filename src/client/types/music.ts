@@ -13,6 +13,10 @@ export interface MusicSong {
   size_bytes: number
   format: string | null
   bitrate_kbps: number | null
+  sample_rate: number | null
+  bit_depth: number | null
+  channel_count: number | null
+  codec: string | null
   has_cover_art: boolean
   in_library: boolean
   created_at: string
@@ -50,13 +54,27 @@ export interface MusicTagUpdate {
   year: number | null
   track_number: number | null
   disc_number: number | null
+  /** Plain lyrics; an empty string removes them. Omitted to leave lyrics untouched. */
+  lyrics?: string
+  /** Synced lyrics as LRC text; an empty string removes them. */
+  synced_lyrics?: string
 }
 
 export interface MusicTagFormValues
-  extends Omit<MusicTagUpdate, 'year' | 'track_number' | 'disc_number'> {
+  extends Omit<
+    MusicTagUpdate,
+    'year' | 'track_number' | 'disc_number' | 'lyrics' | 'synced_lyrics'
+  > {
   year: number | string
   track_number: number | string
   disc_number: number | string
+  lyrics: string
+  synced_lyrics: string
+}
+
+export interface MusicSongLyrics {
+  lyrics: string | null
+  synced_lyrics: string | null
 }
 
 export interface MusicSongMutation {
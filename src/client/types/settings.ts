@@ -1,7 +1,11 @@
 export interface ApiKey {
   id: string
   label: string | null
+  scopes: Array<string> | null
+  allowed_ips: Array<string> | null
+  expires_at: string | null
   last_used_at: string | null
+  last_used_ip: string | null
   created_at: string
 }
 
@@ -9,9 +13,19 @@ export interface CreatedApiKey {
   id: string
   label: string | null
   key: string
+  scopes: Array<string> | null
+  allowed_ips: Array<string> | null
+  expires_at: string | null
   created_at: string
 }
 
+export interface ApiKeyUsageEntry {
+  ip_address: string | null
+  method: string
+  path: string
+  used_at: string
+}
+
 export interface ApiKeyListResponse {
   keys: Array<ApiKey>
 }
@@ -27,6 +41,9 @@ export interface AdminApiKeyListResponse {
 
 export interface CreateApiKeyRequest {
   label: string | null
+  scopes?: Array<string> | null
+  allowed_ips?: Array<string> | null
+  expires_in_days?: number | null
 }
 
 export interface PasswordFormValues {
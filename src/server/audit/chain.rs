@@ -0,0 +1,166 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use rocket::serde::Serialize;
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::shared::sha256_hex;
+
+/// The columns of one audit row that its hash covers.
+#[derive(Debug, Clone, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct AuditRecord {
+    pub occurred_at: DateTime<Utc>,
+    pub actor_user_id: Option<Uuid>,
+    pub actor_username: Option<String>,
+    pub impersonator_username: Option<String>,
+    pub action: String,
+    pub target_type: String,
+    pub target_id: String,
+    pub ip_address: Option<String>,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+impl AuditRecord {
+    pub(crate) fn from_row(row: &tokio_postgres::Row) -> Self {
+        Self {
+            occurred_at: row.get("occurred_at"),
+            actor_user_id: row.get("actor_user_id"),
+            actor_username: row.get("actor_username"),
+            impersonator_username: row.get("impersonator_username"),
+            action: row.get("action"),
+            target_type: row.get("target_type"),
+            target_id: row.get("target_id"),
+            ip_address: row.get("ip_address"),
+            before: row.get("before"),
+            after: row.get("after"),
+        }
+    }
+
+    /// `sha256(prev_hash || canonical JSON of the row)`, hex encoded.
+    ///
+    /// Object keys are sorted so the hash survives the JSONB round trip, and the
+    /// timestamp is rendered at the microsecond precision PostgreSQL stores.
+    pub(crate) fn chain_hash(&self, prev_hash: Option<&str>) -> String {
+        let fields = serde_json::json!([
+            self.occurred_at
+                .to_rfc3339_opts(SecondsFormat::Micros, true),
+            self.actor_user_id,
+            self.actor_username,
+            self.impersonator_username,
+            self.action,
+            self.target_type,
+            self.target_id,
+            self.ip_address,
+            self.before,
+            self.after,
+        ]);
+        let mut input = prev_hash.unwrap_or_default().to_owned();
+        write_canonical(&fields, &mut input);
+        sha256_hex(&input)
+    }
+}
+
+fn write_canonical(value: &Value, out: &mut String) {
+    match value {
+        Value::Array(items) => {
+            out.push('[');
+            for (index, item) in items.iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                write_canonical(item, out);
+            }
+            out.push(']');
+        }
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by_key(|(key, _)| *key);
+            out.push('{');
+            for (index, (key, item)) in entries.into_iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                out.push_str(&Value::String(key.clone()).to_string());
+                out.push(':');
+                write_canonical(item, out);
+            }
+            out.push('}');
+        }
+        scalar => out.push_str(&scalar.to_string()),
+    }
+}
+
+/// Walk rows in id order and return the id of the first one whose link or hash is wrong.
+pub(crate) fn first_broken_link<'a>(
+    rows: impl IntoIterator<Item = (i64, &'a AuditRecord, Option<&'a str>, &'a str)>,
+) -> Option<i64> {
+    let mut expected_prev: Option<&str> = None;
+    for (id, record, prev_hash, hash) in rows {
+        if prev_hash != expected_prev || record.chain_hash(prev_hash) != hash {
+            return Some(id);
+        }
+        expected_prev = Some(hash);
+    }
+    None
+}
+
+/// Truncate to the microsecond precision PostgreSQL keeps, so a stored row rehashes identically.
+pub(crate) fn storage_precision(timestamp: DateTime<Utc>) -> DateTime<Utc> {
+    DateTime::from_timestamp_micros(timestamp.timestamp_micros()).unwrap_or(timestamp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(action: &str, after: Value) -> AuditRecord {
+        AuditRecord {
+            occurred_at: storage_precision(Utc::now()),
+            actor_user_id: Some(Uuid::nil()),
+            actor_username: Some("admin".to_owned()),
+            impersonator_username: None,
+            action: action.to_owned(),
+            target_type: "file".to_owned(),
+            target_id: "docs/report.pdf".to_owned(),
+            ip_address: Some("127.0.0.1".to_owned()),
+            before: None,
+            after: Some(after),
+        }
+    }
+
+    #[test]
+    fn hashes_ignore_object_key_order() {
+        let a = record("file.rename", serde_json::json!({"path": "a", "name": "b"}));
+        let mut b = a.clone();
+        b.after = Some(serde_json::from_str(r#"{"name":"b","path":"a"}"#).unwrap());
+
+        assert_eq!(a.chain_hash(None), b.chain_hash(None));
+        assert_ne!(a.chain_hash(None), a.chain_hash(Some("00")));
+    }
+
+    #[test]
+    fn edited_or_removed_rows_break_the_chain() {
+        let first = record("file.delete", serde_json::json!({}));
+        let second = record("file.rename", serde_json::json!({"name": "x"}));
+        let first_hash = first.chain_hash(None);
+        let second_hash = second.chain_hash(Some(&first_hash));
+
+        let intact = [
+            (1, &first, None, first_hash.as_str()),
+            (2, &second, Some(first_hash.as_str()), second_hash.as_str()),
+        ];
+        assert_eq!(first_broken_link(intact), None);
+
+        let mut edited = second.clone();
+        edited.target_id = "other.pdf".to_owned();
+        let tampered = [
+            (1, &first, None, first_hash.as_str()),
+            (2, &edited, Some(first_hash.as_str()), second_hash.as_str()),
+        ];
+        assert_eq!(first_broken_link(tampered), Some(2));
+
+        let gap = [(2, &second, Some(first_hash.as_str()), second_hash.as_str())];
+        assert_eq!(first_broken_link(gap), Some(2));
+    }
+}
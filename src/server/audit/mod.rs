@@ -0,0 +1,14 @@
+// Re-export shared infrastructure for submodules.
+pub(crate) use crate::auth::{AuthenticatedUser, require_permission};
+pub(crate) use crate::shared::*;
+pub(crate) use deadpool_postgres::Pool;
+pub(crate) use rocket::State;
+pub(crate) use rocket::serde::json::Json;
+
+// Submodules
+pub(crate) mod chain;
+pub(crate) mod record;
+pub(crate) mod routes;
+
+// Re-exports for parent (main.rs) and the handlers that write audit events
+pub(crate) use {record::*, routes::*};
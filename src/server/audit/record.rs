@@ -0,0 +1,195 @@
+use super::*;
+
+use super::chain::{AuditRecord, storage_precision};
+use chrono::Utc;
+use serde_json::Value;
+use std::net::IpAddr;
+use uuid::Uuid;
+
+const AUDIT_CHAIN_LOCK: i64 = 1_976_102;
+
+/// Something that happened to a target, with its state before and after where that applies.
+pub(crate) struct AuditEvent {
+    action: &'static str,
+    target_type: &'static str,
+    target_id: String,
+    before: Option<Value>,
+    after: Option<Value>,
+}
+
+impl AuditEvent {
+    pub(crate) fn new(
+        action: &'static str,
+        target_type: &'static str,
+        target_id: impl ToString,
+    ) -> Self {
+        Self {
+            action,
+            target_type,
+            target_id: target_id.to_string(),
+            before: None,
+            after: None,
+        }
+    }
+
+    pub(crate) fn before(mut self, before: Value) -> Self {
+        self.before = Some(before);
+        self
+    }
+
+    pub(crate) fn after(mut self, after: Value) -> Self {
+        self.after = Some(after);
+        self
+    }
+}
+
+/// Who performed an audited action; anonymous for public upload links.
+pub(crate) struct AuditActor {
+    user_id: Option<Uuid>,
+    username: Option<String>,
+    impersonator_username: Option<String>,
+    ip: Option<IpAddr>,
+}
+
+impl AuditActor {
+    pub(crate) fn anonymous(ip: Option<IpAddr>) -> Self {
+        Self {
+            user_id: None,
+            username: None,
+            impersonator_username: None,
+            ip,
+        }
+    }
+
+    /// A user identified without an [`AuthenticatedUser`], such as a Subsonic client.
+    pub(crate) fn user(user_id: Uuid, username: &str) -> Self {
+        Self {
+            user_id: Some(user_id),
+            username: Some(username.to_string()),
+            impersonator_username: None,
+            ip: None,
+        }
+    }
+
+    /// The same actor, seen from `ip`.
+    pub(crate) fn with_ip(mut self, ip: Option<IpAddr>) -> Self {
+        self.ip = ip;
+        self
+    }
+
+    /// An operator running the `blackfiles` command-line interface.
+    pub(crate) fn command_line() -> Self {
+        let os_user = std::env::var("USER").unwrap_or_else(|_| "unknown".to_string());
+        Self {
+            user_id: None,
+            username: Some(format!("cli:{os_user}")),
+            impersonator_username: None,
+            ip: None,
+        }
+    }
+}
+
+impl From<&AuthenticatedUser> for AuditActor {
+    fn from(user: &AuthenticatedUser) -> Self {
+        Self {
+            user_id: Some(user.id),
+            username: Some(user.username.clone()),
+            impersonator_username: user
+                .impersonator
+                .as_ref()
+                .map(|impersonator| impersonator.username.clone()),
+            ip: user.client_ip,
+        }
+    }
+}
+
+/// Append `event` to the audit chain.
+///
+/// The action has already happened by the time this runs, so a failure is logged
+/// rather than turned into an error response.
+pub(crate) async fn record_audit(pool: &Pool, actor: impl Into<AuditActor>, event: AuditEvent) {
+    let record = audit_record(actor.into(), event);
+    if let Err(error) = append(pool, &record).await {
+        eprintln!(
+            "Audit: failed to record {} on {} {}: {error}",
+            record.action, record.target_type, record.target_id
+        );
+    }
+}
+
+/// Append `event` inside `transaction`, so it commits or rolls back with the action it records.
+///
+/// The audit chain stays locked until the transaction ends.
+pub(crate) async fn record_audit_in(
+    transaction: &deadpool_postgres::Transaction<'_>,
+    actor: impl Into<AuditActor>,
+    event: AuditEvent,
+) -> Result<(), String> {
+    insert(transaction, &audit_record(actor.into(), event)).await
+}
+
+fn audit_record(actor: AuditActor, event: AuditEvent) -> AuditRecord {
+    AuditRecord {
+        occurred_at: storage_precision(Utc::now()),
+        actor_user_id: actor.user_id,
+        actor_username: actor.username,
+        impersonator_username: actor.impersonator_username,
+        action: event.action.to_owned(),
+        target_type: event.target_type.to_owned(),
+        target_id: event.target_id,
+        ip_address: actor.ip.map(|ip| ip.to_string()),
+        before: event.before,
+        after: event.after,
+    }
+}
+
+async fn append(pool: &Pool, record: &AuditRecord) -> Result<(), String> {
+    let mut client = pool.get().await.map_err(|e| e.to_string())?;
+    let transaction = client.transaction().await.map_err(|e| e.to_string())?;
+    insert(&transaction, record).await?;
+    transaction.commit().await.map_err(|e| e.to_string())
+}
+
+async fn insert(
+    transaction: &deadpool_postgres::Transaction<'_>,
+    record: &AuditRecord,
+) -> Result<(), String> {
+    // Serialize writers so every row links to the one before it.
+    transaction
+        .execute("SELECT pg_advisory_xact_lock($1)", &[&AUDIT_CHAIN_LOCK])
+        .await
+        .map_err(|e| e.to_string())?;
+    let prev_hash: Option<String> = transaction
+        .query_opt(
+            "SELECT hash FROM audit_events ORDER BY id DESC LIMIT 1",
+            &[],
+        )
+        .await
+        .map_err(|e| e.to_string())?
+        .map(|row| row.get("hash"));
+    let hash = record.chain_hash(prev_hash.as_deref());
+    transaction
+        .execute(
+            "INSERT INTO audit_events
+                 (occurred_at, actor_user_id, actor_username, impersonator_username, action,
+                  target_type, target_id, ip_address, before, after, prev_hash, hash)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)",
+            &[
+                &record.occurred_at,
+                &record.actor_user_id,
+                &record.actor_username,
+                &record.impersonator_username,
+                &record.action,
+                &record.target_type,
+                &record.target_id,
+                &record.ip_address,
+                &record.before,
+                &record.after,
+                &prev_hash,
+                &hash,
+            ],
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
@@ -0,0 +1,324 @@
+use super::*;
+
+use super::chain::{AuditRecord, first_broken_link};
+use chrono::{DateTime, Utc};
+use rocket::http::{ContentType, Header};
+use rocket::serde::Serialize;
+use tokio_postgres::types::ToSql;
+
+const EVENT_COLUMNS: &str = "id, occurred_at, actor_user_id, actor_username, impersonator_username,
+     action, target_type, target_id, ip_address, before, after, prev_hash, hash";
+
+#[derive(Debug, FromForm)]
+pub struct AuditQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+    /// Exact username of the acting user.
+    pub actor: Option<String>,
+    /// Exact action, or a prefix ending in `.` such as `file.`.
+    pub action: Option<String>,
+    pub target_type: Option<String>,
+    pub target_id: Option<String>,
+    /// RFC 3339 bounds on `occurred_at`: `since` inclusive, `until` exclusive.
+    pub since: Option<String>,
+    pub until: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct AuditEntry {
+    pub id: i64,
+    #[serde(flatten)]
+    pub record: AuditRecord,
+    pub prev_hash: Option<String>,
+    pub hash: String,
+}
+
+fn row_to_entry(row: &tokio_postgres::Row) -> AuditEntry {
+    AuditEntry {
+        id: row.get("id"),
+        record: AuditRecord::from_row(row),
+        prev_hash: row.get("prev_hash"),
+        hash: row.get("hash"),
+    }
+}
+
+fn parse_bound(value: Option<&String>, name: &str) -> Result<Option<DateTime<Utc>>, ApiError> {
+    value
+        .map(|value| {
+            DateTime::parse_from_rfc3339(value)
+                .map(|date| date.with_timezone(&Utc))
+                .map_err(|_| bad_request(&format!("{name} must be an RFC 3339 timestamp")))
+        })
+        .transpose()
+}
+
+/// Owned filter values, so the SQL parameters can borrow from one place.
+struct AuditFilters {
+    actor: Option<String>,
+    action: Option<String>,
+    action_is_prefix: bool,
+    target_type: Option<String>,
+    target_id: Option<String>,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+}
+
+impl AuditFilters {
+    fn from_query(query: &AuditQuery) -> Result<Self, ApiError> {
+        let action_is_prefix = query
+            .action
+            .as_ref()
+            .is_some_and(|action| action.ends_with('.'));
+        Ok(Self {
+            actor: query.actor.clone(),
+            action: query.action.as_ref().map(|action| {
+                if action_is_prefix {
+                    format!("{}%", action.replace('%', "\\%").replace('_', "\\_"))
+                } else {
+                    action.clone()
+                }
+            }),
+            action_is_prefix,
+            target_type: query.target_type.clone(),
+            target_id: query.target_id.clone(),
+            since: parse_bound(query.since.as_ref(), "since")?,
+            until: parse_bound(query.until.as_ref(), "until")?,
+        })
+    }
+
+    fn where_clause(&self) -> (String, Vec<&(dyn ToSql + Sync)>) {
+        let mut conditions = Vec::new();
+        let mut params = Vec::new();
+        if let Some(actor) = &self.actor {
+            add_condition(&mut conditions, &mut params, "actor_username = $?", actor);
+        }
+        if let Some(action) = &self.action {
+            let sql = if self.action_is_prefix {
+                "action LIKE $?"
+            } else {
+                "action = $?"
+            };
+            add_condition(&mut conditions, &mut params, sql, action);
+        }
+        if let Some(target_type) = &self.target_type {
+            add_condition(
+                &mut conditions,
+                &mut params,
+                "target_type = $?",
+                target_type,
+            );
+        }
+        if let Some(target_id) = &self.target_id {
+            add_condition(&mut conditions, &mut params, "target_id = $?", target_id);
+        }
+        if let Some(since) = &self.since {
+            add_condition(&mut conditions, &mut params, "occurred_at >= $?", since);
+        }
+        if let Some(until) = &self.until {
+            add_condition(&mut conditions, &mut params, "occurred_at < $?", until);
+        }
+        let clause = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+        (clause, params)
+    }
+}
+
+fn add_condition<'a>(
+    conditions: &mut Vec<String>,
+    params: &mut Vec<&'a (dyn ToSql + Sync)>,
+    sql: &str,
+    value: &'a (dyn ToSql + Sync),
+) {
+    params.push(value);
+    conditions.push(sql.replace("$?", &format!("${}", params.len())));
+}
+
+/// Quote a CSV field when it contains a separator, quote, or line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_owned()
+    }
+}
+
+fn csv_line(entry: &AuditEntry) -> String {
+    let record = &entry.record;
+    let json = |value: &Option<serde_json::Value>| {
+        value
+            .as_ref()
+            .map(|value| value.to_string())
+            .unwrap_or_default()
+    };
+    [
+        entry.id.to_string(),
+        record.occurred_at.to_rfc3339(),
+        record
+            .actor_user_id
+            .map(|id| id.to_string())
+            .unwrap_or_default(),
+        record.actor_username.clone().unwrap_or_default(),
+        record.impersonator_username.clone().unwrap_or_default(),
+        record.action.clone(),
+        record.target_type.clone(),
+        record.target_id.clone(),
+        record.ip_address.clone().unwrap_or_default(),
+        json(&record.before),
+        json(&record.after),
+        entry.prev_hash.clone().unwrap_or_default(),
+        entry.hash.clone(),
+    ]
+    .iter()
+    .map(|field| csv_field(field))
+    .collect::<Vec<_>>()
+    .join(",")
+}
+
+const CSV_HEADER: &str = "id,occurred_at,actor_user_id,actor_username,impersonator_username,action,target_type,target_id,ip_address,before,after,prev_hash,hash";
+
+#[derive(Responder)]
+pub(crate) struct AuditExport {
+    body: String,
+    content_type: ContentType,
+    disposition: Header<'static>,
+}
+
+/// GET /api/admin/audit - Audit events, newest first
+#[get("/admin/audit?<query..>")]
+pub(crate) async fn list_audit_events(
+    pool: &State<Pool>,
+    user: AuthenticatedUser,
+    query: AuditQuery,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    require_permission(pool, &user, "view_audit_log").await?;
+    let filters = AuditFilters::from_query(&query)?;
+    let (where_clause, mut params) = filters.where_clause();
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    let client = get_client(pool).await?;
+    let total: i64 = client
+        .query_one(
+            &format!("SELECT COUNT(*) FROM audit_events {where_clause}"),
+            &params,
+        )
+        .await
+        .map_err(db_error)?
+        .get(0);
+
+    let sql = format!(
+        "SELECT {EVENT_COLUMNS} FROM audit_events {where_clause}
+         ORDER BY id DESC LIMIT ${} OFFSET ${}",
+        params.len() + 1,
+        params.len() + 2,
+    );
+    params.push(&limit);
+    params.push(&offset);
+    let rows = client.query(&sql, &params).await.map_err(db_error)?;
+    let events: Vec<AuditEntry> = rows.iter().map(row_to_entry).collect();
+
+    Ok(Json(serde_json::json!({"data": events, "total": total})))
+}
+
+/// GET /api/admin/audit/export?format=csv|jsonl - Every matching event, oldest first
+#[get("/admin/audit/export?<format>&<query..>")]
+pub(crate) async fn export_audit_events(
+    pool: &State<Pool>,
+    user: AuthenticatedUser,
+    format: &str,
+    query: AuditQuery,
+) -> Result<AuditExport, ApiError> {
+    require_permission(pool, &user, "view_audit_log").await?;
+    let filters = AuditFilters::from_query(&query)?;
+    let (where_clause, params) = filters.where_clause();
+
+    let client = get_client(pool).await?;
+    let rows = client
+        .query(
+            &format!("SELECT {EVENT_COLUMNS} FROM audit_events {where_clause} ORDER BY id"),
+            &params,
+        )
+        .await
+        .map_err(db_error)?;
+    let entries = rows.iter().map(row_to_entry);
+
+    let (body, content_type, extension) = match format {
+        "csv" => {
+            let mut body = format!("{CSV_HEADER}\n");
+            for entry in entries {
+                body.push_str(&csv_line(&entry));
+                body.push('\n');
+            }
+            (body, ContentType::CSV, "csv")
+        }
+        "jsonl" => {
+            let mut body = String::new();
+            for entry in entries {
+                body.push_str(&serde_json::to_string(&entry).map_err(|_| server_error())?);
+                body.push('\n');
+            }
+            (body, ContentType::new("application", "x-ndjson"), "jsonl")
+        }
+        _ => return Err(bad_request("format must be csv or jsonl")),
+    };
+
+    Ok(AuditExport {
+        body,
+        content_type,
+        disposition: Header::new(
+            "Content-Disposition",
+            format!(
+                "attachment; filename=\"audit-{}.{extension}\"",
+                Utc::now().format("%Y%m%d-%H%M%S")
+            ),
+        ),
+    })
+}
+
+/// GET /api/admin/audit/verify - Recompute the hash chain and report the first broken row
+#[get("/admin/audit/verify")]
+pub(crate) async fn verify_audit_chain(
+    pool: &State<Pool>,
+    user: AuthenticatedUser,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    require_permission(pool, &user, "view_audit_log").await?;
+    let client = get_client(pool).await?;
+    let rows = client
+        .query(
+            &format!("SELECT {EVENT_COLUMNS} FROM audit_events ORDER BY id"),
+            &[],
+        )
+        .await
+        .map_err(db_error)?;
+    let entries: Vec<AuditEntry> = rows.iter().map(row_to_entry).collect();
+    let first_invalid_id = first_broken_link(entries.iter().map(|entry| {
+        (
+            entry.id,
+            &entry.record,
+            entry.prev_hash.as_deref(),
+            entry.hash.as_str(),
+        )
+    }));
+
+    Ok(Json(serde_json::json!({
+        "valid": first_invalid_id.is_none(),
+        "checked": entries.len(),
+        "first_invalid_id": first_invalid_id,
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_fields_are_quoted_only_when_needed() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field(r#"{"k":"v"}"#), r#""{""k"":""v""}""#);
+    }
+}
@@ -1,11 +1,23 @@
-use deadpool_postgres::Pool;
+use deadpool_postgres::{GenericClient, Pool};
 use rocket::State;
 use rocket::http::Status;
 use rocket::serde::{Deserialize, Serialize, json::Json};
+use std::net::IpAddr;
 use uuid::Uuid;
 
 use super::guards::AuthenticatedUser;
-use crate::shared::{db_error, forbidden, get_client, not_found, random_hex, sha256_hex};
+use super::helpers::{ACTIVE_USER_SQL, require_session, role_permissions};
+use crate::audit::{AuditEvent, record_audit};
+use crate::shared::{
+    ApiError, bad_request, db_error, forbidden, get_client, not_found, random_hex, sha256_hex,
+};
+
+/// Prefix of keys accepted as `Authorization: Bearer` credentials on `/api`.
+pub(crate) const API_KEY_PREFIX: &str = "bf_";
+const MAX_KEY_LIFETIME_DAYS: i64 = 3650;
+const USAGE_HISTORY_LIMIT: i64 = 100;
+/// Repeated use of a key from the same address is recorded at most this often.
+const USAGE_RECORD_INTERVAL_SECONDS: i64 = 60;
 
 // ── Response types ──
 
@@ -14,7 +26,11 @@ use crate::shared::{db_error, forbidden, get_client, not_found, random_hex, sha2
 pub struct ApiKeyResponse {
     pub id: Uuid,
     pub label: Option<String>,
+    pub scopes: Option<Vec<String>>,
+    pub allowed_ips: Option<Vec<String>>,
+    pub expires_at: Option<String>,
     pub last_used_at: Option<String>,
+    pub last_used_ip: Option<String>,
     pub created_at: String,
 }
 
@@ -24,6 +40,9 @@ pub struct ApiKeyCreatedResponse {
     pub id: Uuid,
     pub label: Option<String>,
     pub key: String,
+    pub scopes: Option<Vec<String>>,
+    pub allowed_ips: Option<Vec<String>>,
+    pub expires_at: Option<String>,
     pub created_at: String,
 }
 
@@ -39,9 +58,8 @@ pub struct AdminApiKeyResponse {
     pub id: Uuid,
     pub user_id: Uuid,
     pub username: String,
-    pub label: Option<String>,
-    pub last_used_at: Option<String>,
-    pub created_at: String,
+    #[serde(flatten)]
+    pub key: ApiKeyResponse,
 }
 
 #[derive(Debug, Serialize)]
@@ -50,43 +68,296 @@ pub struct AdminApiKeyListResponse {
     pub keys: Vec<AdminApiKeyResponse>,
 }
 
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ApiKeyUsageEntry {
+    pub ip_address: Option<String>,
+    pub method: String,
+    pub path: String,
+    pub used_at: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ApiKeyUsageResponse {
+    pub entries: Vec<ApiKeyUsageEntry>,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(crate = "rocket::serde")]
 pub struct CreateApiKeyRequest {
     pub label: Option<String>,
+    /// Permissions the key may use on `/api`. Omit for a Subsonic-only key.
+    pub scopes: Option<Vec<String>>,
+    /// IP addresses or CIDR ranges the key may be used from. Omit to allow any.
+    pub allowed_ips: Option<Vec<String>>,
+    pub expires_in_days: Option<i64>,
 }
 
-fn key_timestamps(row: &tokio_postgres::Row) -> (Option<String>, String) {
-    let last_used_at = row
-        .try_get::<_, chrono::DateTime<chrono::Utc>>("last_used_at")
-        .ok()
-        .map(|date| date.to_rfc3339());
-    let created_at = row
-        .get::<_, chrono::DateTime<chrono::Utc>>("created_at")
-        .to_rfc3339();
-    (last_used_at, created_at)
+const KEY_COLUMNS: &str = "ak.id, ak.label, ak.scopes, ak.allowed_ips, ak.expires_at,
+     ak.last_used_at, ak.last_used_ip, ak.created_at";
+
+fn rfc3339(row: &tokio_postgres::Row, column: &str) -> Option<String> {
+    row.get::<_, Option<chrono::DateTime<chrono::Utc>>>(column)
+        .map(|date| date.to_rfc3339())
 }
 
 fn row_to_key(row: &tokio_postgres::Row) -> ApiKeyResponse {
-    let (last_used_at, created_at) = key_timestamps(row);
     ApiKeyResponse {
         id: row.get("id"),
         label: row.get("label"),
-        last_used_at,
-        created_at,
+        scopes: row.get("scopes"),
+        allowed_ips: row.get("allowed_ips"),
+        expires_at: rfc3339(row, "expires_at"),
+        last_used_at: rfc3339(row, "last_used_at"),
+        last_used_ip: row.get("last_used_ip"),
+        created_at: rfc3339(row, "created_at").unwrap_or_default(),
     }
 }
 
 fn row_to_admin_key(row: &tokio_postgres::Row) -> AdminApiKeyResponse {
-    let (last_used_at, created_at) = key_timestamps(row);
     AdminApiKeyResponse {
+        user_id: row.get("user_id"),
+        username: row.get("username"),
         id: row.get("id"),
+        key: row_to_key(row),
+    }
+}
+
+// ── IP allow-lists ──
+
+/// Parse `203.0.113.7`, `203.0.113.0/24` or an IPv6 equivalent into address and prefix.
+fn parse_network(value: &str) -> Option<(IpAddr, u8)> {
+    let (address, prefix) = match value.trim().split_once('/') {
+        Some((address, prefix)) => (address, Some(prefix)),
+        None => (value.trim(), None),
+    };
+    let address: IpAddr = address.parse().ok()?;
+    let max_prefix = if address.is_ipv4() { 32 } else { 128 };
+    let prefix = match prefix {
+        Some(prefix) => prefix.parse::<u8>().ok().filter(|p| *p <= max_prefix)?,
+        None => max_prefix,
+    };
+    Some((address, prefix))
+}
+
+fn network_contains((network, prefix): (IpAddr, u8), ip: IpAddr) -> bool {
+    match (network, ip.to_canonical()) {
+        (IpAddr::V4(network), IpAddr::V4(ip)) => {
+            let mask = u32::MAX.checked_shl(32 - u32::from(prefix)).unwrap_or(0);
+            u32::from(network) & mask == u32::from(ip) & mask
+        }
+        (IpAddr::V6(network), IpAddr::V6(ip)) => {
+            let mask = u128::MAX.checked_shl(128 - u32::from(prefix)).unwrap_or(0);
+            u128::from(network) & mask == u128::from(ip) & mask
+        }
+        _ => false,
+    }
+}
+
+pub(crate) fn ip_allowed(allowed: &[String], ip: Option<IpAddr>) -> bool {
+    let Some(ip) = ip else {
+        return false;
+    };
+    allowed
+        .iter()
+        .filter_map(|entry| parse_network(entry))
+        .any(|network| network_contains(network, ip))
+}
+
+// ── Authentication ──
+
+pub(crate) enum ApiKeyRejection {
+    Invalid,
+    Expired,
+    AccountDisabled,
+    IpNotAllowed,
+    Database,
+}
+
+pub(crate) struct ApiKeyOwner {
+    pub(crate) user_id: Uuid,
+    pub(crate) username: String,
+    pub(crate) role: String,
+    pub(crate) scopes: Option<Vec<String>>,
+}
+
+/// Whether a request from `ip` belongs in a key's usage trail; bursts from one address are
+/// recorded once per [`USAGE_RECORD_INTERVAL_SECONDS`].
+fn usage_due(
+    last_used_at: Option<chrono::DateTime<chrono::Utc>>,
+    last_used_ip: Option<&str>,
+    ip: Option<&str>,
+    now: chrono::DateTime<chrono::Utc>,
+) -> bool {
+    last_used_ip != ip
+        || last_used_at.is_none_or(|used_at| {
+            now - used_at >= chrono::Duration::seconds(USAGE_RECORD_INTERVAL_SECONDS)
+        })
+}
+
+/// Resolve `key`, enforce its expiry and IP allow-list, and record the request in its usage trail.
+pub(crate) async fn authenticate_api_key(
+    client: &impl GenericClient,
+    key: &str,
+    ip: Option<IpAddr>,
+    method: &str,
+    path: &str,
+) -> Result<ApiKeyOwner, ApiKeyRejection> {
+    let key_hash = sha256_hex(key);
+    let row = client
+        .query_opt(
+            &format!(
+                "SELECT ak.id, ak.scopes, ak.allowed_ips, ak.expires_at,
+                        ak.last_used_at, ak.last_used_ip, u.id AS user_id, u.username, r.name AS role_name,
+                        ({ACTIVE_USER_SQL}) AS active
+                 FROM api_keys ak
+                 JOIN users u ON ak.user_id = u.id
+                 JOIN roles r ON u.role_id = r.id
+                 WHERE ak.key_hash = $1"
+            ),
+            &[&key_hash],
+        )
+        .await
+        .map_err(|_| ApiKeyRejection::Database)?
+        .ok_or(ApiKeyRejection::Invalid)?;
+
+    if !row.get::<_, bool>("active") {
+        return Err(ApiKeyRejection::AccountDisabled);
+    }
+    let expires_at: Option<chrono::DateTime<chrono::Utc>> = row.get("expires_at");
+    if expires_at.is_some_and(|expires_at| expires_at <= chrono::Utc::now()) {
+        return Err(ApiKeyRejection::Expired);
+    }
+    let allowed_ips: Option<Vec<String>> = row.get("allowed_ips");
+    if let Some(allowed_ips) = allowed_ips
+        && !ip_allowed(&allowed_ips, ip)
+    {
+        return Err(ApiKeyRejection::IpNotAllowed);
+    }
+
+    let key_id: Uuid = row.get("id");
+    let ip = ip.map(|ip| ip.to_canonical().to_string());
+    let due = usage_due(
+        row.get("last_used_at"),
+        row.get("last_used_ip"),
+        ip.as_deref(),
+        chrono::Utc::now(),
+    );
+    let recorded = async {
+        if !due {
+            return Ok(0);
+        }
+        client
+            .execute(
+                "UPDATE api_keys SET last_used_at = NOW(), last_used_ip = $2 WHERE id = $1",
+                &[&key_id, &ip],
+            )
+            .await?;
+        client
+            .execute(
+                "INSERT INTO api_key_usage (api_key_id, ip_address, method, path)
+                 VALUES ($1, $2, $3, $4)",
+                &[&key_id, &ip, &method, &path],
+            )
+            .await?;
+        client
+            .execute(
+                "DELETE FROM api_key_usage WHERE api_key_id = $1 AND id NOT IN (
+                     SELECT id FROM api_key_usage WHERE api_key_id = $1
+                     ORDER BY id DESC LIMIT $2
+                 )",
+                &[&key_id, &USAGE_HISTORY_LIMIT],
+            )
+            .await
+    }
+    .await;
+    if let Err(error) = recorded {
+        eprintln!("Failed to record API key usage: {error}");
+    }
+
+    Ok(ApiKeyOwner {
         user_id: row.get("user_id"),
         username: row.get("username"),
-        label: row.get("label"),
-        last_used_at,
-        created_at,
+        role: row.get("role_name"),
+        scopes: row.get("scopes"),
+    })
+}
+
+// ── Validation ──
+
+async fn validate_scopes(
+    client: &impl GenericClient,
+    user_id: Uuid,
+    scopes: &[String],
+) -> Result<Vec<String>, ApiError> {
+    let owner = client
+        .query_one(
+            "SELECT r.id, r.name FROM users u JOIN roles r ON u.role_id = r.id WHERE u.id = $1",
+            &[&user_id],
+        )
+        .await
+        .map_err(db_error)?;
+    let granted: Vec<String> = if owner.get::<_, String>("name") == "admin" {
+        client
+            .query("SELECT name FROM permissions", &[])
+            .await
+            .map_err(db_error)?
+            .iter()
+            .map(|row| row.get("name"))
+            .collect()
+    } else {
+        role_permissions(client, owner.get("id"))
+            .await
+            .map_err(db_error)?
+    };
+
+    let mut scopes: Vec<String> = scopes.iter().map(|scope| scope.trim().to_owned()).collect();
+    scopes.sort();
+    scopes.dedup();
+    if let Some(scope) = scopes.iter().find(|scope| !granted.contains(scope)) {
+        return Err(bad_request(&format!(
+            "Scope '{scope}' is not granted to your role"
+        )));
     }
+    Ok(scopes)
+}
+
+fn validate_allowed_ips(allowed_ips: &[String]) -> Result<Vec<String>, ApiError> {
+    allowed_ips
+        .iter()
+        .map(|entry| match parse_network(entry) {
+            Some((address, prefix)) => Ok(format!("{address}/{prefix}")),
+            None => Err(bad_request(&format!(
+                "'{entry}' is not an IP address or CIDR range"
+            ))),
+        })
+        .collect()
+}
+
+async fn key_usage(
+    client: &impl GenericClient,
+    key_id: Uuid,
+) -> Result<ApiKeyUsageResponse, ApiError> {
+    let rows = client
+        .query(
+            "SELECT ip_address, method, path, used_at FROM api_key_usage
+             WHERE api_key_id = $1 ORDER BY id DESC",
+            &[&key_id],
+        )
+        .await
+        .map_err(db_error)?;
+    Ok(ApiKeyUsageResponse {
+        entries: rows
+            .iter()
+            .map(|row| ApiKeyUsageEntry {
+                ip_address: row.get("ip_address"),
+                method: row.get("method"),
+                path: row.get("path"),
+                used_at: rfc3339(row, "used_at").unwrap_or_default(),
+            })
+            .collect(),
+    })
 }
 
 // ── User endpoints ──
@@ -96,11 +367,18 @@ pub(crate) async fn list_my_api_keys(
     pool: &State<Pool>,
     user: AuthenticatedUser,
 ) -> Result<Json<ApiKeyListResponse>, (Status, Json<serde_json::Value>)> {
+    require_session(&user)?;
     let client = get_client(pool).await?;
-    let rows = client.query(
-        "SELECT id, label, last_used_at, created_at FROM api_keys WHERE user_id = $1 ORDER BY created_at DESC",
-        &[&user.id],
-    ).await.map_err(db_error)?;
+    let rows = client
+        .query(
+            &format!(
+                "SELECT {KEY_COLUMNS} FROM api_keys ak
+                 WHERE ak.user_id = $1 ORDER BY ak.created_at DESC"
+            ),
+            &[&user.id],
+        )
+        .await
+        .map_err(db_error)?;
     let keys: Vec<ApiKeyResponse> = rows.iter().map(row_to_key).collect();
     Ok(Json(ApiKeyListResponse { keys }))
 }
@@ -111,9 +389,32 @@ pub(crate) async fn create_api_key(
     user: AuthenticatedUser,
     req: Json<CreateApiKeyRequest>,
 ) -> Result<Json<ApiKeyCreatedResponse>, (Status, Json<serde_json::Value>)> {
+    require_session(&user)?;
     let client = get_client(pool).await?;
+
+    let scopes = match &req.scopes {
+        Some(scopes) => Some(validate_scopes(&client, user.id, scopes).await?),
+        None => None,
+    };
+    let allowed_ips = req
+        .allowed_ips
+        .as_deref()
+        .map(validate_allowed_ips)
+        .transpose()?;
+    let expires_at = match req.expires_in_days {
+        Some(days @ 1..=MAX_KEY_LIFETIME_DAYS) => {
+            Some(chrono::Utc::now() + chrono::Duration::days(days))
+        }
+        Some(_) => {
+            return Err(bad_request(&format!(
+                "API keys must expire within 1 to {MAX_KEY_LIFETIME_DAYS} days"
+            )));
+        }
+        None => None,
+    };
+
     let (key, key_hash) = loop {
-        let key = random_hex::<24>();
+        let key = format!("{API_KEY_PREFIX}{}", random_hex::<24>());
         let hash = sha256_hex(&key);
         if client
             .query_opt("SELECT 1 FROM api_keys WHERE key_hash = $1", &[&hash])
@@ -127,8 +428,17 @@ pub(crate) async fn create_api_key(
     let id = Uuid::new_v4();
     client
         .execute(
-            "INSERT INTO api_keys (id, user_id, key_hash, label) VALUES ($1,$2,$3,$4)",
-            &[&id, &user.id, &key_hash, &req.label],
+            "INSERT INTO api_keys (id, user_id, key_hash, label, scopes, allowed_ips, expires_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7)",
+            &[
+                &id,
+                &user.id,
+                &key_hash,
+                &req.label,
+                &scopes,
+                &allowed_ips,
+                &expires_at,
+            ],
         )
         .await
         .map_err(db_error)?;
@@ -136,35 +446,69 @@ pub(crate) async fn create_api_key(
         .query_one("SELECT created_at FROM api_keys WHERE id = $1", &[&id])
         .await
         .map_err(db_error)?;
+    record_audit(
+        pool,
+        &user,
+        AuditEvent::new("api_key.create", "api_key", id).after(serde_json::json!({
+            "label": req.label,
+            "scopes": scopes,
+            "allowed_ips": allowed_ips,
+            "expires_at": expires_at,
+        })),
+    )
+    .await;
     Ok(Json(ApiKeyCreatedResponse {
         id,
         label: req.label.clone(),
         key,
-        created_at: row
-            .get::<_, chrono::DateTime<chrono::Utc>>("created_at")
-            .to_rfc3339(),
+        scopes,
+        allowed_ips,
+        expires_at: expires_at.map(|date| date.to_rfc3339()),
+        created_at: rfc3339(&row, "created_at").unwrap_or_default(),
     }))
 }
 
-#[delete("/music/api-keys/<id>")]
-pub(crate) async fn revoke_api_key(
+#[get("/music/api-keys/<id>/usage")]
+pub(crate) async fn get_my_api_key_usage(
     pool: &State<Pool>,
     user: AuthenticatedUser,
     id: &str,
-) -> Result<Json<serde_json::Value>, (Status, Json<serde_json::Value>)> {
+) -> Result<Json<ApiKeyUsageResponse>, (Status, Json<serde_json::Value>)> {
+    require_session(&user)?;
     let key_id = Uuid::parse_str(id).map_err(|_| not_found("Invalid key ID"))?;
     let client = get_client(pool).await?;
     if client
-        .execute(
-            "DELETE FROM api_keys WHERE id = $1 AND user_id = $2",
+        .query_opt(
+            "SELECT 1 FROM api_keys WHERE id = $1 AND user_id = $2",
             &[&key_id, &user.id],
         )
         .await
         .map_err(db_error)?
-        == 0
+        .is_none()
     {
         return Err(not_found("API key not found"));
     }
+    Ok(Json(key_usage(&client, key_id).await?))
+}
+
+#[delete("/music/api-keys/<id>")]
+pub(crate) async fn revoke_api_key(
+    pool: &State<Pool>,
+    user: AuthenticatedUser,
+    id: &str,
+) -> Result<Json<serde_json::Value>, (Status, Json<serde_json::Value>)> {
+    require_session(&user)?;
+    let key_id = Uuid::parse_str(id).map_err(|_| not_found("Invalid key ID"))?;
+    let client = get_client(pool).await?;
+    let revoked = client
+        .query_opt(
+            "DELETE FROM api_keys WHERE id = $1 AND user_id = $2 RETURNING user_id, label",
+            &[&key_id, &user.id],
+        )
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| not_found("API key not found"))?;
+    record_audit(pool, &user, revoked_key_event(key_id, &revoked)).await;
     Ok(Json(serde_json::json!({"message": "API key revoked"})))
 }
 
@@ -175,36 +519,122 @@ pub(crate) async fn list_all_api_keys(
     pool: &State<Pool>,
     user: AuthenticatedUser,
 ) -> Result<Json<AdminApiKeyListResponse>, (Status, Json<serde_json::Value>)> {
+    require_session(&user)?;
     if user.role != "admin" {
         return Err(forbidden());
     }
     let client = get_client(pool).await?;
-    let rows = client.query(
-        "SELECT ak.id, ak.user_id, u.username, ak.label, ak.last_used_at, ak.created_at FROM api_keys ak JOIN users u ON ak.user_id = u.id ORDER BY ak.created_at DESC",
-        &[],
-    ).await.map_err(db_error)?;
+    let rows = client
+        .query(
+            &format!(
+                "SELECT {KEY_COLUMNS}, ak.user_id, u.username
+                 FROM api_keys ak JOIN users u ON ak.user_id = u.id
+                 ORDER BY ak.created_at DESC"
+            ),
+            &[],
+        )
+        .await
+        .map_err(db_error)?;
     let keys: Vec<AdminApiKeyResponse> = rows.iter().map(row_to_admin_key).collect();
     Ok(Json(AdminApiKeyListResponse { keys }))
 }
 
-#[delete("/admin/api-keys/<id>")]
-pub(crate) async fn admin_revoke_api_key(
+#[get("/admin/api-keys/<id>/usage")]
+pub(crate) async fn get_api_key_usage(
     pool: &State<Pool>,
     user: AuthenticatedUser,
     id: &str,
-) -> Result<Json<serde_json::Value>, (Status, Json<serde_json::Value>)> {
+) -> Result<Json<ApiKeyUsageResponse>, (Status, Json<serde_json::Value>)> {
+    require_session(&user)?;
     if user.role != "admin" {
         return Err(forbidden());
     }
     let key_id = Uuid::parse_str(id).map_err(|_| not_found("Invalid key ID"))?;
     let client = get_client(pool).await?;
     if client
-        .execute("DELETE FROM api_keys WHERE id = $1", &[&key_id])
+        .query_opt("SELECT 1 FROM api_keys WHERE id = $1", &[&key_id])
         .await
         .map_err(db_error)?
-        == 0
+        .is_none()
     {
         return Err(not_found("API key not found"));
     }
+    Ok(Json(key_usage(&client, key_id).await?))
+}
+
+#[delete("/admin/api-keys/<id>")]
+pub(crate) async fn admin_revoke_api_key(
+    pool: &State<Pool>,
+    user: AuthenticatedUser,
+    id: &str,
+) -> Result<Json<serde_json::Value>, (Status, Json<serde_json::Value>)> {
+    require_session(&user)?;
+    if user.role != "admin" {
+        return Err(forbidden());
+    }
+    let key_id = Uuid::parse_str(id).map_err(|_| not_found("Invalid key ID"))?;
+    let client = get_client(pool).await?;
+    let revoked = client
+        .query_opt(
+            "DELETE FROM api_keys WHERE id = $1 RETURNING user_id, label",
+            &[&key_id],
+        )
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| not_found("API key not found"))?;
+    record_audit(pool, &user, revoked_key_event(key_id, &revoked)).await;
     Ok(Json(serde_json::json!({"message": "API key revoked"})))
 }
+
+pub(crate) fn revoked_key_event(key_id: Uuid, row: &tokio_postgres::Row) -> AuditEvent {
+    AuditEvent::new("api_key.revoke", "api_key", key_id).before(serde_json::json!({
+        "user_id": row.get::<_, Uuid>("user_id"),
+        "label": row.get::<_, Option<String>>("label"),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(value: &str) -> Option<IpAddr> {
+        value.parse().ok()
+    }
+
+    #[test]
+    fn allow_lists_match_single_addresses_and_cidr_ranges() {
+        let allowed = vec!["203.0.113.7".to_owned(), "10.0.0.0/8".to_owned()];
+        assert!(ip_allowed(&allowed, ip("203.0.113.7")));
+        assert!(ip_allowed(&allowed, ip("10.42.0.1")));
+        assert!(ip_allowed(&allowed, ip("::ffff:10.1.2.3")));
+        assert!(!ip_allowed(&allowed, ip("203.0.113.8")));
+        assert!(!ip_allowed(&allowed, None));
+
+        let allowed = vec!["2001:db8::/32".to_owned()];
+        assert!(ip_allowed(&allowed, ip("2001:db8:1::1")));
+        assert!(!ip_allowed(&allowed, ip("2001:db9::1")));
+    }
+
+    #[test]
+    fn repeated_use_from_one_address_is_recorded_once_a_minute() {
+        let now = chrono::Utc::now();
+        let recent = Some(now - chrono::Duration::seconds(10));
+        let stale = Some(now - chrono::Duration::seconds(90));
+        let here = Some("192.0.2.1");
+
+        assert!(usage_due(None, None, here, now));
+        assert!(!usage_due(recent, here, here, now));
+        assert!(usage_due(stale, here, here, now));
+        assert!(usage_due(recent, Some("192.0.2.2"), here, now));
+    }
+
+    #[test]
+    fn allow_list_entries_are_validated_and_normalized() {
+        assert_eq!(
+            validate_allowed_ips(&["192.0.2.1".to_owned(), "0.0.0.0/0".to_owned()]).unwrap(),
+            vec!["192.0.2.1/32".to_owned(), "0.0.0.0/0".to_owned()]
+        );
+        assert!(validate_allowed_ips(&["192.0.2.0/33".to_owned()]).is_err());
+        assert!(validate_allowed_ips(&["example.com".to_owned()]).is_err());
+    }
+}
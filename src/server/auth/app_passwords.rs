@@ -0,0 +1,189 @@
+use deadpool_postgres::{GenericClient, Pool};
+use md5::{Digest, Md5};
+use rocket::State;
+use rocket::http::Status;
+use rocket::serde::{Deserialize, Serialize, json::Json};
+use uuid::Uuid;
+
+use super::guards::AuthenticatedUser;
+use super::helpers::require_session;
+use crate::shared::{
+    AppSecretKey, db_error, get_client, not_found, open_secret, random_hex, seal_secret,
+    server_error,
+};
+
+// ── Response types ──
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct AppPasswordResponse {
+    pub id: Uuid,
+    pub label: Option<String>,
+    pub last_used_at: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct AppPasswordCreatedResponse {
+    pub id: Uuid,
+    pub label: Option<String>,
+    pub password: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct AppPasswordListResponse {
+    pub app_passwords: Vec<AppPasswordResponse>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CreateAppPasswordRequest {
+    pub label: Option<String>,
+}
+
+fn rfc3339(row: &tokio_postgres::Row, column: &str) -> Option<String> {
+    row.get::<_, Option<chrono::DateTime<chrono::Utc>>>(column)
+        .map(|date| date.to_rfc3339())
+}
+
+/// Subsonic token: lowercase hex of `md5(password + salt)`.
+pub(crate) fn subsonic_token(password: &str, salt: &str) -> String {
+    hex::encode(Md5::digest(format!("{password}{salt}").as_bytes()))
+}
+
+/// Find which of `user_id`'s app passwords satisfies `matches`, and mark it used.
+///
+/// Passwords that no longer decrypt (for example after the server key changed) are skipped.
+pub(crate) async fn verify_app_password(
+    client: &impl GenericClient,
+    secret: &AppSecretKey,
+    user_id: Uuid,
+    matches: impl Fn(&str) -> bool,
+) -> Result<bool, tokio_postgres::Error> {
+    let rows = client
+        .query(
+            "SELECT id, sealed_password FROM subsonic_app_passwords WHERE user_id = $1",
+            &[&user_id],
+        )
+        .await?;
+    let matched = rows.iter().find_map(|row| {
+        let sealed: Vec<u8> = row.get("sealed_password");
+        let password = open_secret(secret, &sealed).ok()?;
+        matches(&password).then(|| row.get::<_, Uuid>("id"))
+    });
+
+    match matched {
+        Some(id) => {
+            client
+                .execute(
+                    "UPDATE subsonic_app_passwords SET last_used_at = NOW() WHERE id = $1",
+                    &[&id],
+                )
+                .await?;
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+// ── Endpoints ──
+
+#[get("/music/app-passwords")]
+pub(crate) async fn list_app_passwords(
+    pool: &State<Pool>,
+    user: AuthenticatedUser,
+) -> Result<Json<AppPasswordListResponse>, (Status, Json<serde_json::Value>)> {
+    require_session(&user)?;
+    let client = get_client(pool).await?;
+    let rows = client
+        .query(
+            "SELECT id, label, last_used_at, created_at FROM subsonic_app_passwords
+             WHERE user_id = $1 ORDER BY created_at DESC",
+            &[&user.id],
+        )
+        .await
+        .map_err(db_error)?;
+    let app_passwords = rows
+        .iter()
+        .map(|row| AppPasswordResponse {
+            id: row.get("id"),
+            label: row.get("label"),
+            last_used_at: rfc3339(row, "last_used_at"),
+            created_at: rfc3339(row, "created_at").unwrap_or_default(),
+        })
+        .collect();
+    Ok(Json(AppPasswordListResponse { app_passwords }))
+}
+
+#[post("/music/app-passwords", data = "<req>")]
+pub(crate) async fn create_app_password(
+    pool: &State<Pool>,
+    secret: &State<AppSecretKey>,
+    user: AuthenticatedUser,
+    req: Json<CreateAppPasswordRequest>,
+) -> Result<Json<AppPasswordCreatedResponse>, (Status, Json<serde_json::Value>)> {
+    require_session(&user)?;
+    let password = random_hex::<12>();
+    let sealed = seal_secret(secret, &password).map_err(|error| {
+        eprintln!("Failed to seal app password: {error}");
+        server_error()
+    })?;
+
+    let client = get_client(pool).await?;
+    let row = client
+        .query_one(
+            "INSERT INTO subsonic_app_passwords (user_id, label, sealed_password)
+             VALUES ($1, $2, $3)
+             RETURNING id, created_at",
+            &[&user.id, &req.label, &sealed],
+        )
+        .await
+        .map_err(db_error)?;
+
+    Ok(Json(AppPasswordCreatedResponse {
+        id: row.get("id"),
+        label: req.label.clone(),
+        password,
+        created_at: rfc3339(&row, "created_at").unwrap_or_default(),
+    }))
+}
+
+#[delete("/music/app-passwords/<id>")]
+pub(crate) async fn revoke_app_password(
+    pool: &State<Pool>,
+    user: AuthenticatedUser,
+    id: &str,
+) -> Result<Json<serde_json::Value>, (Status, Json<serde_json::Value>)> {
+    require_session(&user)?;
+    let id = Uuid::parse_str(id).map_err(|_| not_found("Invalid app password ID"))?;
+    let client = get_client(pool).await?;
+    if client
+        .execute(
+            "DELETE FROM subsonic_app_passwords WHERE id = $1 AND user_id = $2",
+            &[&id, &user.id],
+        )
+        .await
+        .map_err(db_error)?
+        == 0
+    {
+        return Err(not_found("App password not found"));
+    }
+    Ok(Json(serde_json::json!({"message": "App password revoked"})))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subsonic_token_matches_the_protocol_example() {
+        // From the Subsonic API documentation: password "sesame", salt "c19b2d".
+        assert_eq!(
+            subsonic_token("sesame", "c19b2d"),
+            "26719a1196d2a940705a59634eb18eab"
+        );
+    }
+}
@@ -1,11 +1,15 @@
 use super::*;
 
+use crate::audit::{AuditEvent, record_audit};
 use crate::models::{
     CreateRoleRequest, CreateUserRequest, LoginResponse, MoveDirection, MoveRoleRequest,
-    PaginationParams, RoleWithPermissions, UpdateRoleRequest, UpdateUserPasswordRequest,
+    PaginationParams, RoleWithPermissions, UpdateRoleRequest, UpdateUserDisabledRequest,
+    UpdateUserEmailRequest, UpdateUserExpiryRequest, UpdateUserPasswordRequest,
     UpdateUserRoleRequest, User,
 };
-use rocket::http::Status;
+use deadpool_postgres::GenericClient;
+use rocket::http::{CookieJar, Status};
+use tokio_postgres::error::SqlState;
 use uuid::Uuid;
 
 type SqlParam<'a> = &'a (dyn tokio_postgres::types::ToSql + Sync);
@@ -46,24 +50,15 @@ async fn query_page<'a>(
     client.query(sql, &params).await
 }
 
-/// POST /api/users - Create a new user (admin only)
-#[post("/users", data = "<create>")]
-pub async fn create_user(
-    pool: &State<Pool>,
-    user: AuthenticatedUser,
-    create: Json<CreateUserRequest>,
-) -> Result<Json<LoginResponse>, (Status, Json<serde_json::Value>)> {
-    require_permission(pool, user.id, "create_user").await?;
-
-    if create.username.is_empty() || create.password.is_empty() {
-        return Err(bad_request("Username and password are required"));
-    }
-
-    let client = get_client(pool).await?;
-    let role_name = create
-        .role_name
-        .clone()
-        .unwrap_or_else(|| "viewer".to_string());
+/// Create an account after checking the password policy, role, and username.
+pub(crate) async fn insert_user(
+    client: &impl GenericClient,
+    username: &str,
+    password: &str,
+    role_name: &str,
+    email: Option<String>,
+) -> Result<User, ApiError> {
+    PasswordPolicy::from_env().validate(password).await?;
 
     let role_row = client
         .query_opt("SELECT id, name FROM roles WHERE name = $1", &[&role_name])
@@ -74,10 +69,7 @@ pub async fn create_user(
     let role_id: i32 = role_row.get("id");
 
     let existing = client
-        .query_opt(
-            "SELECT id FROM users WHERE username = $1",
-            &[&create.username],
-        )
+        .query_opt("SELECT id FROM users WHERE username = $1", &[&username])
         .await
         .map_err(db_error)?;
 
@@ -85,7 +77,7 @@ pub async fn create_user(
         return Err(conflict("Username already exists"));
     }
 
-    let password_hash = match hash_password(&create.password) {
+    let password_hash = match hash_password(password) {
         Ok(h) => h,
         Err(_) => return Err(server_error()),
     };
@@ -93,24 +85,68 @@ pub async fn create_user(
     let user_id = Uuid::new_v4();
     client
         .execute(
-            "INSERT INTO users (id, username, password_hash, role_id) VALUES ($1, $2, $3, $4)",
-            &[&user_id, &create.username, &password_hash, &role_id],
+            "INSERT INTO users (id, username, password_hash, role_id, email)
+             VALUES ($1, $2, $3, $4, $5)",
+            &[&user_id, &username, &password_hash, &role_id, &email],
         )
         .await
         .map_err(|e| {
+            if e.code() == Some(&SqlState::UNIQUE_VIOLATION) {
+                return conflict("Email address is already in use");
+            }
             eprintln!("Failed to create user: {e}");
             server_error()
         })?;
 
-    let new_user = User {
+    Ok(User {
         id: user_id,
-        username: create.username.clone(),
+        username: username.to_owned(),
         password_hash: String::new(),
         role_id,
-        role_name: role_name.clone(),
+        role_name: role_name.to_owned(),
+        email,
+        disabled_at: None,
+        disabled_reason: None,
+        expires_at: None,
         created_at: Utc::now(),
         updated_at: Utc::now(),
-    };
+    })
+}
+
+/// POST /api/users - Create a new user (admin only)
+#[post("/users", data = "<create>")]
+pub async fn create_user(
+    pool: &State<Pool>,
+    user: AuthenticatedUser,
+    create: Json<CreateUserRequest>,
+) -> Result<Json<LoginResponse>, (Status, Json<serde_json::Value>)> {
+    require_permission(pool, &user, "create_user").await?;
+
+    if create.username.is_empty() || create.password.is_empty() {
+        return Err(bad_request("Username and password are required"));
+    }
+    let email = normalize_email(create.email.as_deref())?;
+
+    let client = get_client(pool).await?;
+    let role_name = create.role_name.as_deref().unwrap_or("viewer");
+    let new_user = insert_user(
+        &client,
+        &create.username,
+        &create.password,
+        role_name,
+        email,
+    )
+    .await?;
+    record_audit(
+        pool,
+        &user,
+        AuditEvent::new("user.create", "user", new_user.id).after(serde_json::json!({
+            "username": new_user.username,
+            "role": new_user.role_name,
+            "email": new_user.email,
+        })),
+    )
+    .await;
 
     Ok(Json(LoginResponse { user: new_user }))
 }
@@ -122,7 +158,7 @@ pub async fn list_users(
     user: AuthenticatedUser,
     pagination: PaginationParams,
 ) -> Result<Json<serde_json::Value>, (Status, Json<serde_json::Value>)> {
-    require_permission(pool, user.id, "view_users").await?;
+    require_permission(pool, &user, "view_users").await?;
 
     let client = get_client(pool).await?;
     let limit = pagination.effective_limit();
@@ -163,6 +199,7 @@ pub async fn list_users(
 
     let data_sql = format!(
         "SELECT u.id, u.username, u.password_hash, u.role_id, r.name as role_name,
+                u.email, u.disabled_at, u.disabled_reason, u.expires_at,
                 u.created_at, u.updated_at
          FROM users u
          JOIN roles r ON u.role_id = r.id
@@ -281,7 +318,7 @@ pub async fn create_role(
     user: AuthenticatedUser,
     create: Json<CreateRoleRequest>,
 ) -> Result<Json<RoleWithPermissions>, (Status, Json<serde_json::Value>)> {
-    require_permission(pool, user.id, "manage_roles").await?;
+    require_permission(pool, &user, "manage_roles").await?;
 
     if create.name.is_empty() || create.display_name.is_empty() {
         return Err(bad_request("Name and display_name are required"));
@@ -319,6 +356,16 @@ pub async fn create_role(
     let response = row_to_role(&role, create.permissions.clone());
 
     transaction.commit().await.map_err(db_error)?;
+    record_audit(
+        pool,
+        &user,
+        AuditEvent::new("role.create", "role", &response.name).after(serde_json::json!({
+            "display_name": response.display_name,
+            "color": response.color,
+            "permissions": response.permissions,
+        })),
+    )
+    .await;
     Ok(Json(response))
 }
 
@@ -330,7 +377,7 @@ pub async fn update_role(
     id: i32,
     update: Json<UpdateRoleRequest>,
 ) -> Result<Json<RoleWithPermissions>, (Status, Json<serde_json::Value>)> {
-    require_permission(pool, user.id, "manage_roles").await?;
+    require_permission(pool, &user, "manage_roles").await?;
 
     if update.display_name.is_empty() {
         return Err(bad_request("display_name is required"));
@@ -340,6 +387,16 @@ pub async fn update_role(
     let mut client = get_client(pool).await?;
     let transaction = client.transaction().await.map_err(db_error)?;
 
+    let previous = transaction
+        .query_opt(
+            "SELECT display_name, color FROM roles WHERE id = $1 FOR UPDATE",
+            &[&id],
+        )
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| not_found("Role not found"))?;
+    let previous_permissions = role_permissions(&transaction, id).await.map_err(db_error)?;
+
     let row = transaction
         .query_opt(
             "UPDATE roles SET display_name = $1, color = $2
@@ -362,6 +419,22 @@ pub async fn update_role(
     let response = row_to_role(&row, update.permissions.clone());
 
     transaction.commit().await.map_err(db_error)?;
+    record_audit(
+        pool,
+        &user,
+        AuditEvent::new("role.update", "role", &response.name)
+            .before(serde_json::json!({
+                "display_name": previous.get::<_, String>("display_name"),
+                "color": previous.get::<_, String>("color"),
+                "permissions": previous_permissions,
+            }))
+            .after(serde_json::json!({
+                "display_name": response.display_name,
+                "color": response.color,
+                "permissions": response.permissions,
+            })),
+    )
+    .await;
     Ok(Json(response))
 }
 
@@ -373,7 +446,7 @@ pub async fn move_role(
     id: i32,
     move_request: Json<MoveRoleRequest>,
 ) -> Result<Json<RoleWithPermissions>, (Status, Json<serde_json::Value>)> {
-    require_permission(pool, user.id, "manage_roles").await?;
+    require_permission(pool, &user, "manage_roles").await?;
 
     let mut client = get_client(pool).await?;
     let transaction = client.transaction().await.map_err(db_error)?;
@@ -440,6 +513,14 @@ pub async fn move_role(
     let response = row_to_role(&row, permissions);
 
     transaction.commit().await.map_err(db_error)?;
+    record_audit(
+        pool,
+        &user,
+        AuditEvent::new("role.move", "role", &response.name)
+            .before(serde_json::json!({"position": position}))
+            .after(serde_json::json!({"position": response.position})),
+    )
+    .await;
     Ok(Json(response))
 }
 
@@ -450,7 +531,7 @@ pub async fn delete_role(
     user: AuthenticatedUser,
     id: i32,
 ) -> Result<Json<serde_json::Value>, (Status, Json<serde_json::Value>)> {
-    require_permission(pool, user.id, "manage_roles").await?;
+    require_permission(pool, &user, "manage_roles").await?;
 
     let mut client = get_client(pool).await?;
     let transaction = client.transaction().await.map_err(db_error)?;
@@ -482,7 +563,8 @@ pub async fn delete_role(
 
     transaction
         .execute(
-            "UPDATE users SET role_id = $1 WHERE role_id = $2",
+            "UPDATE users SET role_id = $1, token_version = token_version + 1
+             WHERE role_id = $2",
             &[&viewer_id, &id],
         )
         .await
@@ -504,9 +586,54 @@ pub async fn delete_role(
         .map_err(db_error)?;
 
     transaction.commit().await.map_err(db_error)?;
+    record_audit(
+        pool,
+        &user,
+        AuditEvent::new("role.delete", "role", &name)
+            .before(serde_json::json!({"position": position})),
+    )
+    .await;
     Ok(Json(serde_json::json!({"success": true})))
 }
 
+/// Move `user_id` to `role`, returning the previous role name and the updated user.
+pub(crate) async fn set_user_role(
+    client: &impl GenericClient,
+    user_id: Uuid,
+    role: &str,
+) -> Result<(Option<String>, User), ApiError> {
+    let role_row = client
+        .query_opt("SELECT id FROM roles WHERE name = $1", &[&role])
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| bad_request(&format!("Role '{}' not found", role)))?;
+
+    let role_id: i32 = role_row.get("id");
+    let previous_role: Option<String> = find_user_by_id(client, user_id)
+        .await
+        .map_err(db_error)?
+        .map(|row| row.get("role_name"));
+
+    // Bumping the token version forces a refresh, which reissues the role claim.
+    let updated = client
+        .execute(
+            "UPDATE users SET role_id = $1, token_version = token_version + 1 WHERE id = $2",
+            &[&role_id, &user_id],
+        )
+        .await
+        .map_err(db_error)?;
+
+    if updated == 0 {
+        return Err(not_found("User not found"));
+    }
+
+    let row = find_user_by_id(client, user_id)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| not_found("User not found after update"))?;
+    Ok((previous_role, row_to_user(&row)))
+}
+
 /// PUT /api/users/<id>/role - Update user role
 #[put("/users/<id>/role", data = "<update>")]
 pub async fn update_user_role(
@@ -516,29 +643,159 @@ pub async fn update_user_role(
     update: Json<UpdateUserRoleRequest>,
 ) -> Result<Json<User>, (Status, Json<serde_json::Value>)> {
     let user_id = parse_user_id(&id)?;
-    require_permission(pool, user.id, "edit_user").await?;
+    require_permission(pool, &user, "edit_user").await?;
 
     if user_id == user.id {
         return Err(bad_request("Cannot change your own role"));
     }
 
     let client = get_client(pool).await?;
+    let (previous_role, updated) = set_user_role(&client, user_id, &update.role).await?;
+    record_audit(
+        pool,
+        &user,
+        AuditEvent::new("user.role.update", "user", user_id)
+            .before(serde_json::json!({"role": previous_role}))
+            .after(serde_json::json!({"role": update.role})),
+    )
+    .await;
+
+    Ok(Json(updated))
+}
 
-    let role_row = client
-        .query_opt("SELECT id FROM roles WHERE name = $1", &[&update.role])
+/// A new password that passed the policy, hashed before any row is locked.
+pub(crate) struct NewPassword {
+    hash: String,
+    replaces: String,
+}
+
+/// Check `password` against the policy and the account's current password, then hash it.
+pub(crate) async fn prepare_user_password(
+    client: &impl GenericClient,
+    user_id: Uuid,
+    password: &str,
+) -> Result<NewPassword, ApiError> {
+    let current_hash: String = client
+        .query_opt("SELECT password_hash FROM users WHERE id = $1", &[&user_id])
         .await
         .map_err(db_error)?
-        .ok_or_else(|| bad_request(&format!("Role '{}' not found", update.role)))?;
+        .ok_or_else(|| not_found("User not found"))?
+        .get("password_hash");
+
+    PasswordPolicy::from_env()
+        .validate_new_password(password, Some(&current_hash))
+        .await?;
+
+    let hash = hash_password(password).map_err(|_| server_error())?;
+    Ok(NewPassword {
+        hash,
+        replaces: current_hash,
+    })
+}
 
-    let role_id: i32 = role_row.get("id");
+/// Store a prepared password and sign the account out everywhere.
+///
+/// Fails with a conflict when the password changed after it was prepared.
+pub(crate) async fn set_user_password(
+    transaction: &impl GenericClient,
+    user_id: Uuid,
+    password: &NewPassword,
+) -> Result<(), ApiError> {
+    let updated = transaction
+        .execute(
+            "UPDATE users SET password_hash = $1, password_changed_at = NOW(), updated_at = NOW()
+             WHERE id = $2 AND password_hash = $3",
+            &[&password.hash, &user_id, &password.replaces],
+        )
+        .await
+        .map_err(db_error)?;
+    if updated == 0 {
+        return Err(conflict("Password was changed by another request"));
+    }
+
+    revoke_user_sessions(transaction, user_id)
+        .await
+        .map_err(db_error)?;
+    Ok(())
+}
 
+/// PUT /api/users/<id>/password - Update user password and sign out its sessions
+#[put("/users/<id>/password", data = "<update>")]
+pub async fn update_user_password(
+    pool: &State<Pool>,
+    keys: &State<SigningKeys>,
+    jar: &CookieJar<'_>,
+    user: AuthenticatedUser,
+    id: String,
+    update: Json<UpdateUserPasswordRequest>,
+) -> Result<Json<serde_json::Value>, (Status, Json<serde_json::Value>)> {
+    let user_id = parse_user_id(&id)?;
+    reject_impersonation(&user)?;
+    if user_id == user.id {
+        require_session(&user)?;
+    } else {
+        require_permission(pool, &user, "edit_user").await?;
+    }
+
+    let mut client = get_client(pool).await?;
+    let password = prepare_user_password(&client, user_id, &update.password).await?;
+    let transaction = client.transaction().await.map_err(db_error)?;
+    set_user_password(&transaction, user_id, &password).await?;
+
+    // Keep the caller signed in when they changed their own password.
+    if user_id == user.id {
+        let profile = find_user_by_id(&transaction, user_id)
+            .await
+            .map_err(db_error)?
+            .map(|row| row_to_user(&row))
+            .ok_or_else(|| not_found("User not found"))?;
+        super::login::issue_tokens(&transaction, jar, keys, &profile, Uuid::new_v4()).await?;
+    }
+
+    transaction.commit().await.map_err(db_error)?;
+    record_audit(
+        pool,
+        &user,
+        AuditEvent::new("user.password.update", "user", user_id),
+    )
+    .await;
+    Ok(Json(serde_json::json!({"success": true})))
+}
+
+/// PUT /api/users/<id>/email - Set or clear the address used for password resets
+#[put("/users/<id>/email", data = "<update>")]
+pub async fn update_user_email(
+    pool: &State<Pool>,
+    user: AuthenticatedUser,
+    id: String,
+    update: Json<UpdateUserEmailRequest>,
+) -> Result<Json<User>, (Status, Json<serde_json::Value>)> {
+    let user_id = parse_user_id(&id)?;
+    if user_id == user.id {
+        require_session(&user)?;
+    } else {
+        require_permission(pool, &user, "edit_user").await?;
+    }
+
+    let email = normalize_email(update.email.as_deref())?;
+    let client = get_client(pool).await?;
+    let previous_email: Option<String> = find_user_by_id(&client, user_id)
+        .await
+        .map_err(db_error)?
+        .and_then(|row| row.get("email"));
     let updated = client
         .execute(
-            "UPDATE users SET role_id = $1 WHERE id = $2",
-            &[&role_id, &user_id],
+            "UPDATE users SET email = $1, updated_at = NOW() WHERE id = $2",
+            &[&email, &user_id],
         )
         .await
-        .map_err(db_error)?;
+        .map_err(|e| {
+            if e.code() == Some(&SqlState::UNIQUE_VIOLATION) {
+                conflict("Email address is already in use")
+            } else {
+                db_error(e)
+            }
+        })?;
 
     if updated == 0 {
         return Err(not_found("User not found"));
@@ -548,38 +805,125 @@ pub async fn update_user_role(
         .await
         .map_err(db_error)?
         .ok_or_else(|| not_found("User not found after update"))?;
+    record_audit(
+        pool,
+        &user,
+        AuditEvent::new("user.email.update", "user", user_id)
+            .before(serde_json::json!({"email": previous_email}))
+            .after(serde_json::json!({"email": email})),
+    )
+    .await;
 
     Ok(Json(row_to_user(&row)))
 }
 
-/// PUT /api/users/<id>/password - Update user password
-#[put("/users/<id>/password", data = "<update>")]
-pub async fn update_user_password(
+/// PUT /api/users/<id>/disabled - Disable or re-enable an account without deleting its data
+#[put("/users/<id>/disabled", data = "<update>")]
+pub async fn update_user_disabled(
     pool: &State<Pool>,
     user: AuthenticatedUser,
     id: String,
-    update: Json<UpdateUserPasswordRequest>,
-) -> Result<Json<serde_json::Value>, (Status, Json<serde_json::Value>)> {
+    update: Json<UpdateUserDisabledRequest>,
+) -> Result<Json<User>, (Status, Json<serde_json::Value>)> {
     let user_id = parse_user_id(&id)?;
-    if user_id != user.id {
-        require_permission(pool, user.id, "edit_user").await?;
+    require_permission(pool, &user, "edit_user").await?;
+
+    if user_id == user.id {
+        return Err(bad_request("Cannot disable your own account"));
     }
 
-    if update.password.len() < 4 {
-        return Err(bad_request("Password must be at least 4 characters"));
+    let reason = update
+        .reason
+        .as_deref()
+        .map(str::trim)
+        .filter(|reason| !reason.is_empty());
+    let mut client = get_client(pool).await?;
+    let transaction = client.transaction().await.map_err(db_error)?;
+    let updated = if update.disabled {
+        transaction
+            .execute(
+                "UPDATE users
+                 SET disabled_at = COALESCE(disabled_at, NOW()), disabled_reason = $2,
+                     disabled_by_user_id = $3, updated_at = NOW()
+                 WHERE id = $1",
+                &[&user_id, &reason, &user.id],
+            )
+            .await
+    } else {
+        transaction
+            .execute(
+                "UPDATE users
+                 SET disabled_at = NULL, disabled_reason = NULL, disabled_by_user_id = NULL,
+                     updated_at = NOW()
+                 WHERE id = $1",
+                &[&user_id],
+            )
+            .await
     }
+    .map_err(db_error)?;
 
-    let client = get_client(pool).await?;
+    if updated == 0 {
+        return Err(not_found("User not found"));
+    }
 
-    let password_hash = match hash_password(&update.password) {
-        Ok(h) => h,
-        Err(_) => return Err(server_error()),
-    };
+    if update.disabled {
+        revoke_user_sessions(&transaction, user_id)
+            .await
+            .map_err(db_error)?;
+    }
 
+    let row = find_user_by_id(&transaction, user_id)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| not_found("User not found after update"))?;
+    transaction.commit().await.map_err(db_error)?;
+    let profile = row_to_user(&row);
+    record_audit(
+        pool,
+        &user,
+        AuditEvent::new(
+            if update.disabled {
+                "user.disable"
+            } else {
+                "user.enable"
+            },
+            "user",
+            user_id,
+        )
+        .after(serde_json::json!({
+            "disabled_at": profile.disabled_at,
+            "disabled_reason": profile.disabled_reason,
+        })),
+    )
+    .await;
+
+    Ok(Json(profile))
+}
+
+/// PUT /api/users/<id>/expiry - Set or clear the time after which an account can no longer sign in
+#[put("/users/<id>/expiry", data = "<update>")]
+pub async fn update_user_expiry(
+    pool: &State<Pool>,
+    user: AuthenticatedUser,
+    id: String,
+    update: Json<UpdateUserExpiryRequest>,
+) -> Result<Json<User>, (Status, Json<serde_json::Value>)> {
+    let user_id = parse_user_id(&id)?;
+    require_permission(pool, &user, "edit_user").await?;
+
+    if user_id == user.id {
+        return Err(bad_request("Cannot set an expiry on your own account"));
+    }
+
+    let client = get_client(pool).await?;
+    let previous_expiry: Option<chrono::DateTime<Utc>> = find_user_by_id(&client, user_id)
+        .await
+        .map_err(db_error)?
+        .and_then(|row| row.get("expires_at"));
     let updated = client
         .execute(
-            "UPDATE users SET password_hash = $1 WHERE id = $2",
-            &[&password_hash, &user_id],
+            "UPDATE users SET expires_at = $2, updated_at = NOW() WHERE id = $1",
+            &[&user_id, &update.expires_at],
         )
         .await
         .map_err(db_error)?;
@@ -588,6 +932,47 @@ pub async fn update_user_password(
         return Err(not_found("User not found"));
     }
 
+    let row = find_user_by_id(&client, user_id)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| not_found("User not found after update"))?;
+    record_audit(
+        pool,
+        &user,
+        AuditEvent::new("user.expiry.update", "user", user_id)
+            .before(serde_json::json!({"expires_at": previous_expiry}))
+            .after(serde_json::json!({"expires_at": update.expires_at})),
+    )
+    .await;
+
+    Ok(Json(row_to_user(&row)))
+}
+
+/// POST /api/users/<id>/revoke-sessions - Sign a user out everywhere
+#[post("/users/<id>/revoke-sessions")]
+pub async fn revoke_sessions(
+    pool: &State<Pool>,
+    user: AuthenticatedUser,
+    id: String,
+) -> Result<Json<serde_json::Value>, (Status, Json<serde_json::Value>)> {
+    let user_id = parse_user_id(&id)?;
+    require_permission(pool, &user, "edit_user").await?;
+
+    let client = get_client(pool).await?;
+    if revoke_user_sessions(&client, user_id)
+        .await
+        .map_err(db_error)?
+        == 0
+    {
+        return Err(not_found("User not found"));
+    }
+    record_audit(
+        pool,
+        &user,
+        AuditEvent::new("user.sessions.revoke", "user", user_id),
+    )
+    .await;
+
     Ok(Json(serde_json::json!({"success": true})))
 }
 
@@ -599,7 +984,7 @@ pub async fn delete_user(
     id: String,
 ) -> Result<Json<serde_json::Value>, (Status, Json<serde_json::Value>)> {
     let user_id = parse_user_id(&id)?;
-    require_permission(pool, user.id, "delete_user").await?;
+    require_permission(pool, &user, "delete_user").await?;
 
     if user_id == user.id {
         return Err(bad_request("Cannot delete yourself"));
@@ -607,14 +992,15 @@ pub async fn delete_user(
 
     let client = get_client(pool).await?;
 
-    if let Ok(row) = client
-        .query_one("SELECT username FROM users WHERE id = $1", &[&user_id])
+    let previous = find_user_by_id(&client, user_id)
         .await
+        .map_err(db_error)?
+        .map(|row| row_to_user(&row));
+    if previous
+        .as_ref()
+        .is_some_and(|previous| previous.username == "admin")
     {
-        let username: String = row.get("username");
-        if username == "admin" {
-            return Err(bad_request("Cannot delete the admin user"));
-        }
+        return Err(bad_request("Cannot delete the admin user"));
     }
 
     let deleted = client
@@ -625,6 +1011,18 @@ pub async fn delete_user(
     if deleted == 0 {
         return Err(not_found("User not found"));
     }
+    if let Some(previous) = previous {
+        record_audit(
+            pool,
+            &user,
+            AuditEvent::new("user.delete", "user", user_id).before(serde_json::json!({
+                "username": previous.username,
+                "role": previous.role_name,
+                "email": previous.email,
+            })),
+        )
+        .await;
+    }
 
     Ok(Json(serde_json::json!({"success": true})))
 }
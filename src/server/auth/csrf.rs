@@ -0,0 +1,95 @@
+use super::*;
+
+use rocket::http::{Cookie, CookieJar, Method, SameSite, Status};
+use rocket::request::{FromRequest, Outcome, Request};
+
+/// Readable by the web UI, which echoes it in [`CSRF_HEADER`] on every mutating request.
+pub(crate) const CSRF_COOKIE: &str = "csrfToken";
+pub(crate) const CSRF_HEADER: &str = "X-CSRF-Token";
+
+pub(crate) fn make_csrf_cookie(token: String, expiration_hours: i64) -> Cookie<'static> {
+    Cookie::build((CSRF_COOKIE, token))
+        .path("/")
+        .http_only(false)
+        .same_site(SameSite::Strict)
+        .max_age(rocket::time::Duration::hours(expiration_hours))
+        .into()
+}
+
+pub(crate) fn new_csrf_cookie() -> Cookie<'static> {
+    make_csrf_cookie(random_hex::<32>(), access_token_hours() * 24)
+}
+
+/// Whether the request carries a session cookie, so cross-site forgery is possible at all.
+pub(crate) fn has_session_cookie(jar: &CookieJar<'_>) -> bool {
+    jar.get("accessToken").is_some() || jar.get("refreshToken").is_some()
+}
+
+fn is_safe_method(method: Method) -> bool {
+    matches!(method, Method::Get | Method::Head | Method::Options)
+}
+
+fn tokens_match(cookie: Option<&str>, header: Option<&str>) -> bool {
+    let (Some(cookie), Some(header)) = (cookie, header) else {
+        return false;
+    };
+    if cookie.is_empty() || cookie.len() != header.len() {
+        return false;
+    }
+    cookie
+        .bytes()
+        .zip(header.bytes())
+        .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+        == 0
+}
+
+/// Double-submit check for a request authenticated by cookie.
+///
+/// Safe methods pass; anything else must send the `csrfToken` cookie's value back
+/// in `X-CSRF-Token`, which a cross-site page cannot read or set.
+pub(crate) fn csrf_verified(request: &Request<'_>) -> bool {
+    is_safe_method(request.method())
+        || tokens_match(
+            request
+                .cookies()
+                .get(CSRF_COOKIE)
+                .map(|cookie| cookie.value()),
+            request.headers().get_one(CSRF_HEADER),
+        )
+}
+
+/// Record the rejection so the 403 catcher can explain it.
+pub(crate) fn reject_csrf<T, E>(request: &Request<'_>, error: E) -> Outcome<T, E> {
+    request.local_cache(|| CsrfRejected(true));
+    Outcome::Error((Status::Forbidden, error))
+}
+
+/// Guard for routes that authenticate with the refresh cookie rather than [`AuthenticatedUser`].
+pub struct CsrfVerified;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for CsrfVerified {
+    type Error = AuthError;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        if has_session_cookie(request.cookies()) && !csrf_verified(request) {
+            return reject_csrf(request, AuthError::CsrfMismatch);
+        }
+        Outcome::Success(CsrfVerified)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens_must_be_present_and_equal() {
+        assert!(tokens_match(Some("abc123"), Some("abc123")));
+        assert!(!tokens_match(Some("abc123"), Some("abc124")));
+        assert!(!tokens_match(Some("abc123"), Some("abc12")));
+        assert!(!tokens_match(Some("abc123"), None));
+        assert!(!tokens_match(None, Some("abc123")));
+        assert!(!tokens_match(Some(""), Some("")));
+    }
+}
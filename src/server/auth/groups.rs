@@ -0,0 +1,398 @@
+use super::*;
+
+use crate::audit::{AuditEvent, record_audit};
+use crate::models::{CreateGroupRequest, Group, GroupMember, GroupWithMembers, UpdateGroupRequest};
+use deadpool_postgres::GenericClient;
+use rocket::http::Status;
+use tokio_postgres::error::SqlState;
+
+const GROUP_COLUMNS: &str =
+    "g.id, g.name, g.display_name, g.description, g.created_at, g.updated_at,
+     (SELECT COUNT(*) FROM user_groups ug WHERE ug.group_id = g.id) AS member_count";
+
+fn row_to_group(row: &tokio_postgres::Row, permissions: Vec<String>) -> Group {
+    Group {
+        id: row.get("id"),
+        name: row.get("name"),
+        display_name: row.get("display_name"),
+        description: row.get("description"),
+        permissions,
+        member_count: row.get("member_count"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    }
+}
+
+async fn group_permissions(
+    client: &impl GenericClient,
+    group_id: i32,
+) -> Result<Vec<String>, tokio_postgres::Error> {
+    let rows = client
+        .query(
+            "SELECT p.name FROM permissions p
+             JOIN group_permissions gp ON p.id = gp.permission_id
+             WHERE gp.group_id = $1 ORDER BY p.group_name, p.name",
+            &[&group_id],
+        )
+        .await?;
+    Ok(rows.iter().map(|row| row.get("name")).collect())
+}
+
+async fn assign_group_permissions(
+    client: &impl GenericClient,
+    group_id: i32,
+    permissions: &[String],
+) -> Result<(), tokio_postgres::Error> {
+    for permission in permissions {
+        client
+            .execute(
+                "INSERT INTO group_permissions (group_id, permission_id)
+                 SELECT $1, id FROM permissions WHERE name = $2
+                 ON CONFLICT DO NOTHING",
+                &[&group_id, permission],
+            )
+            .await?;
+    }
+    Ok(())
+}
+
+fn group_snapshot(group: &Group) -> serde_json::Value {
+    serde_json::json!({
+        "name": group.name,
+        "display_name": group.display_name,
+        "description": group.description,
+        "permissions": group.permissions,
+    })
+}
+
+async fn find_group(
+    client: &impl GenericClient,
+    id: i32,
+) -> Result<Option<Group>, tokio_postgres::Error> {
+    let Some(row) = client
+        .query_opt(
+            &format!("SELECT {GROUP_COLUMNS} FROM groups g WHERE g.id = $1"),
+            &[&id],
+        )
+        .await?
+    else {
+        return Ok(None);
+    };
+    let permissions = group_permissions(client, id).await?;
+    Ok(Some(row_to_group(&row, permissions)))
+}
+
+/// Only let callers hand out permissions they hold themselves.
+async fn require_grantable(
+    pool: &Pool,
+    user: &AuthenticatedUser,
+    permissions: &[String],
+) -> Result<(), ApiError> {
+    for permission in permissions {
+        if !has_permission(pool, user, permission).await {
+            return Err(forbidden());
+        }
+    }
+    Ok(())
+}
+
+fn group_write_error(error: tokio_postgres::Error) -> ApiError {
+    if error.code() == Some(&SqlState::UNIQUE_VIOLATION) {
+        conflict("Group already exists")
+    } else {
+        db_error(error)
+    }
+}
+
+/// GET /api/groups - List all groups with their permissions
+#[get("/groups")]
+pub async fn list_groups(
+    pool: &State<Pool>,
+    user: AuthenticatedUser,
+) -> Result<Json<Vec<Group>>, (Status, Json<serde_json::Value>)> {
+    require_permission(pool, &user, "manage_groups").await?;
+    let client = get_client(pool).await?;
+
+    let rows = client
+        .query(
+            &format!("SELECT {GROUP_COLUMNS} FROM groups g ORDER BY g.name"),
+            &[],
+        )
+        .await
+        .map_err(db_error)?;
+
+    let mut groups = Vec::with_capacity(rows.len());
+    for row in &rows {
+        let permissions = group_permissions(&client, row.get("id"))
+            .await
+            .map_err(db_error)?;
+        groups.push(row_to_group(row, permissions));
+    }
+    Ok(Json(groups))
+}
+
+/// GET /api/groups/<id> - A group with its permissions and members
+#[get("/groups/<id>")]
+pub async fn get_group(
+    pool: &State<Pool>,
+    user: AuthenticatedUser,
+    id: i32,
+) -> Result<Json<GroupWithMembers>, (Status, Json<serde_json::Value>)> {
+    require_permission(pool, &user, "manage_groups").await?;
+    let client = get_client(pool).await?;
+
+    let group = find_group(&client, id)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| not_found("Group not found"))?;
+    let members = client
+        .query(
+            "SELECT u.id, u.username, ug.added_at FROM user_groups ug
+             JOIN users u ON u.id = ug.user_id
+             WHERE ug.group_id = $1 ORDER BY u.username",
+            &[&id],
+        )
+        .await
+        .map_err(db_error)?
+        .iter()
+        .map(|row| GroupMember {
+            user_id: row.get("id"),
+            username: row.get("username"),
+            added_at: row.get("added_at"),
+        })
+        .collect();
+
+    Ok(Json(GroupWithMembers { group, members }))
+}
+
+/// POST /api/groups - Create a group
+#[post("/groups", data = "<create>")]
+pub async fn create_group(
+    pool: &State<Pool>,
+    user: AuthenticatedUser,
+    create: Json<CreateGroupRequest>,
+) -> Result<Json<Group>, (Status, Json<serde_json::Value>)> {
+    require_permission(pool, &user, "manage_groups").await?;
+
+    let name = create.name.trim();
+    let display_name = create.display_name.trim();
+    if name.is_empty() || display_name.is_empty() {
+        return Err(bad_request("Name and display_name are required"));
+    }
+    require_grantable(pool, &user, &create.permissions).await?;
+
+    let mut client = get_client(pool).await?;
+    let transaction = client.transaction().await.map_err(db_error)?;
+    let row = transaction
+        .query_one(
+            "INSERT INTO groups (name, display_name, description)
+             VALUES ($1, $2, $3)
+             RETURNING id",
+            &[&name, &display_name, &create.description],
+        )
+        .await
+        .map_err(group_write_error)?;
+    let group_id: i32 = row.get("id");
+    assign_group_permissions(&transaction, group_id, &create.permissions)
+        .await
+        .map_err(db_error)?;
+    let group = find_group(&transaction, group_id)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| not_found("Group not found after create"))?;
+
+    transaction.commit().await.map_err(db_error)?;
+    record_audit(
+        pool,
+        &user,
+        AuditEvent::new("group.create", "group", group.id).after(group_snapshot(&group)),
+    )
+    .await;
+    Ok(Json(group))
+}
+
+/// PUT /api/groups/<id> - Update a group's details and replace its permissions
+#[put("/groups/<id>", data = "<update>")]
+pub async fn update_group(
+    pool: &State<Pool>,
+    user: AuthenticatedUser,
+    id: i32,
+    update: Json<UpdateGroupRequest>,
+) -> Result<Json<Group>, (Status, Json<serde_json::Value>)> {
+    require_permission(pool, &user, "manage_groups").await?;
+
+    let display_name = update.display_name.trim();
+    if display_name.is_empty() {
+        return Err(bad_request("display_name is required"));
+    }
+    require_grantable(pool, &user, &update.permissions).await?;
+
+    let mut client = get_client(pool).await?;
+    let transaction = client.transaction().await.map_err(db_error)?;
+
+    // Revoking a permission is as sensitive as granting it.
+    let previous = find_group(&transaction, id)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| not_found("Group not found"))?;
+    require_grantable(pool, &user, &previous.permissions).await?;
+
+    transaction
+        .execute(
+            "UPDATE groups SET display_name = $1, description = $2, updated_at = NOW()
+             WHERE id = $3",
+            &[&display_name, &update.description, &id],
+        )
+        .await
+        .map_err(db_error)?;
+    transaction
+        .execute("DELETE FROM group_permissions WHERE group_id = $1", &[&id])
+        .await
+        .map_err(db_error)?;
+    assign_group_permissions(&transaction, id, &update.permissions)
+        .await
+        .map_err(db_error)?;
+    let group = find_group(&transaction, id)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| not_found("Group not found after update"))?;
+
+    transaction.commit().await.map_err(db_error)?;
+    record_audit(
+        pool,
+        &user,
+        AuditEvent::new("group.update", "group", id)
+            .before(group_snapshot(&previous))
+            .after(group_snapshot(&group)),
+    )
+    .await;
+    Ok(Json(group))
+}
+
+/// DELETE /api/groups/<id> - Delete a group; members keep their role's permissions
+#[delete("/groups/<id>")]
+pub async fn delete_group(
+    pool: &State<Pool>,
+    user: AuthenticatedUser,
+    id: i32,
+) -> Result<Json<serde_json::Value>, (Status, Json<serde_json::Value>)> {
+    require_permission(pool, &user, "manage_groups").await?;
+    let client = get_client(pool).await?;
+
+    let group = find_group(&client, id)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| not_found("Group not found"))?;
+    require_grantable(pool, &user, &group.permissions).await?;
+
+    if client
+        .execute("DELETE FROM groups WHERE id = $1", &[&id])
+        .await
+        .map_err(db_error)?
+        == 0
+    {
+        return Err(not_found("Group not found"));
+    }
+    record_audit(
+        pool,
+        &user,
+        AuditEvent::new("group.delete", "group", id).before(serde_json::json!({
+            "group": group_snapshot(&group),
+            "member_count": group.member_count,
+        })),
+    )
+    .await;
+    Ok(Json(serde_json::json!({"message": "Group deleted"})))
+}
+
+/// PUT /api/groups/<id>/members/<user_id> - Add a user to a group
+#[put("/groups/<id>/members/<user_id>")]
+pub async fn add_group_member(
+    pool: &State<Pool>,
+    user: AuthenticatedUser,
+    id: i32,
+    user_id: &str,
+) -> Result<Json<serde_json::Value>, (Status, Json<serde_json::Value>)> {
+    require_permission(pool, &user, "manage_groups").await?;
+    let member_id = parse_user_id(user_id)?;
+    let client = get_client(pool).await?;
+
+    let group = find_group(&client, id)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| not_found("Group not found"))?;
+    require_grantable(pool, &user, &group.permissions).await?;
+
+    let username: String = client
+        .query_opt("SELECT username FROM users WHERE id = $1", &[&member_id])
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| not_found("User not found"))?
+        .get("username");
+    let added = client
+        .execute(
+            "INSERT INTO user_groups (user_id, group_id) VALUES ($1, $2)
+             ON CONFLICT DO NOTHING",
+            &[&member_id, &id],
+        )
+        .await
+        .map_err(db_error)?;
+    if added > 0 {
+        record_audit(
+            pool,
+            &user,
+            AuditEvent::new("group.member.add", "group", id).after(serde_json::json!({
+                "group": group.name,
+                "user_id": member_id,
+                "username": username,
+                "permissions": group.permissions,
+            })),
+        )
+        .await;
+    }
+    Ok(Json(serde_json::json!({"message": "User added to group"})))
+}
+
+/// DELETE /api/groups/<id>/members/<user_id> - Remove a user from a group
+#[delete("/groups/<id>/members/<user_id>")]
+pub async fn remove_group_member(
+    pool: &State<Pool>,
+    user: AuthenticatedUser,
+    id: i32,
+    user_id: &str,
+) -> Result<Json<serde_json::Value>, (Status, Json<serde_json::Value>)> {
+    require_permission(pool, &user, "manage_groups").await?;
+    let member_id = parse_user_id(user_id)?;
+    let client = get_client(pool).await?;
+
+    let group = find_group(&client, id)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| not_found("Group not found"))?;
+    require_grantable(pool, &user, &group.permissions).await?;
+
+    if client
+        .execute(
+            "DELETE FROM user_groups WHERE user_id = $1 AND group_id = $2",
+            &[&member_id, &id],
+        )
+        .await
+        .map_err(db_error)?
+        == 0
+    {
+        return Err(not_found("User is not a member of this group"));
+    }
+    record_audit(
+        pool,
+        &user,
+        AuditEvent::new("group.member.remove", "group", id).before(serde_json::json!({
+            "group": group.name,
+            "user_id": member_id,
+            "permissions": group.permissions,
+        })),
+    )
+    .await;
+    Ok(Json(
+        serde_json::json!({"message": "User removed from group"}),
+    ))
+}
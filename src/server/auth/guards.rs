@@ -1,11 +1,15 @@
 use deadpool_postgres::Pool;
-use jsonwebtoken::{Algorithm, DecodingKey, Validation, decode};
 use rocket::State;
 use rocket::http::Status;
 use rocket::request::{FromRequest, Outcome, Request};
+use std::net::IpAddr;
 use uuid::Uuid;
 
-use crate::models::{AuthError, Claims};
+use super::api_keys::{API_KEY_PREFIX, ApiKeyOwner, ApiKeyRejection, authenticate_api_key};
+use super::csrf::{csrf_verified, reject_csrf};
+use super::helpers::ACTIVE_USER_SQL;
+use super::keys::SigningKeys;
+use crate::models::{AuthError, Claims, Impersonator};
 
 /// Authenticated user attached to every protected request.
 #[derive(Debug, Clone)]
@@ -13,6 +17,67 @@ pub struct AuthenticatedUser {
     pub id: Uuid,
     pub username: String,
     pub role: String,
+    /// Permissions the request is limited to when it authenticated with an API key.
+    pub scopes: Option<Vec<String>>,
+    /// The admin behind the request when it carries an impersonation token.
+    pub impersonator: Option<Impersonator>,
+    pub client_ip: Option<IpAddr>,
+}
+
+impl AuthenticatedUser {
+    /// Whether the credential used for this request may exercise `permission` at all.
+    pub fn has_scope(&self, permission: &str) -> bool {
+        self.scopes
+            .as_ref()
+            .is_none_or(|scopes| scopes.iter().any(|scope| scope == permission))
+    }
+}
+
+async fn api_key_user(request: &Request<'_>, key: &str) -> Outcome<AuthenticatedUser, AuthError> {
+    let client = match request.guard::<&State<Pool>>().await {
+        Outcome::Success(pool) => match pool.get().await {
+            Ok(client) => client,
+            Err(_) => {
+                return Outcome::Error((Status::InternalServerError, AuthError::InvalidToken));
+            }
+        },
+        _ => return Outcome::Error((Status::InternalServerError, AuthError::InvalidToken)),
+    };
+
+    match authenticate_api_key(
+        &client,
+        key,
+        request.client_ip(),
+        request.method().as_str(),
+        request.uri().path().as_str(),
+    )
+    .await
+    {
+        // Keys without scopes predate /api access and stay limited to the Subsonic API.
+        Ok(ApiKeyOwner { scopes: None, .. }) => {
+            Outcome::Error((Status::Forbidden, AuthError::InvalidToken))
+        }
+        Ok(owner) => Outcome::Success(AuthenticatedUser {
+            id: owner.user_id,
+            username: owner.username,
+            role: owner.role,
+            scopes: owner.scopes,
+            impersonator: None,
+            client_ip: request.client_ip(),
+        }),
+        Err(ApiKeyRejection::Invalid) => {
+            Outcome::Error((Status::Unauthorized, AuthError::InvalidToken))
+        }
+        Err(ApiKeyRejection::Expired) => {
+            Outcome::Error((Status::Unauthorized, AuthError::ExpiredToken))
+        }
+        Err(ApiKeyRejection::AccountDisabled | ApiKeyRejection::IpNotAllowed) => {
+            Outcome::Error((Status::Forbidden, AuthError::InvalidToken))
+        }
+        Err(ApiKeyRejection::Database) => {
+            Outcome::Error((Status::InternalServerError, AuthError::InvalidToken))
+        }
+    }
 }
 
 #[rocket::async_trait]
@@ -20,32 +85,52 @@ impl<'r> FromRequest<'r> for AuthenticatedUser {
     type Error = AuthError;
 
     async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
-        let jwt_secret = match std::env::var("JWT_SECRET") {
-            Ok(s) => s,
-            Err(_) => {
-                eprintln!("JWT_SECRET not set");
-                return Outcome::Error((Status::InternalServerError, AuthError::InvalidToken));
-            }
+        if let Some(key) = request
+            .headers()
+            .get_one("Authorization")
+            .and_then(|h| h.strip_prefix("Bearer "))
+            .filter(|token| token.starts_with(API_KEY_PREFIX))
+        {
+            return api_key_user(request, key).await;
+        }
+
+        let (keys, pool) = match (
+            request.guard::<&State<SigningKeys>>().await,
+            request.guard::<&State<Pool>>().await,
+        ) {
+            (Outcome::Success(keys), Outcome::Success(pool)) => (keys, pool),
+            _ => return Outcome::Error((Status::InternalServerError, AuthError::InvalidToken)),
         };
 
-        // Try cookie first, then Authorization header
-        let token = request
+        // Try cookie first, then Authorization header. A cross-site page can make the
+        // browser send the cookie but cannot set the header, so only cookie-only
+        // requests need the CSRF check.
+        let bearer = request
+            .headers()
+            .get_one("Authorization")
+            .and_then(|h| h.strip_prefix("Bearer ").map(|s| s.to_string()));
+        let cookie_token = request
             .cookies()
             .get("accessToken")
-            .map(|c| c.value().to_string())
-            .or_else(|| {
-                request
-                    .headers()
-                    .get_one("Authorization")
-                    .and_then(|h| h.strip_prefix("Bearer ").map(|s| s.to_string()))
-            });
+            .map(|c| c.value().to_string());
+        if cookie_token.is_some() && bearer.is_none() && !csrf_verified(request) {
+            return reject_csrf(request, AuthError::CsrfMismatch);
+        }
+        let token = cookie_token.or(bearer);
 
         let token = match token {
             Some(t) => t,
             None => return Outcome::Error((Status::Unauthorized, AuthError::MissingToken)),
         };
 
-        match decode_jwt(&token, &jwt_secret) {
+        let mut decoded = decode_jwt(&token, keys);
+        if matches!(decoded, Err(AuthError::InvalidToken))
+            && keys.reload_for_unknown_kid(pool, &token).await
+        {
+            decoded = decode_jwt(&token, keys);
+        }
+
+        match decoded {
             Ok(claims) => {
                 let user_id = match Uuid::parse_str(&claims.sub) {
                     Ok(id) => id,
@@ -54,26 +139,36 @@ impl<'r> FromRequest<'r> for AuthenticatedUser {
                     }
                 };
 
-                // Verify the user still exists in the database
-                let valid = match request.guard::<&State<Pool>>().await {
-                    Outcome::Success(pool) => match pool.get().await {
-                        Ok(client) => client
-                            .query_one("SELECT 1 FROM users WHERE id = $1", &[&user_id])
-                            .await
-                            .is_ok(),
-                        Err(_) => false,
-                    },
-                    _ => false,
-                };
-
-                if !valid {
+                // Verify the user still exists, may sign in, and the token has not been revoked
+                if !token_holder_valid(pool, user_id, claims.ver).await {
                     return Outcome::Error((Status::Unauthorized, AuthError::InvalidToken));
                 }
 
+                // An impersonation token is only as good as the admin who started it.
+                let impersonator = match claims.act {
+                    Some(actor) => {
+                        let Ok(actor_id) = Uuid::parse_str(&actor.sub) else {
+                            return Outcome::Error((Status::Unauthorized, AuthError::InvalidToken));
+                        };
+                        if !token_holder_valid(pool, actor_id, actor.ver).await {
+                            return Outcome::Error((Status::Unauthorized, AuthError::InvalidToken));
+                        }
+                        Some(Impersonator {
+                            id: actor_id,
+                            username: actor.username,
+                            session_id: actor.sid,
+                        })
+                    }
+                    None => None,
+                };
+
                 Outcome::Success(AuthenticatedUser {
                     id: user_id,
                     username: claims.username,
                     role: claims.role,
+                    scopes: None,
+                    impersonator,
+                    client_ip: request.client_ip(),
                 })
             }
             Err(AuthError::ExpiredToken) => {
@@ -84,19 +179,26 @@ impl<'r> FromRequest<'r> for AuthenticatedUser {
     }
 }
 
-/// Decode and validate a JWT token.
-pub fn decode_jwt(token: &str, secret: &str) -> Result<Claims, AuthError> {
-    let token_data = decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(secret.as_bytes()),
-        &Validation::new(Algorithm::HS256),
-    )
-    .map_err(|err| match err.kind() {
-        jsonwebtoken::errors::ErrorKind::ExpiredSignature => AuthError::ExpiredToken,
-        _ => AuthError::InvalidToken,
-    })?;
+/// Whether `user_id` may still sign in and `token_version` has not been revoked.
+async fn token_holder_valid(pool: &Pool, user_id: Uuid, token_version: i32) -> bool {
+    match pool.get().await {
+        Ok(client) => client
+            .query_opt(
+                &format!(
+                    "SELECT 1 FROM users u
+                     WHERE u.id = $1 AND u.token_version = $2 AND {ACTIVE_USER_SQL}"
+                ),
+                &[&user_id, &token_version],
+            )
+            .await
+            .is_ok_and(|row| row.is_some()),
+        Err(_) => false,
+    }
+}
 
-    Ok(token_data.claims)
+/// Decode and validate a JWT token against the key its `kid` names.
+pub fn decode_jwt(token: &str, keys: &SigningKeys) -> Result<Claims, AuthError> {
+    keys.verify(token)
 }
 
 // Permission check at runtime
@@ -130,18 +232,94 @@ pub async fn check_permission(
         return Ok(true);
     }
 
+    // Effective permissions are the union of the role's and every group's.
     let row = client
         .query_one(
-            "SELECT COUNT(*)::int8 AS cnt
-             FROM users u
-             JOIN role_permissions rp ON u.role_id = rp.role_id
-             JOIN permissions p ON rp.permission_id = p.id
-             WHERE u.id = $1 AND p.name = $2",
+            "SELECT EXISTS (
+                 SELECT 1 FROM users u
+                 JOIN role_permissions rp ON u.role_id = rp.role_id
+                 JOIN permissions p ON rp.permission_id = p.id
+                 WHERE u.id = $1 AND p.name = $2
+                 UNION ALL
+                 SELECT 1 FROM user_groups ug
+                 JOIN group_permissions gp ON ug.group_id = gp.group_id
+                 JOIN permissions p ON gp.permission_id = p.id
+                 WHERE ug.user_id = $1 AND p.name = $2
+             ) AS granted",
             &[&user_id, &permission],
         )
         .await
         .map_err(|e| format!("DB query error: {e}"))?;
 
-    let count: i64 = row.get("cnt");
-    Ok(count > 0)
+    Ok(row.get("granted"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::{generate_impersonation_jwt, generate_jwt};
+    use crate::models::ActorClaim;
+
+    #[test]
+    fn decoded_claims_carry_the_token_version() {
+        let keys = SigningKeys::generated();
+        let user_id = Uuid::new_v4();
+        let token = generate_jwt(&user_id, "alice", "viewer", 7, &keys, 1)
+            .expect("token should be generated");
+        let claims = decode_jwt(&token, &keys).expect("token should decode");
+
+        assert_eq!(claims.sub, user_id.to_string());
+        assert_eq!(claims.ver, 7);
+    }
+
+    #[test]
+    fn impersonation_tokens_name_the_actor_and_plain_tokens_do_not() {
+        let keys = SigningKeys::generated();
+        let admin_id = Uuid::new_v4();
+        let token = generate_impersonation_jwt(
+            &Uuid::new_v4(),
+            "alice",
+            "viewer",
+            3,
+            ActorClaim {
+                sub: admin_id.to_string(),
+                username: "admin".to_owned(),
+                ver: 9,
+                sid: None,
+            },
+            &keys,
+            chrono::Duration::minutes(30),
+        )
+        .expect("token should be generated");
+        let actor = decode_jwt(&token, &keys)
+            .expect("token should decode")
+            .act
+            .expect("token should carry an actor");
+        assert_eq!(actor.sub, admin_id.to_string());
+        assert_eq!(actor.ver, 9);
+
+        let plain = generate_jwt(&admin_id, "admin", "admin", 9, &keys, 1)
+            .expect("token should be generated");
+        assert!(decode_jwt(&plain, &keys).unwrap().act.is_none());
+    }
+
+    #[test]
+    fn tokens_without_a_version_decode_as_version_zero() {
+        let keys = SigningKeys::generated();
+        let exp = (chrono::Utc::now().timestamp() + 3600) as usize;
+        let token = keys
+            .sign(&serde_json::json!({
+                "sub": Uuid::nil().to_string(),
+                "username": "alice",
+                "role": "viewer",
+                "exp": exp,
+                "iat": exp - 3600,
+            }))
+            .expect("token should encode");
+
+        assert_eq!(
+            decode_jwt(&token, &keys).map(|claims| claims.ver).ok(),
+            Some(0)
+        );
+    }
 }
@@ -3,37 +3,80 @@ use rocket::http::{Cookie, SameSite};
 use tokio_postgres::Row;
 use uuid::Uuid;
 
-use crate::models::{RoleWithPermissions, User};
-use crate::shared::{ApiError, bad_request, forbidden};
+use crate::models::{EffectivePermission, PermissionSource, RoleWithPermissions, User};
+use crate::shared::{ApiError, bad_request, forbidden, status_error};
 
-use super::guards::check_permission;
+use super::guards::{AuthenticatedUser, check_permission};
 
 pub(crate) async fn require_permission(
     pool: &deadpool_postgres::Pool,
-    user_id: Uuid,
+    user: &AuthenticatedUser,
     permission: &str,
 ) -> Result<(), ApiError> {
-    if has_permission(pool, user_id, permission).await {
+    if has_permission(pool, user, permission).await {
         Ok(())
     } else {
         Err(forbidden())
     }
 }
 
+/// Role permission check, narrowed to the key's scopes for API-key requests.
 pub(crate) async fn has_permission(
     pool: &deadpool_postgres::Pool,
-    user_id: Uuid,
+    user: &AuthenticatedUser,
     permission: &str,
 ) -> bool {
-    check_permission(pool, user_id, permission)
+    if !user.has_scope(permission) {
+        return false;
+    }
+    check_permission(pool, user.id, permission)
         .await
         .unwrap_or(false)
 }
 
+/// Reject API-key and impersonated requests on endpoints that manage credentials or sessions.
+pub(crate) fn require_session(user: &AuthenticatedUser) -> Result<(), ApiError> {
+    if user.scopes.is_some() || user.impersonator.is_some() {
+        return Err(forbidden());
+    }
+    Ok(())
+}
+
+/// Passwords stay out of reach of an admin acting as someone else.
+pub(crate) fn reject_impersonation(user: &AuthenticatedUser) -> Result<(), ApiError> {
+    if user.impersonator.is_some() {
+        return Err(status_error(
+            rocket::http::Status::Forbidden,
+            "Passwords cannot be changed while impersonating",
+        ));
+    }
+    Ok(())
+}
+
+/// SQL condition (on alias `u`) for accounts that may sign in.
+pub(crate) const ACTIVE_USER_SQL: &str =
+    "u.disabled_at IS NULL AND (u.expires_at IS NULL OR u.expires_at > NOW())";
+
 pub(crate) fn parse_user_id(value: &str) -> Result<Uuid, ApiError> {
     Uuid::parse_str(value).map_err(|_| bad_request("Invalid user ID"))
 }
 
+/// Trim an optional email address; blank clears it, anything else must look like `local@domain`.
+pub(crate) fn normalize_email(value: Option<&str>) -> Result<Option<String>, ApiError> {
+    let Some(email) = value.map(str::trim).filter(|email| !email.is_empty()) else {
+        return Ok(None);
+    };
+    let valid = email.len() <= 255
+        && !email.chars().any(char::is_whitespace)
+        && email
+            .split_once('@')
+            .is_some_and(|(local, domain)| !local.is_empty() && domain.contains('.'));
+    if !valid {
+        return Err(bad_request("Invalid email address"));
+    }
+    Ok(Some(email.to_owned()))
+}
+
 pub(crate) async fn find_user_by_id(
     client: &impl GenericClient,
     user_id: Uuid,
@@ -41,6 +84,7 @@ pub(crate) async fn find_user_by_id(
     client
         .query_opt(
             "SELECT u.id, u.username, u.password_hash, u.role_id, r.name as role_name,
+                    u.email, u.disabled_at, u.disabled_reason, u.expires_at,
                     u.created_at, u.updated_at
              FROM users u
              JOIN roles r ON u.role_id = r.id
@@ -57,6 +101,10 @@ pub(crate) fn row_to_user(row: &Row) -> User {
         password_hash: String::new(),
         role_id: row.get("role_id"),
         role_name: row.get("role_name"),
+        email: row.get("email"),
+        disabled_at: row.get("disabled_at"),
+        disabled_reason: row.get("disabled_reason"),
+        expires_at: row.get("expires_at"),
         created_at: row.get("created_at"),
         updated_at: row.get("updated_at"),
     }
@@ -90,6 +138,62 @@ pub(crate) async fn role_permissions(
     Ok(rows.iter().map(|row| row.get("name")).collect())
 }
 
+/// Every permission `user_id` holds, each listed with the role and groups granting it.
+///
+/// The admin role is reported as the source of every permission, matching `check_permission`.
+pub(crate) async fn effective_permissions(
+    client: &impl GenericClient,
+    user_id: Uuid,
+) -> Result<Vec<EffectivePermission>, tokio_postgres::Error> {
+    let rows = client
+        .query(
+            "SELECT p.name, 'role' AS kind, r.name AS source
+             FROM users u
+             JOIN roles r ON u.role_id = r.id
+             JOIN permissions p ON r.name = 'admin' OR EXISTS (
+                 SELECT 1 FROM role_permissions rp
+                 WHERE rp.role_id = r.id AND rp.permission_id = p.id
+             )
+             WHERE u.id = $1
+             UNION ALL
+             SELECT p.name, 'group' AS kind, g.name AS source
+             FROM user_groups ug
+             JOIN groups g ON ug.group_id = g.id
+             JOIN group_permissions gp ON gp.group_id = g.id
+             JOIN permissions p ON gp.permission_id = p.id
+             WHERE ug.user_id = $1
+             ORDER BY 1, 2 DESC, 3",
+            &[&user_id],
+        )
+        .await?;
+    Ok(merge_permission_sources(rows.iter().map(|row| {
+        let kind: String = row.get("kind");
+        (
+            row.get("name"),
+            if kind == "role" { "role" } else { "group" },
+            row.get("source"),
+        )
+    })))
+}
+
+/// Fold `(permission, kind, source)` rows sorted by permission into one entry per permission.
+fn merge_permission_sources(
+    rows: impl IntoIterator<Item = (String, &'static str, String)>,
+) -> Vec<EffectivePermission> {
+    let mut merged: Vec<EffectivePermission> = Vec::new();
+    for (name, kind, source) in rows {
+        let source = PermissionSource { kind, name: source };
+        match merged.last_mut() {
+            Some(last) if last.name == name => last.sources.push(source),
+            _ => merged.push(EffectivePermission {
+                name,
+                sources: vec![source],
+            }),
+        }
+    }
+    merged
+}
+
 pub(crate) async fn assign_role_permissions(
     client: &impl GenericClient,
     role_id: i32,
@@ -108,6 +212,34 @@ pub(crate) async fn assign_role_permissions(
     Ok(())
 }
 
+/// Invalidate every access token issued to `user_id` by bumping its token version.
+pub(crate) async fn bump_token_version(
+    client: &impl GenericClient,
+    user_id: Uuid,
+) -> Result<u64, tokio_postgres::Error> {
+    client
+        .execute(
+            "UPDATE users SET token_version = token_version + 1 WHERE id = $1",
+            &[&user_id],
+        )
+        .await
+}
+
+/// Sign `user_id` out everywhere: invalidate access tokens and revoke refresh sessions.
+pub(crate) async fn revoke_user_sessions(
+    client: &impl GenericClient,
+    user_id: Uuid,
+) -> Result<u64, tokio_postgres::Error> {
+    let updated = bump_token_version(client, user_id).await?;
+    client
+        .execute(
+            "UPDATE sessions SET revoked = TRUE WHERE user_id = $1 AND NOT revoked",
+            &[&user_id],
+        )
+        .await?;
+    Ok(updated)
+}
+
 pub(crate) fn make_access_cookie(token: String, expiration_hours: i64) -> Cookie<'static> {
     make_auth_cookie("accessToken", token, "/", expiration_hours)
 }
@@ -129,3 +261,38 @@ fn make_auth_cookie(
         .max_age(rocket::time::Duration::hours(expiration_hours))
         .into()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn email_addresses_are_trimmed_and_blank_clears() {
+        assert_eq!(
+            normalize_email(Some("  ana@example.com ")).unwrap(),
+            Some("ana@example.com".to_owned())
+        );
+        assert_eq!(normalize_email(Some("   ")).unwrap(), None);
+        assert_eq!(normalize_email(None).unwrap(), None);
+        assert!(normalize_email(Some("not-an-address")).is_err());
+        assert!(normalize_email(Some("a b@example.com")).is_err());
+    }
+
+    #[test]
+    fn permission_sources_are_merged_per_permission() {
+        let merged = merge_permission_sources([
+            ("download".to_owned(), "role", "editor".to_owned()),
+            ("download".to_owned(), "group", "family".to_owned()),
+            ("upload".to_owned(), "group", "family".to_owned()),
+        ]);
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].name, "download");
+        let sources: Vec<_> = merged[0]
+            .sources
+            .iter()
+            .map(|source| (source.kind, source.name.as_str()))
+            .collect();
+        assert_eq!(sources, [("role", "editor"), ("group", "family")]);
+        assert_eq!(merged[1].sources.len(), 1);
+    }
+}
@@ -0,0 +1,374 @@
+use super::*;
+
+use crate::audit::{AuditActor, AuditEvent, record_audit_in};
+use crate::models::{ActorClaim, LoginResponse};
+use chrono::DateTime;
+use deadpool_postgres::Transaction;
+use rocket::http::{CookieJar, Status};
+use std::net::IpAddr;
+use uuid::Uuid;
+
+const IMPERSONATION_MINUTES: i64 = 30;
+
+/// How an impersonation session ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ImpersonationEnd {
+    /// The admin stopped impersonating or signed out.
+    Stop,
+    /// A refresh swapped the token for the admin's own before it expired.
+    Refresh,
+    /// The token expired at the given time.
+    Expired(DateTime<Utc>),
+}
+
+impl ImpersonationEnd {
+    fn action(self) -> &'static str {
+        match self {
+            Self::Stop => "stop",
+            Self::Refresh => "refresh",
+            Self::Expired(_) => "expire",
+        }
+    }
+
+    fn audit_action(self) -> &'static str {
+        match self {
+            Self::Stop => "impersonation.stop",
+            Self::Refresh => "impersonation.refresh",
+            Self::Expired(_) => "impersonation.expire",
+        }
+    }
+
+    fn at(self) -> Option<DateTime<Utc>> {
+        match self {
+            Self::Expired(at) => Some(at),
+            Self::Stop | Self::Refresh => None,
+        }
+    }
+
+    /// The end a refresh records for an impersonation token expiring at `expires_at`.
+    fn on_refresh(expires_at: DateTime<Utc>, now: DateTime<Utc>) -> Self {
+        if expires_at <= now {
+            Self::Expired(expires_at)
+        } else {
+            Self::Refresh
+        }
+    }
+}
+
+fn audit_error(error: String) -> ApiError {
+    eprintln!("Impersonation: failed to write the audit event: {error}");
+    server_error()
+}
+
+/// Record the start of an impersonation session, with the admin as the audit actor.
+async fn record_impersonation_start(
+    transaction: &Transaction<'_>,
+    session_id: Uuid,
+    impersonator: (Uuid, &str),
+    target: (Uuid, &str),
+    expires_at: DateTime<Utc>,
+    ip: Option<IpAddr>,
+) -> Result<(), ApiError> {
+    transaction
+        .execute(
+            "INSERT INTO impersonation_events
+                 (session_id, impersonator_user_id, impersonator_username, target_user_id,
+                  target_username, action, ip_address, expires_at)
+             VALUES ($1, $2, $3, $4, $5, 'start', $6, $7)",
+            &[
+                &session_id,
+                &impersonator.0,
+                &impersonator.1,
+                &target.0,
+                &target.1,
+                &ip.map(|ip| ip.to_string()),
+                &expires_at,
+            ],
+        )
+        .await
+        .map_err(db_error)?;
+    record_audit_in(
+        transaction,
+        AuditActor::user(impersonator.0, impersonator.1).with_ip(ip),
+        AuditEvent::new("impersonation.start", "user", target.0).after(serde_json::json!({
+            "session_id": session_id,
+            "username": target.1,
+            "expires_at": expires_at,
+        })),
+    )
+    .await
+    .map_err(audit_error)
+}
+
+/// Record the end of an impersonation session once; tokens issued before sessions were
+/// tracked carry no `session_id` and are recorded every time.
+async fn record_impersonation_end(
+    transaction: &Transaction<'_>,
+    session_id: Option<Uuid>,
+    impersonator: (Uuid, &str),
+    target: (Uuid, &str),
+    end: ImpersonationEnd,
+    ip: Option<IpAddr>,
+) -> Result<(), ApiError> {
+    let recorded = transaction
+        .execute(
+            "INSERT INTO impersonation_events
+                 (session_id, impersonator_user_id, impersonator_username, target_user_id,
+                  target_username, action, ip_address, created_at)
+             SELECT $1, $2, $3, $4, $5, $6, $7, COALESCE($8, NOW())
+             WHERE $1::UUID IS NULL OR NOT EXISTS (
+                 SELECT 1 FROM impersonation_events
+                 WHERE session_id = $1 AND action <> 'start'
+             )",
+            &[
+                &session_id,
+                &impersonator.0,
+                &impersonator.1,
+                &target.0,
+                &target.1,
+                &end.action(),
+                &ip.map(|ip| ip.to_string()),
+                &end.at(),
+            ],
+        )
+        .await
+        .map_err(db_error)?;
+    if recorded == 0 {
+        return Ok(());
+    }
+    record_audit_in(
+        transaction,
+        AuditActor::user(impersonator.0, impersonator.1).with_ip(ip),
+        AuditEvent::new(end.audit_action(), "user", target.0).before(serde_json::json!({
+            "session_id": session_id,
+            "username": target.1,
+        })),
+    )
+    .await
+    .map_err(audit_error)?;
+    eprintln!(
+        "Impersonation: {} stopped acting as {} ({})",
+        impersonator.1,
+        target.1,
+        end.action()
+    );
+    Ok(())
+}
+
+/// Record that a request ended the impersonation it was made under, if any.
+pub(crate) async fn end_impersonation(
+    client: &mut deadpool_postgres::Client,
+    user: &AuthenticatedUser,
+    end: ImpersonationEnd,
+) -> Result<(), ApiError> {
+    let Some(impersonator) = &user.impersonator else {
+        return Ok(());
+    };
+    let transaction = client.transaction().await.map_err(db_error)?;
+    record_impersonation_end(
+        &transaction,
+        impersonator.session_id,
+        (impersonator.id, &impersonator.username),
+        (user.id, &user.username),
+        end,
+        user.client_ip,
+    )
+    .await?;
+    transaction.commit().await.map_err(db_error)
+}
+
+/// Record the end of the impersonation carried by `access_token` when a refresh for the
+/// admin `session_user_id` replaces it, noting whether the token had already expired.
+pub(crate) async fn end_impersonation_on_refresh(
+    transaction: &Transaction<'_>,
+    keys: &SigningKeys,
+    access_token: &str,
+    session_user_id: Uuid,
+    ip: Option<IpAddr>,
+) -> Result<(), ApiError> {
+    let Ok(claims) = keys.verify_ignoring_expiry(access_token) else {
+        return Ok(());
+    };
+    let (Some(actor), Ok(target_id)) = (claims.act, Uuid::parse_str(&claims.sub)) else {
+        return Ok(());
+    };
+    if actor.sub != session_user_id.to_string() {
+        return Ok(());
+    }
+    let expires_at = DateTime::from_timestamp(claims.exp as i64, 0).unwrap_or_else(Utc::now);
+    record_impersonation_end(
+        transaction,
+        actor.sid,
+        (session_user_id, &actor.username),
+        (target_id, &claims.username),
+        ImpersonationEnd::on_refresh(expires_at, Utc::now()),
+        ip,
+    )
+    .await
+}
+
+/// POST /api/auth/impersonation/<id> - Act as a lower-ranked user for a short while
+///
+/// The access cookie is swapped for a token naming the admin as actor; the admin's
+/// refresh session is left alone, so refreshing or stopping returns to their own account.
+#[post("/auth/impersonation/<id>")]
+pub async fn start_impersonation(
+    pool: &State<Pool>,
+    keys: &State<SigningKeys>,
+    jar: &CookieJar<'_>,
+    user: AuthenticatedUser,
+    id: &str,
+) -> Result<Json<LoginResponse>, (Status, Json<serde_json::Value>)> {
+    require_session(&user)?;
+    require_permission(pool, &user, "impersonate_users").await?;
+    let target_id = parse_user_id(id)?;
+    if target_id == user.id {
+        return Err(bad_request("Cannot impersonate yourself"));
+    }
+
+    let mut client = get_client(pool).await?;
+    let actor = client
+        .query_one(
+            "SELECT u.token_version, r.position FROM users u
+             JOIN roles r ON r.id = u.role_id WHERE u.id = $1",
+            &[&user.id],
+        )
+        .await
+        .map_err(db_error)?;
+    let target = client
+        .query_opt(
+            &format!(
+                "SELECT u.username, u.token_version, r.name AS role_name, r.position,
+                        ({ACTIVE_USER_SQL}) AS active
+                 FROM users u JOIN roles r ON r.id = u.role_id WHERE u.id = $1"
+            ),
+            &[&target_id],
+        )
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| not_found("User not found"))?;
+
+    if target.get::<_, i32>("position") <= actor.get::<_, i32>("position") {
+        return Err(status_error(
+            Status::Forbidden,
+            "Only users with a lower-ranked role can be impersonated",
+        ));
+    }
+    if !target.get::<_, bool>("active") {
+        return Err(conflict("Account is disabled or has expired"));
+    }
+
+    let target_username: String = target.get("username");
+    let session_id = Uuid::new_v4();
+    let lifetime = Duration::minutes(IMPERSONATION_MINUTES);
+    let expires_at = Utc::now() + lifetime;
+
+    // The session is recorded before its token exists, so no token is issued unrecorded.
+    let transaction = client.transaction().await.map_err(db_error)?;
+    record_impersonation_start(
+        &transaction,
+        session_id,
+        (user.id, &user.username),
+        (target_id, &target_username),
+        expires_at,
+        user.client_ip,
+    )
+    .await?;
+    let profile = find_user_by_id(&transaction, target_id)
+        .await
+        .map_err(db_error)?
+        .map(|row| row_to_user(&row))
+        .ok_or_else(|| not_found("User not found"))?;
+    let token = generate_impersonation_jwt(
+        &target_id,
+        &target_username,
+        target.get("role_name"),
+        target.get("token_version"),
+        ActorClaim {
+            sub: user.id.to_string(),
+            username: user.username.clone(),
+            ver: actor.get("token_version"),
+            sid: Some(session_id),
+        },
+        keys,
+        lifetime,
+    )
+    .map_err(|_| server_error())?;
+    transaction.commit().await.map_err(db_error)?;
+    eprintln!(
+        "Impersonation: {} started acting as {target_username}",
+        user.username
+    );
+
+    jar.add(make_access_cookie(token, 1));
+    Ok(Json(LoginResponse { user: profile }))
+}
+
+/// DELETE /api/auth/impersonation - Stop impersonating and return to the admin's account
+#[delete("/auth/impersonation")]
+pub async fn stop_impersonation(
+    pool: &State<Pool>,
+    keys: &State<SigningKeys>,
+    jar: &CookieJar<'_>,
+    user: AuthenticatedUser,
+) -> Result<Json<LoginResponse>, (Status, Json<serde_json::Value>)> {
+    let impersonator = user
+        .impersonator
+        .as_ref()
+        .ok_or_else(|| bad_request("Not impersonating"))?;
+
+    let mut client = get_client(pool).await?;
+    let profile = find_user_by_id(&client, impersonator.id)
+        .await
+        .map_err(db_error)?
+        .map(|row| row_to_user(&row))
+        .ok_or_else(|| unauthorized("User not found"))?;
+    let token_version: i32 = client
+        .query_one(
+            "SELECT token_version FROM users WHERE id = $1",
+            &[&profile.id],
+        )
+        .await
+        .map_err(db_error)?
+        .get("token_version");
+
+    let expiration_hours = access_token_hours();
+    let token = generate_jwt(
+        &profile.id,
+        &profile.username,
+        &profile.role_name,
+        token_version,
+        keys,
+        expiration_hours,
+    )
+    .map_err(|_| server_error())?;
+
+    end_impersonation(&mut client, &user, ImpersonationEnd::Stop).await?;
+
+    jar.add(make_access_cookie(token, expiration_hours));
+    Ok(Json(LoginResponse { user: profile }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refresh_after_expiry_records_the_expiry() {
+        let now = Utc::now();
+        let earlier = now - Duration::minutes(5);
+        assert_eq!(
+            ImpersonationEnd::on_refresh(earlier, now),
+            ImpersonationEnd::Expired(earlier)
+        );
+        assert_eq!(
+            earlier,
+            ImpersonationEnd::Expired(earlier).at().unwrap_or(now)
+        );
+        assert_eq!(
+            ImpersonationEnd::on_refresh(now + Duration::minutes(5), now),
+            ImpersonationEnd::Refresh
+        );
+        assert_eq!(ImpersonationEnd::Refresh.at(), None);
+    }
+}
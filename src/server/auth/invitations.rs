@@ -0,0 +1,253 @@
+use super::*;
+
+use crate::models::{
+    AcceptInvitationRequest, CreateInvitationRequest, CreatedInvitation, Invitation, LoginResponse,
+    PublicInvitationStatus,
+};
+use rocket::http::{CookieJar, Status};
+use uuid::Uuid;
+
+const DEFAULT_INVITATION_HOURS: i64 = 72;
+const MAX_INVITATION_HOURS: i64 = 24 * 30;
+
+fn row_to_invitation(row: &tokio_postgres::Row) -> Invitation {
+    Invitation {
+        id: row.get("id"),
+        role_name: row.get("role_name"),
+        role_display_name: row.get("role_display_name"),
+        created_by_user_id: row.get("created_by_user_id"),
+        created_by_username: row.get("created_by_username"),
+        max_uses: row.get("max_uses"),
+        use_count: row.get("use_count"),
+        expires_at: row.get("expires_at"),
+        created_at: row.get("created_at"),
+    }
+}
+
+const INVITATION_COLUMNS: &str = "i.id, r.name AS role_name, r.display_name AS role_display_name,
+     i.created_by_user_id, u.username AS created_by_username,
+     i.max_uses, i.use_count, i.expires_at, i.created_at";
+
+/// POST /api/invitations - Create an invitation link for a role below the caller's own.
+#[post("/invitations", data = "<request>")]
+pub async fn create_invitation(
+    pool: &State<Pool>,
+    user: AuthenticatedUser,
+    request: Json<CreateInvitationRequest>,
+) -> Result<Json<CreatedInvitation>, (Status, Json<serde_json::Value>)> {
+    require_permission(pool, &user, "manage_invitations").await?;
+
+    let expires_in_hours = request.expires_in_hours.unwrap_or(DEFAULT_INVITATION_HOURS);
+    if !(1..=MAX_INVITATION_HOURS).contains(&expires_in_hours) {
+        return Err(bad_request(&format!(
+            "Invitations must expire within 1 to {MAX_INVITATION_HOURS} hours"
+        )));
+    }
+    let max_uses = request.max_uses.unwrap_or(1);
+    if max_uses < 1 {
+        return Err(bad_request("Invitations must allow at least one use"));
+    }
+
+    let client = get_client(pool).await?;
+    let role_name = request.role_name.as_deref().unwrap_or("viewer");
+    let role = client
+        .query_opt(
+            "SELECT id, position FROM roles WHERE name = $1",
+            &[&role_name],
+        )
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| bad_request(&format!("Role '{role_name}' not found")))?;
+    let actor = client
+        .query_one(
+            "SELECT r.name, r.position FROM users u JOIN roles r ON r.id = u.role_id
+             WHERE u.id = $1",
+            &[&user.id],
+        )
+        .await
+        .map_err(db_error)?;
+    let actor_is_admin = actor.get::<_, String>("name") == "admin";
+    if !actor_is_admin && role.get::<_, i32>("position") <= actor.get::<_, i32>("position") {
+        return Err(forbidden());
+    }
+
+    let role_id: i32 = role.get("id");
+    let token = random_hex::<32>();
+    let token_hash = sha256_hex(&token);
+    let expires_at = Utc::now() + Duration::hours(expires_in_hours);
+    let id: Uuid = client
+        .query_one(
+            "INSERT INTO invitations (token_hash, role_id, created_by_user_id, max_uses, expires_at)
+             VALUES ($1, $2, $3, $4, $5)
+             RETURNING id",
+            &[&token_hash, &role_id, &user.id, &max_uses, &expires_at],
+        )
+        .await
+        .map_err(db_error)?
+        .get("id");
+    let row = client
+        .query_one(
+            &format!(
+                "SELECT {INVITATION_COLUMNS}
+                 FROM invitations i
+                 JOIN roles r ON r.id = i.role_id
+                 JOIN users u ON u.id = i.created_by_user_id
+                 WHERE i.id = $1"
+            ),
+            &[&id],
+        )
+        .await
+        .map_err(db_error)?;
+
+    Ok(Json(CreatedInvitation {
+        invitation: row_to_invitation(&row),
+        token,
+    }))
+}
+
+/// GET /api/invitations - List outstanding invitations.
+#[get("/invitations")]
+pub async fn list_invitations(
+    pool: &State<Pool>,
+    user: AuthenticatedUser,
+) -> Result<Json<Vec<Invitation>>, (Status, Json<serde_json::Value>)> {
+    require_permission(pool, &user, "manage_invitations").await?;
+
+    let client = get_client(pool).await?;
+    let rows = client
+        .query(
+            &format!(
+                "SELECT {INVITATION_COLUMNS}
+                 FROM invitations i
+                 JOIN roles r ON r.id = i.role_id
+                 JOIN users u ON u.id = i.created_by_user_id
+                 WHERE i.expires_at > NOW() AND i.use_count < i.max_uses
+                 ORDER BY i.created_at DESC"
+            ),
+            &[],
+        )
+        .await
+        .map_err(db_error)?;
+
+    Ok(Json(rows.iter().map(row_to_invitation).collect()))
+}
+
+/// DELETE /api/invitations/<id> - Revoke an invitation. Accounts it already created are kept.
+#[delete("/invitations/<id>")]
+pub async fn revoke_invitation(
+    pool: &State<Pool>,
+    user: AuthenticatedUser,
+    id: String,
+) -> Result<Json<serde_json::Value>, (Status, Json<serde_json::Value>)> {
+    require_permission(pool, &user, "manage_invitations").await?;
+
+    let id = Uuid::parse_str(&id).map_err(|_| bad_request("Invalid invitation ID"))?;
+    let client = get_client(pool).await?;
+    if client
+        .execute("DELETE FROM invitations WHERE id = $1", &[&id])
+        .await
+        .map_err(db_error)?
+        == 0
+    {
+        return Err(not_found("Invitation not found"));
+    }
+
+    Ok(Json(serde_json::json!({"success": true})))
+}
+
+/// GET /api/public/invitations/<token> - Validate an invitation before sign-up.
+#[get("/public/invitations/<token>")]
+pub async fn get_public_invitation(
+    pool: &State<Pool>,
+    token: &str,
+) -> Result<Json<PublicInvitationStatus>, (Status, Json<serde_json::Value>)> {
+    let token_hash = sha256_hex(token);
+    let client = get_client(pool).await?;
+    let row = client
+        .query_opt(
+            "SELECT r.display_name, i.expires_at, i.max_uses - i.use_count AS remaining_uses
+             FROM invitations i
+             JOIN roles r ON r.id = i.role_id
+             WHERE i.token_hash = $1 AND i.expires_at > NOW() AND i.use_count < i.max_uses",
+            &[&token_hash],
+        )
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| not_found("Invitation is invalid or has expired"))?;
+
+    Ok(Json(PublicInvitationStatus {
+        role_display_name: row.get("display_name"),
+        expires_at: row.get("expires_at"),
+        remaining_uses: row.get("remaining_uses"),
+    }))
+}
+
+/// POST /api/public/invitations/<token> - Create an account from an invitation and sign in.
+#[post("/public/invitations/<token>", data = "<request>")]
+pub async fn accept_invitation(
+    pool: &State<Pool>,
+    keys: &State<SigningKeys>,
+    jar: &CookieJar<'_>,
+    token: &str,
+    request: Json<AcceptInvitationRequest>,
+) -> Result<Json<LoginResponse>, (Status, Json<serde_json::Value>)> {
+    let username = request.username.trim();
+    if username.is_empty() || request.password.is_empty() {
+        return Err(bad_request("Username and password are required"));
+    }
+    PasswordPolicy::from_env()
+        .validate(&request.password)
+        .await?;
+    let password_hash = hash_password(&request.password).map_err(|_| server_error())?;
+
+    let token_hash = sha256_hex(token);
+    let mut client = get_client(pool).await?;
+    let transaction = client.transaction().await.map_err(db_error)?;
+    let invitation = transaction
+        .query_opt(
+            "SELECT id, role_id FROM invitations
+             WHERE token_hash = $1 AND expires_at > NOW() AND use_count < max_uses
+             FOR UPDATE",
+            &[&token_hash],
+        )
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| not_found("Invitation is invalid or has expired"))?;
+    let invitation_id: Uuid = invitation.get("id");
+    let role_id: i32 = invitation.get("role_id");
+
+    if transaction
+        .query_opt("SELECT id FROM users WHERE username = $1", &[&username])
+        .await
+        .map_err(db_error)?
+        .is_some()
+    {
+        return Err(conflict("Username already exists"));
+    }
+
+    let user_id = Uuid::new_v4();
+    transaction
+        .execute(
+            "INSERT INTO users (id, username, password_hash, role_id) VALUES ($1, $2, $3, $4)",
+            &[&user_id, &username, &password_hash, &role_id],
+        )
+        .await
+        .map_err(db_error)?;
+    transaction
+        .execute(
+            "UPDATE invitations SET use_count = use_count + 1 WHERE id = $1",
+            &[&invitation_id],
+        )
+        .await
+        .map_err(db_error)?;
+
+    let user = find_user_by_id(&transaction, user_id)
+        .await
+        .map_err(db_error)?
+        .map(|row| row_to_user(&row))
+        .ok_or_else(server_error)?;
+    super::login::issue_tokens(&transaction, jar, keys, &user, Uuid::new_v4()).await?;
+    transaction.commit().await.map_err(db_error)?;
+
+    Ok(Json(LoginResponse { user }))
+}
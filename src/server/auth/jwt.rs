@@ -1,12 +1,11 @@
 use super::*;
 
-use crate::models::{AuthError, Claims};
+use crate::models::{ActorClaim, AuthError, Claims};
 use argon2::{
     Argon2,
     password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString, rand_core::OsRng},
 };
 use chrono::{Duration, Utc};
-use jsonwebtoken::{EncodingKey, Header, encode};
 use uuid::Uuid;
 
 const ROLE_POSITION_LOCK: i64 = 1_976_101;
@@ -39,15 +38,65 @@ pub(crate) fn verify_password(password: &str, hash: &str) -> Result<bool, AuthEr
 
 // JWT generation
 
+/// Access token lifetime from `JWT_EXPIRATION_HOURS`, 24 by default.
+pub(crate) fn access_token_hours() -> i64 {
+    std::env::var("JWT_EXPIRATION_HOURS")
+        .ok()
+        .and_then(|hours| hours.parse().ok())
+        .unwrap_or(24)
+}
+
 pub(crate) fn generate_jwt(
     user_id: &Uuid,
     username: &str,
     role: &str,
-    secret: &str,
+    token_version: i32,
+    keys: &SigningKeys,
     expiration_hours: i64,
+) -> Result<String, AuthError> {
+    sign_jwt(
+        user_id,
+        username,
+        role,
+        token_version,
+        None,
+        keys,
+        Duration::hours(expiration_hours),
+    )
+}
+
+/// Short-lived access token for `actor` acting as the given user.
+pub(crate) fn generate_impersonation_jwt(
+    user_id: &Uuid,
+    username: &str,
+    role: &str,
+    token_version: i32,
+    actor: ActorClaim,
+    keys: &SigningKeys,
+    lifetime: Duration,
+) -> Result<String, AuthError> {
+    sign_jwt(
+        user_id,
+        username,
+        role,
+        token_version,
+        Some(actor),
+        keys,
+        lifetime,
+    )
+}
+
+fn sign_jwt(
+    user_id: &Uuid,
+    username: &str,
+    role: &str,
+    token_version: i32,
+    act: Option<ActorClaim>,
+    keys: &SigningKeys,
+    lifetime: Duration,
 ) -> Result<String, AuthError> {
     let expiration = Utc::now()
-        .checked_add_signed(Duration::hours(expiration_hours))
+        .checked_add_signed(lifetime)
         .ok_or(AuthError::TokenGenerationError)?
         .timestamp() as usize;
 
@@ -55,16 +104,13 @@ pub(crate) fn generate_jwt(
         sub: user_id.to_string(),
         username: username.to_string(),
         role: role.to_string(),
+        ver: token_version,
+        act,
         exp: expiration,
         iat: Utc::now().timestamp() as usize,
     };
 
-    encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(secret.as_bytes()),
-    )
-    .map_err(|_| AuthError::TokenGenerationError)
+    keys.sign(&claims)
 }
 
 // Refresh token helpers
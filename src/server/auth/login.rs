@@ -1,25 +1,30 @@
 use super::*;
 
-use crate::models::{LoginRequest, LoginResponse, LogoutResponse};
+use crate::models::{LoginRequest, LoginResponse, LogoutResponse, MeResponse};
+use deadpool_postgres::GenericClient;
 use rocket::http::{Cookie, CookieJar, Status};
+use std::net::IpAddr;
 use uuid::Uuid;
 
-async fn issue_tokens(
-    client: &deadpool_postgres::Object,
+pub(crate) async fn issue_tokens(
+    client: &impl GenericClient,
     jar: &CookieJar<'_>,
+    keys: &SigningKeys,
     user: &User,
-    fail_on_session_error: bool,
+    family_id: Uuid,
 ) -> Result<(), ApiError> {
-    let jwt_secret = std::env::var("JWT_SECRET").unwrap_or_default();
-    let expiration_hours = std::env::var("JWT_EXPIRATION_HOURS")
-        .unwrap_or_else(|_| "24".to_string())
-        .parse()
-        .unwrap_or(24);
+    let expiration_hours = access_token_hours();
+    let token_version: i32 = client
+        .query_one("SELECT token_version FROM users WHERE id = $1", &[&user.id])
+        .await
+        .map_err(db_error)?
+        .get("token_version");
     let access_token = generate_jwt(
         &user.id,
         &user.username,
         &user.role_name,
-        &jwt_secret,
+        token_version,
+        keys,
         expiration_hours,
     )
     .map_err(|_| server_error())?;
@@ -27,21 +32,21 @@ async fn issue_tokens(
     let token_hash = sha256_hex(&refresh_token);
     let expires_at = Utc::now() + Duration::hours(expiration_hours * 24);
 
-    if let Err(error) = client
+    client
         .execute(
-            "INSERT INTO sessions (user_id, token_hash, expires_at) VALUES ($1, $2, $3)",
-            &[&user.id, &token_hash, &expires_at],
+            "INSERT INTO sessions (user_id, token_hash, expires_at, family_id)
+             VALUES ($1, $2, $3, $4)",
+            &[&user.id, &token_hash, &expires_at, &family_id],
         )
         .await
-    {
-        eprintln!("Failed to store refresh token: {error}");
-        if fail_on_session_error {
-            return Err(server_error());
-        }
-    }
+        .map_err(|error| {
+            eprintln!("Failed to store refresh token: {error}");
+            server_error()
+        })?;
 
     jar.add(make_access_cookie(access_token, expiration_hours));
     jar.add(make_refresh_cookie(refresh_token, expiration_hours));
+    jar.add(new_csrf_cookie());
     Ok(())
 }
 
@@ -49,6 +54,7 @@ async fn issue_tokens(
 #[post("/auth/login", data = "<login>")]
 pub async fn login(
     pool: &State<Pool>,
+    keys: &State<SigningKeys>,
     jar: &CookieJar<'_>,
     login: Json<LoginRequest>,
 ) -> Result<Json<LoginResponse>, (Status, Json<serde_json::Value>)> {
@@ -60,11 +66,15 @@ pub async fn login(
 
     let row = client
         .query_opt(
-            "SELECT u.id, u.username, u.password_hash, u.role_id, r.name as role_name,
-                    u.created_at, u.updated_at
-             FROM users u
-             JOIN roles r ON u.role_id = r.id
-             WHERE u.username = $1",
+            &format!(
+                "SELECT u.id, u.username, u.password_hash, u.role_id, r.name as role_name,
+                        u.email, u.disabled_at, u.disabled_reason, u.expires_at,
+                        u.password_changed_at, u.created_at, u.updated_at,
+                        ({ACTIVE_USER_SQL}) AS active
+                 FROM users u
+                 JOIN roles r ON u.role_id = r.id
+                 WHERE u.username = $1"
+            ),
             &[&login.username],
         )
         .await
@@ -79,8 +89,29 @@ pub async fn login(
         Err(_) => return Err(server_error()),
     }
 
+    if !row.get::<_, bool>("active") {
+        return Err(status_error(
+            Status::Forbidden,
+            "Account is disabled or has expired",
+        ));
+    }
+
     let user = row_to_user(&row);
-    issue_tokens(&client, jar, &user, true).await?;
+
+    // An expired password only buys a short-lived reset link, not a session.
+    if PasswordPolicy::from_env().is_expired(row.get("password_changed_at")) {
+        let reset = create_reset_token(&client, user.id, None, Duration::minutes(15))
+            .await
+            .map_err(db_error)?;
+        return Err(structured_error(
+            Status::Forbidden,
+            "password_expired",
+            "Password has expired and must be changed",
+            serde_json::json!({"reset_token": reset.token}),
+        ));
+    }
+
+    issue_tokens(&client, jar, keys, &user, Uuid::new_v4()).await?;
 
     Ok(Json(LoginResponse { user }))
 }
@@ -92,11 +123,10 @@ pub async fn logout(
     jar: &CookieJar<'_>,
     user: AuthenticatedUser,
 ) -> Json<LogoutResponse> {
-    if let Some(refresh_token) = jar.get("refresh_token") {
-        let token_hash = sha256_hex(refresh_token.value());
-
-        match get_client(pool).await {
-            Ok(client) => {
+    match get_client(pool).await {
+        Ok(mut client) => {
+            if let Some(refresh_token) = jar.get("refreshToken") {
+                let token_hash = sha256_hex(refresh_token.value());
                 if let Err(e) = client
                     .execute(
                         "DELETE FROM sessions WHERE user_id = $1 AND token_hash = $2",
@@ -107,24 +137,54 @@ pub async fn logout(
                     eprintln!("Failed to delete session during logout: {e}");
                 }
             }
-            Err(_) => eprintln!("Failed to get DB connection during logout"),
+            // Clearing the access cookie also ends an impersonation.
+            if end_impersonation(&mut client, &user, ImpersonationEnd::Stop)
+                .await
+                .is_err()
+            {
+                eprintln!("Failed to record the end of an impersonation during logout");
+            }
         }
+        Err(_) => eprintln!("Failed to get DB connection during logout"),
     }
 
     jar.remove(Cookie::build("accessToken").path("/"));
     jar.remove(Cookie::build("refreshToken").path("/api/auth"));
+    jar.remove(Cookie::build(CSRF_COOKIE).path("/"));
 
     Json(LogoutResponse {
         message: "Logged out".to_string(),
     })
 }
 
-/// GET /api/auth/me
+/// POST /api/auth/logout-all - Sign out of every session, including this one
+#[post("/auth/logout-all")]
+pub async fn logout_all(
+    pool: &State<Pool>,
+    jar: &CookieJar<'_>,
+    user: AuthenticatedUser,
+) -> Result<Json<LogoutResponse>, (Status, Json<serde_json::Value>)> {
+    require_session(&user)?;
+    let client = get_client(pool).await?;
+    revoke_user_sessions(&client, user.id)
+        .await
+        .map_err(db_error)?;
+
+    jar.remove(Cookie::build("accessToken").path("/"));
+    jar.remove(Cookie::build("refreshToken").path("/api/auth"));
+    jar.remove(Cookie::build(CSRF_COOKIE).path("/"));
+
+    Ok(Json(LogoutResponse {
+        message: "Logged out of all sessions".to_string(),
+    }))
+}
+
+/// GET /api/auth/me - The current user with effective permissions and their sources
 #[get("/auth/me")]
 pub async fn me(
     pool: &State<Pool>,
     user: AuthenticatedUser,
-) -> Result<Json<LoginResponse>, (Status, Json<serde_json::Value>)> {
+) -> Result<Json<MeResponse>, (Status, Json<serde_json::Value>)> {
     let client = get_client(pool).await?;
 
     let user_obj = find_user_by_id(&client, user.id)
@@ -132,38 +192,77 @@ pub async fn me(
         .map_err(db_error)?
         .map(|row| row_to_user(&row))
         .ok_or_else(|| unauthorized("User not found"))?;
+    let permissions = effective_permissions(&client, user.id)
+        .await
+        .map_err(db_error)?;
 
-    Ok(Json(LoginResponse { user: user_obj }))
+    Ok(Json(MeResponse {
+        user: user_obj,
+        permissions,
+        impersonated_by: user.impersonator,
+    }))
 }
 
-/// POST /api/auth/refresh
+/// POST /api/auth/refresh - Rotate the refresh token.
+///
+/// Each refresh token is single-use. Presenting one that was already rotated
+/// means it leaked, so every session descended from the same login is revoked.
 #[post("/auth/refresh")]
 pub async fn refresh(
     pool: &State<Pool>,
+    keys: &State<SigningKeys>,
     jar: &CookieJar<'_>,
+    ip: Option<IpAddr>,
+    _csrf: CsrfVerified,
 ) -> Result<Json<LoginResponse>, (Status, Json<serde_json::Value>)> {
     let refresh_token = jar
         .get("refreshToken")
         .map(|c| c.value().to_string())
         .ok_or_else(|| unauthorized("Refresh token not found"))?;
 
-    let client = get_client(pool).await?;
+    let mut client = get_client(pool).await?;
     let token_hash = sha256_hex(&refresh_token);
+    let transaction = client.transaction().await.map_err(db_error)?;
 
-    let row = client
+    let row = transaction
         .query_opt(
-            "SELECT s.user_id, s.revoked, s.expires_at
-             FROM sessions s
-             WHERE s.token_hash = $1",
+            &format!(
+                "SELECT s.id, s.user_id, s.family_id, s.revoked, s.consumed_at, s.expires_at,
+                        u.password_changed_at, ({ACTIVE_USER_SQL}) AS active
+                 FROM sessions s
+                 JOIN users u ON u.id = s.user_id
+                 WHERE s.token_hash = $1
+                 FOR UPDATE OF s"
+            ),
             &[&token_hash],
         )
         .await
         .map_err(db_error)?
         .ok_or_else(|| unauthorized("Invalid refresh token"))?;
 
+    let session_id: i32 = row.get("id");
+    let user_id: Uuid = row.get("user_id");
+    let family_id: Uuid = row.get("family_id");
     let revoked: bool = row.get("revoked");
+    let consumed_at: Option<chrono::DateTime<Utc>> = row.get("consumed_at");
     let expires_at: chrono::DateTime<Utc> = row.get("expires_at");
 
+    if consumed_at.is_some() {
+        let revoked_sessions = transaction
+            .execute(
+                "UPDATE sessions SET revoked = TRUE WHERE family_id = $1 AND NOT revoked",
+                &[&family_id],
+            )
+            .await
+            .map_err(db_error)?;
+        transaction.commit().await.map_err(db_error)?;
+        eprintln!(
+            "Refresh token reuse detected for user {user_id} (session family {family_id}); \
+             revoked {revoked_sessions} session(s)"
+        );
+        return Err(unauthorized("Refresh token has already been used"));
+    }
+
     if revoked {
         return Err(unauthorized("Refresh token has been revoked"));
     }
@@ -172,25 +271,34 @@ pub async fn refresh(
         return Err(unauthorized("Refresh token has expired"));
     }
 
-    let user_id: Uuid = row.get("user_id");
+    if !row.get::<_, bool>("active") {
+        return Err(unauthorized("Account is disabled or has expired"));
+    }
+
+    if PasswordPolicy::from_env().is_expired(row.get("password_changed_at")) {
+        return Err(unauthorized("Password has expired"));
+    }
 
-    let user_obj = find_user_by_id(&client, user_id)
+    let user_obj = find_user_by_id(&transaction, user_id)
         .await
         .map_err(db_error)?
         .map(|row| row_to_user(&row))
         .ok_or_else(|| unauthorized("User not found"))?;
 
-    if let Err(e) = client
+    transaction
         .execute(
-            "UPDATE sessions SET revoked = TRUE WHERE token_hash = $1",
-            &[&token_hash],
+            "UPDATE sessions SET consumed_at = NOW() WHERE id = $1",
+            &[&session_id],
         )
         .await
-    {
-        eprintln!("Failed to revoke session: {e}");
-    }
+        .map_err(db_error)?;
 
-    issue_tokens(&client, jar, &user_obj, false).await?;
+    // The admin's refresh session outlives an impersonation; replacing the access cookie ends it.
+    if let Some(access_token) = jar.get("accessToken") {
+        end_impersonation_on_refresh(&transaction, keys, access_token.value(), user_id, ip).await?;
+    }
+    issue_tokens(&transaction, jar, keys, &user_obj, family_id).await?;
+    transaction.commit().await.map_err(db_error)?;
 
     Ok(Json(LoginResponse { user: user_obj }))
 }
@@ -210,18 +318,12 @@ pub async fn check_auth(
         .map(|row| row_to_user(&row))
         .ok_or_else(|| not_found("User not found"))?;
 
-    let rows = client
-        .query(
-            "SELECT p.name FROM role_permissions rp
-             JOIN permissions p ON rp.permission_id = p.id
-             JOIN users u ON u.role_id = rp.role_id
-             WHERE u.id = $1",
-            &[&user.id],
-        )
+    let permissions: Vec<String> = effective_permissions(&client, user.id)
         .await
-        .map_err(db_error)?;
-
-    let permissions: Vec<String> = rows.iter().map(|row| row.get("name")).collect();
+        .map_err(db_error)?
+        .into_iter()
+        .map(|permission| permission.name)
+        .collect();
 
     Ok(Json(serde_json::json!({
         "user": {
@@ -232,6 +334,7 @@ pub async fn check_auth(
             "created_at": profile.created_at,
             "updated_at": profile.updated_at,
             "permissions": permissions,
+            "impersonated_by": user.impersonator,
         }
     })))
 }
@@ -8,13 +8,24 @@ pub(crate) use rocket::serde::json::Json;
 
 // Submodules
 pub(crate) mod api_keys;
+pub(crate) mod app_passwords;
 pub(crate) mod crud;
+pub(crate) mod csrf;
+pub(crate) mod groups;
 pub(crate) mod guards;
 pub(crate) mod helpers;
+pub(crate) mod impersonation;
+pub(crate) mod invitations;
 pub(crate) mod jwt;
+pub(crate) mod keys;
 pub(crate) mod login;
+pub(crate) mod password_policy;
+pub(crate) mod password_resets;
 
 // Re-exports for parent (main.rs) - explicit for login (function/module name collision).
 // Re-exports for parent (main.rs)
-pub(crate) use {api_keys::*, crud::*, guards::*, helpers::*, jwt::*};
+pub(crate) use {
+    api_keys::*, app_passwords::*, crud::*, csrf::*, groups::*, guards::*, helpers::*,
+    impersonation::*, invitations::*, jwt::*, keys::*, password_policy::*, password_resets::*,
+};
 // login is re-exported via its module path - see main.rs.
@@ -0,0 +1,311 @@
+use super::jwt::verify_password;
+use crate::shared::{ApiError, structured_error};
+use rocket::http::Status;
+use sha1::{Digest, Sha1};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock, Mutex};
+
+const COMMON_PASSWORDS: &str = include_str!("common_passwords.txt");
+
+static COMMON_PASSWORD_SET: LazyLock<HashSet<String>> = LazyLock::new(|| {
+    COMMON_PASSWORDS
+        .lines()
+        .map(|line| line.trim().to_lowercase())
+        .filter(|line| !line.is_empty())
+        .collect()
+});
+
+/// Flat breached-hash files, loaded once per path on first use.
+static BREACHED_HASH_FILES: LazyLock<Mutex<HashMap<PathBuf, Arc<HashSet<String>>>>> =
+    LazyLock::new(Default::default);
+
+/// Password rules read from the environment.
+///
+/// - `PASSWORD_MIN_LENGTH` (default 8)
+/// - `PASSWORD_MIN_CHARACTER_CLASSES` - how many of lowercase, uppercase, digits and
+///   symbols must appear (default 1)
+/// - `PASSWORD_MAX_AGE_DAYS` - force a change after this many days; 0 disables (default 0)
+/// - `PASSWORD_BREACHED_HASHES_PATH` - optional local copy of a breached SHA-1 hash list
+#[derive(Debug, Clone)]
+pub(crate) struct PasswordPolicy {
+    pub(crate) min_length: usize,
+    pub(crate) min_character_classes: usize,
+    pub(crate) max_age_days: i64,
+    pub(crate) breached_hashes: Option<PathBuf>,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: 8,
+            min_character_classes: 1,
+            max_age_days: 0,
+            breached_hashes: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum PasswordViolation {
+    TooShort(usize),
+    TooFewCharacterClasses(usize),
+    Common,
+    Breached,
+    Reused,
+}
+
+impl PasswordViolation {
+    pub(crate) fn code(&self) -> &'static str {
+        match self {
+            Self::TooShort(_) => "too_short",
+            Self::TooFewCharacterClasses(_) => "too_few_character_classes",
+            Self::Common => "common",
+            Self::Breached => "breached",
+            Self::Reused => "reused",
+        }
+    }
+}
+
+impl fmt::Display for PasswordViolation {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooShort(min) => write!(formatter, "Password must be at least {min} characters"),
+            Self::TooFewCharacterClasses(min) => write!(
+                formatter,
+                "Password must mix at least {min} of lowercase letters, uppercase letters, digits and symbols"
+            ),
+            Self::Common => formatter.write_str("Password is too common"),
+            Self::Breached => formatter.write_str("Password appears in a known data breach"),
+            Self::Reused => formatter.write_str("New password must differ from the current one"),
+        }
+    }
+}
+
+fn env_number<T: std::str::FromStr>(name: &str) -> Option<T> {
+    std::env::var(name)
+        .ok()
+        .and_then(|value| value.parse().ok())
+}
+
+fn character_classes(password: &str) -> usize {
+    let mut classes = [false; 4];
+    for c in password.chars() {
+        let index = if c.is_lowercase() {
+            0
+        } else if c.is_uppercase() {
+            1
+        } else if c.is_numeric() {
+            2
+        } else {
+            3
+        };
+        classes[index] = true;
+    }
+    classes.iter().filter(|present| **present).count()
+}
+
+/// Upper-cased hashes of a file of `HASH:COUNT` lines.
+fn read_hashes(file: &Path) -> std::io::Result<HashSet<String>> {
+    let mut hashes = HashSet::new();
+    for line in BufReader::new(std::fs::File::open(file)?).lines() {
+        let line = line?;
+        let hash = line.split(':').next().unwrap_or_default().trim();
+        if !hash.is_empty() {
+            hashes.insert(hash.to_ascii_uppercase());
+        }
+    }
+    Ok(hashes)
+}
+
+/// Look a SHA-1 hash up in a local breached-password list.
+///
+/// A directory is treated as a k-anonymity range store: one file per 5-character hash
+/// prefix (optionally with a `.txt` extension) holding `SUFFIX:COUNT` lines, read only for
+/// the prefix being checked. A single file holds `HASH:COUNT` lines and is kept in memory
+/// after the first check.
+fn breached_hash_listed(path: &Path, sha1_hex: &str) -> std::io::Result<bool> {
+    let sha1_hex = sha1_hex.to_ascii_uppercase();
+    let (prefix, suffix) = sha1_hex.split_at(5);
+    if path.is_dir() {
+        let range = path.join(prefix);
+        let range = if range.exists() {
+            range
+        } else {
+            path.join(format!("{prefix}.txt"))
+        };
+        if !range.exists() {
+            return Ok(false);
+        }
+        return Ok(read_hashes(&range)?.contains(suffix));
+    }
+
+    // Holding the lock while loading keeps concurrent first checks from reading the file twice.
+    let mut files = BREACHED_HASH_FILES
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let hashes = match files.get(path) {
+        Some(hashes) => hashes.clone(),
+        None => {
+            let hashes = Arc::new(read_hashes(path)?);
+            files.insert(path.to_path_buf(), hashes.clone());
+            hashes
+        }
+    };
+    drop(files);
+    Ok(hashes.contains(&sha1_hex))
+}
+
+impl PasswordPolicy {
+    pub(crate) fn from_env() -> Self {
+        let default = Self::default();
+        Self {
+            min_length: env_number("PASSWORD_MIN_LENGTH").unwrap_or(default.min_length),
+            min_character_classes: env_number("PASSWORD_MIN_CHARACTER_CLASSES")
+                .map(|classes: usize| classes.clamp(1, 4))
+                .unwrap_or(default.min_character_classes),
+            max_age_days: env_number("PASSWORD_MAX_AGE_DAYS")
+                .filter(|days: &i64| *days >= 0)
+                .unwrap_or(default.max_age_days),
+            breached_hashes: std::env::var("PASSWORD_BREACHED_HASHES_PATH")
+                .ok()
+                .filter(|path| !path.is_empty())
+                .map(PathBuf::from),
+        }
+    }
+
+    /// Rules that need nothing but the password itself.
+    pub(crate) fn local_violations(&self, password: &str) -> Vec<PasswordViolation> {
+        let mut violations = Vec::new();
+        if password.chars().count() < self.min_length {
+            violations.push(PasswordViolation::TooShort(self.min_length));
+        }
+        if character_classes(password) < self.min_character_classes {
+            violations.push(PasswordViolation::TooFewCharacterClasses(
+                self.min_character_classes,
+            ));
+        }
+        if COMMON_PASSWORD_SET.contains(&password.to_lowercase()) {
+            violations.push(PasswordViolation::Common);
+        }
+        violations
+    }
+
+    /// Check every rule, including the optional breached-password list.
+    pub(crate) async fn violations(&self, password: &str) -> Vec<PasswordViolation> {
+        let mut violations = self.local_violations(password);
+        if let Some(path) = self.breached_hashes.clone() {
+            let sha1_hex = hex::encode_upper(Sha1::digest(password.as_bytes()));
+            match tokio::task::spawn_blocking(move || breached_hash_listed(&path, &sha1_hex)).await
+            {
+                Ok(Ok(true)) => violations.push(PasswordViolation::Breached),
+                Ok(Ok(false)) => {}
+                Ok(Err(error)) => eprintln!("Breached password list unavailable: {error}"),
+                Err(error) => eprintln!("Breached password check failed: {error}"),
+            }
+        }
+        violations
+    }
+
+    /// Reject `password` with a structured error listing every broken rule.
+    pub(crate) async fn validate(&self, password: &str) -> Result<(), ApiError> {
+        policy_result(self.violations(password).await)
+    }
+
+    /// Like [`Self::validate`], but also refuses the password currently stored in `current_hash`.
+    pub(crate) async fn validate_new_password(
+        &self,
+        password: &str,
+        current_hash: Option<&str>,
+    ) -> Result<(), ApiError> {
+        let mut violations = self.violations(password).await;
+        if current_hash.is_some_and(|hash| verify_password(password, hash).unwrap_or(false)) {
+            violations.push(PasswordViolation::Reused);
+        }
+        policy_result(violations)
+    }
+
+    pub(crate) fn is_expired(&self, changed_at: chrono::DateTime<chrono::Utc>) -> bool {
+        self.max_age_days > 0
+            && chrono::Utc::now() - changed_at > chrono::Duration::days(self.max_age_days)
+    }
+}
+
+pub(crate) fn policy_result(violations: Vec<PasswordViolation>) -> Result<(), ApiError> {
+    if violations.is_empty() {
+        return Ok(());
+    }
+    let details: Vec<serde_json::Value> = violations
+        .iter()
+        .map(|violation| {
+            serde_json::json!({"rule": violation.code(), "message": violation.to_string()})
+        })
+        .collect();
+    Err(structured_error(
+        Status::BadRequest,
+        "password_policy",
+        &violations[0].to_string(),
+        serde_json::Value::Array(details),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_and_single_class_passwords_are_rejected() {
+        let policy = PasswordPolicy {
+            min_character_classes: 3,
+            ..PasswordPolicy::default()
+        };
+        assert_eq!(
+            policy.local_violations("abc"),
+            vec![
+                PasswordViolation::TooShort(8),
+                PasswordViolation::TooFewCharacterClasses(3)
+            ]
+        );
+        assert!(policy.local_violations("Correct-Horse-7").is_empty());
+    }
+
+    #[test]
+    fn common_passwords_are_rejected_case_insensitively() {
+        let policy = PasswordPolicy::default();
+        assert_eq!(
+            policy.local_violations("PASSWORD123"),
+            vec![PasswordViolation::Common]
+        );
+    }
+
+    #[test]
+    fn breached_hashes_are_found_in_range_directories_and_flat_files() {
+        let root = std::env::temp_dir().join(format!("bf-breached-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&root).unwrap();
+        let sha1_hex = hex::encode_upper(Sha1::digest(b"hunter2hunter2"));
+        let (prefix, suffix) = sha1_hex.split_at(5);
+        std::fs::write(root.join(prefix), format!("{suffix}:42\r\n")).unwrap();
+        let flat = root.join("flat.txt");
+        std::fs::write(&flat, format!("{}:1\n{sha1_hex}:42\n", "0".repeat(40))).unwrap();
+
+        assert!(breached_hash_listed(&root, &sha1_hex).unwrap());
+        assert!(breached_hash_listed(&flat, &sha1_hex).unwrap());
+        assert!(!breached_hash_listed(&root, &"F".repeat(40)).unwrap());
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn max_age_zero_never_expires() {
+        let old = chrono::Utc::now() - chrono::Duration::days(3650);
+        assert!(!PasswordPolicy::default().is_expired(old));
+        let policy = PasswordPolicy {
+            max_age_days: 90,
+            ..PasswordPolicy::default()
+        };
+        assert!(policy.is_expired(old));
+        assert!(!policy.is_expired(chrono::Utc::now()));
+    }
+}
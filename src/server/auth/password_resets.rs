@@ -0,0 +1,212 @@
+use super::*;
+
+use crate::models::{
+    CompletePasswordResetRequest, CreatedPasswordReset, ForgotPasswordRequest,
+    PublicPasswordResetStatus,
+};
+use deadpool_postgres::GenericClient;
+use rocket::http::Status;
+use uuid::Uuid;
+
+const ADMIN_RESET_HOURS: i64 = 24;
+const EMAIL_RESET_MINUTES: i64 = 60;
+const EMAIL_RESET_COOLDOWN_MINUTES: i64 = 5;
+
+/// Store a new reset token for `user_id`, discarding any the user has not used yet.
+pub(crate) async fn create_reset_token(
+    client: &impl GenericClient,
+    user_id: Uuid,
+    created_by_user_id: Option<Uuid>,
+    lifetime: Duration,
+) -> Result<CreatedPasswordReset, tokio_postgres::Error> {
+    client
+        .execute(
+            "DELETE FROM password_reset_tokens WHERE user_id = $1 AND used_at IS NULL",
+            &[&user_id],
+        )
+        .await?;
+
+    let token = random_hex::<32>();
+    let token_hash = sha256_hex(&token);
+    let expires_at = Utc::now() + lifetime;
+    client
+        .execute(
+            "INSERT INTO password_reset_tokens (user_id, token_hash, created_by_user_id, expires_at)
+             VALUES ($1, $2, $3, $4)",
+            &[&user_id, &token_hash, &created_by_user_id, &expires_at],
+        )
+        .await?;
+
+    Ok(CreatedPasswordReset { token, expires_at })
+}
+
+/// POST /api/users/<id>/password-reset - Issue a single-use reset link for a user
+#[post("/users/<id>/password-reset")]
+pub async fn create_password_reset(
+    pool: &State<Pool>,
+    user: AuthenticatedUser,
+    id: String,
+) -> Result<Json<CreatedPasswordReset>, (Status, Json<serde_json::Value>)> {
+    let user_id = parse_user_id(&id)?;
+    reject_impersonation(&user)?;
+    require_permission(pool, &user, "edit_user").await?;
+
+    let client = get_client(pool).await?;
+    if find_user_by_id(&client, user_id)
+        .await
+        .map_err(db_error)?
+        .is_none()
+    {
+        return Err(not_found("User not found"));
+    }
+
+    let reset = create_reset_token(
+        &client,
+        user_id,
+        Some(user.id),
+        Duration::hours(ADMIN_RESET_HOURS),
+    )
+    .await
+    .map_err(db_error)?;
+
+    Ok(Json(reset))
+}
+
+/// POST /api/public/password-resets - Email a reset link when SMTP is configured
+///
+/// Always answers the same way so the endpoint cannot be used to discover accounts.
+#[post("/public/password-resets", data = "<request>")]
+pub async fn request_password_reset(
+    pool: &State<Pool>,
+    request: Json<ForgotPasswordRequest>,
+) -> Result<Json<serde_json::Value>, (Status, Json<serde_json::Value>)> {
+    let Some(mail) = MailConfig::from_env() else {
+        return Err(not_found("Password reset by email is not enabled"));
+    };
+
+    let login = request.login.trim();
+    if login.is_empty() {
+        return Err(bad_request("Username or email is required"));
+    }
+
+    let client = get_client(pool).await?;
+    // One account's username can be another's email; the username match wins.
+    let account = client
+        .query_opt(
+            &format!(
+                "SELECT u.id, u.username, u.email FROM users u
+                 WHERE u.email IS NOT NULL AND {ACTIVE_USER_SQL}
+                   AND (u.username = $1 OR LOWER(u.email) = LOWER($1))
+                   AND NOT EXISTS (
+                       SELECT 1 FROM password_reset_tokens t
+                       WHERE t.user_id = u.id AND t.used_at IS NULL
+                         AND t.created_at > NOW() - make_interval(mins => $2)
+                   )
+                 ORDER BY u.username = $1 DESC
+                 LIMIT 1"
+            ),
+            &[&login, &(EMAIL_RESET_COOLDOWN_MINUTES as i32)],
+        )
+        .await
+        .map_err(db_error)?;
+
+    if let Some(account) = account {
+        let user_id: Uuid = account.get("id");
+        let username: String = account.get("username");
+        let email: String = account.get("email");
+        let reset = create_reset_token(
+            &client,
+            user_id,
+            None,
+            Duration::minutes(EMAIL_RESET_MINUTES),
+        )
+        .await
+        .map_err(db_error)?;
+
+        let body = format!(
+            "Hi {username},\n\n\
+             Someone asked to reset the password for your BlackFiles account.\n\
+             Open this link within {EMAIL_RESET_MINUTES} minutes to choose a new one:\n\n\
+             {}/reset-password/{}\n\n\
+             If this wasn't you, you can ignore this email.\n",
+            public_url(),
+            reset.token,
+        );
+        // Send in the background so response time does not reveal whether the account exists.
+        tokio::spawn(async move {
+            if let Err(error) = mail
+                .send(&email, "Reset your BlackFiles password", body)
+                .await
+            {
+                eprintln!("Failed to send password reset email to user {user_id}: {error}");
+            }
+        });
+    }
+
+    Ok(Json(serde_json::json!({"success": true})))
+}
+
+/// GET /api/public/password-resets/<token> - Validate a reset link
+#[get("/public/password-resets/<token>")]
+pub async fn get_password_reset(
+    pool: &State<Pool>,
+    token: &str,
+) -> Result<Json<PublicPasswordResetStatus>, (Status, Json<serde_json::Value>)> {
+    let token_hash = sha256_hex(token);
+    let client = get_client(pool).await?;
+    let row = client
+        .query_opt(
+            "SELECT u.username, t.expires_at
+             FROM password_reset_tokens t
+             JOIN users u ON u.id = t.user_id
+             WHERE t.token_hash = $1 AND t.used_at IS NULL AND t.expires_at > NOW()",
+            &[&token_hash],
+        )
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| not_found("Reset link is invalid or has expired"))?;
+
+    Ok(Json(PublicPasswordResetStatus {
+        username: row.get("username"),
+        expires_at: row.get("expires_at"),
+    }))
+}
+
+/// POST /api/public/password-resets/<token> - Set a new password and sign out everywhere
+#[post("/public/password-resets/<token>", data = "<request>")]
+pub async fn complete_password_reset(
+    pool: &State<Pool>,
+    token: &str,
+    request: Json<CompletePasswordResetRequest>,
+) -> Result<Json<serde_json::Value>, (Status, Json<serde_json::Value>)> {
+    let token_hash = sha256_hex(token);
+    let mut client = get_client(pool).await?;
+    let reset = client
+        .query_opt(
+            "SELECT t.user_id FROM password_reset_tokens t
+             WHERE t.token_hash = $1 AND t.used_at IS NULL AND t.expires_at > NOW()",
+            &[&token_hash],
+        )
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| not_found("Reset link is invalid or has expired"))?;
+    let user_id: Uuid = reset.get("user_id");
+    let password = prepare_user_password(&client, user_id, &request.password).await?;
+
+    let transaction = client.transaction().await.map_err(db_error)?;
+    // Claiming the token in the same statement that checks it keeps a link single-use.
+    transaction
+        .query_opt(
+            "UPDATE password_reset_tokens SET used_at = NOW()
+             WHERE token_hash = $1 AND used_at IS NULL AND expires_at > NOW()
+             RETURNING id",
+            &[&token_hash],
+        )
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| not_found("Reset link is invalid or has expired"))?;
+    set_user_password(&transaction, user_id, &password).await?;
+    transaction.commit().await.map_err(db_error)?;
+
+    Ok(Json(serde_json::json!({"success": true})))
+}
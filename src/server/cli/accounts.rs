@@ -0,0 +1,214 @@
+use super::*;
+
+use crate::auth::{
+    insert_user, normalize_email, prepare_user_password, revoked_key_event, role_permissions,
+    set_user_password, set_user_role,
+};
+use std::io::BufRead;
+use uuid::Uuid;
+
+const GENERATED_PASSWORD_LENGTH: usize = 20;
+
+/// A random password drawing on every character class, so any policy setting accepts it.
+fn generate_password() -> String {
+    const CLASSES: [&[u8]; 4] = [
+        b"abcdefghijkmnopqrstuvwxyz",
+        b"ABCDEFGHJKLMNPQRSTUVWXYZ",
+        b"23456789",
+        b"-_.!@#%+=",
+    ];
+    (0..GENERATED_PASSWORD_LENGTH)
+        .map(|index| {
+            let class = CLASSES[index % CLASSES.len()];
+            class[rand::random_range(0..class.len())] as char
+        })
+        .collect()
+}
+
+/// The password from `--password`, the first line of stdin with `--password-stdin`,
+/// or a generated one; the flag says whether it should be printed.
+fn password_argument(args: &mut Args) -> Result<(String, bool), CliError> {
+    let from_stdin = args.flag("password-stdin");
+    match (args.option("password")?, from_stdin) {
+        (Some(_), true) => Err(CliError::Usage(
+            "use either --password or --password-stdin".to_string(),
+        )),
+        (Some(password), false) => Ok((password, false)),
+        (None, true) => {
+            let mut line = String::new();
+            std::io::stdin()
+                .lock()
+                .read_line(&mut line)
+                .map_err(|error| CliError::Failed(format!("cannot read stdin: {error}")))?;
+            Ok((line.trim_end_matches(['\r', '\n']).to_owned(), false))
+        }
+        (None, false) => Ok((generate_password(), true)),
+    }
+}
+
+async fn user_id_by_name(
+    client: &deadpool_postgres::Object,
+    username: &str,
+) -> Result<Uuid, CliError> {
+    client
+        .query_opt("SELECT id FROM users WHERE username = $1", &[&username])
+        .await
+        .map_err(db_error)?
+        .map(|row| row.get("id"))
+        .ok_or_else(|| CliError::Failed(format!("user '{username}' not found")))
+}
+
+pub(crate) async fn create_user(pool: &Pool, mut args: Args) -> CliResult {
+    let username = args.positional("username")?;
+    let role = args.option("role")?.unwrap_or_else(|| "viewer".to_string());
+    let email = normalize_email(args.option("email")?.as_deref())?;
+    let (password, generated) = password_argument(&mut args)?;
+    args.finish()?;
+
+    let client = get_client(pool).await?;
+    let user = insert_user(&client, &username, &password, &role, email).await?;
+    record_audit(
+        pool,
+        AuditActor::command_line(),
+        AuditEvent::new("user.create", "user", user.id).after(serde_json::json!({
+            "username": user.username,
+            "role": user.role_name,
+            "email": user.email,
+        })),
+    )
+    .await;
+
+    println!(
+        "Created user {} ({}) with role {}",
+        user.username, user.id, user.role_name
+    );
+    if generated {
+        println!("Password: {password}");
+    }
+    Ok(())
+}
+
+pub(crate) async fn reset_password(pool: &Pool, mut args: Args) -> CliResult {
+    let username = args.positional("username")?;
+    let (password, generated) = password_argument(&mut args)?;
+    args.finish()?;
+
+    let mut client = get_client(pool).await?;
+    let user_id = user_id_by_name(&client, &username).await?;
+    let new_password = prepare_user_password(&client, user_id, &password).await?;
+    let transaction = client.transaction().await.map_err(db_error)?;
+    set_user_password(&transaction, user_id, &new_password).await?;
+    transaction.commit().await.map_err(db_error)?;
+    record_audit(
+        pool,
+        AuditActor::command_line(),
+        AuditEvent::new("user.password.update", "user", user_id),
+    )
+    .await;
+
+    println!("Password for {username} reset; existing sessions were signed out");
+    if generated {
+        println!("Password: {password}");
+    }
+    Ok(())
+}
+
+pub(crate) async fn set_role(pool: &Pool, mut args: Args) -> CliResult {
+    let username = args.positional("username")?;
+    let role = args.positional("role")?;
+    args.finish()?;
+
+    let client = get_client(pool).await?;
+    let user_id = user_id_by_name(&client, &username).await?;
+    let (previous_role, user) = set_user_role(&client, user_id, &role).await?;
+    record_audit(
+        pool,
+        AuditActor::command_line(),
+        AuditEvent::new("user.role.update", "user", user_id)
+            .before(serde_json::json!({"role": previous_role}))
+            .after(serde_json::json!({"role": user.role_name})),
+    )
+    .await;
+
+    println!(
+        "{username}: {} -> {}",
+        previous_role.unwrap_or_default(),
+        user.role_name
+    );
+    Ok(())
+}
+
+pub(crate) async fn list_roles(pool: &Pool, args: Args) -> CliResult {
+    args.finish()?;
+    let client = get_client(pool).await?;
+    let rows = client
+        .query(
+            "SELECT r.id, r.name, r.display_name, r.position,
+                    (SELECT COUNT(*) FROM users u WHERE u.role_id = r.id) AS user_count
+             FROM roles r ORDER BY r.position",
+            &[],
+        )
+        .await
+        .map_err(db_error)?;
+
+    println!(
+        "{:<4} {:<20} {:<24} {:>5}  PERMISSIONS",
+        "POS", "NAME", "DISPLAY NAME", "USERS"
+    );
+    for row in &rows {
+        let permissions = role_permissions(&client, row.get("id"))
+            .await
+            .map_err(db_error)?;
+        println!(
+            "{:<4} {:<20} {:<24} {:>5}  {}",
+            row.get::<_, i32>("position"),
+            row.get::<_, String>("name"),
+            row.get::<_, String>("display_name"),
+            row.get::<_, i64>("user_count"),
+            permissions.join(", ")
+        );
+    }
+    Ok(())
+}
+
+pub(crate) async fn revoke_api_key(pool: &Pool, mut args: Args) -> CliResult {
+    let id = args.positional("key_id")?;
+    args.finish()?;
+    let key_id =
+        Uuid::parse_str(&id).map_err(|_| CliError::Failed(format!("'{id}' is not a key ID")))?;
+
+    let client = get_client(pool).await?;
+    let revoked = client
+        .query_opt(
+            "DELETE FROM api_keys WHERE id = $1 RETURNING user_id, label",
+            &[&key_id],
+        )
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| CliError::Failed("API key not found".to_string()))?;
+    record_audit(
+        pool,
+        AuditActor::command_line(),
+        revoked_key_event(key_id, &revoked),
+    )
+    .await;
+
+    println!("API key {key_id} revoked");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_passwords_use_every_character_class() {
+        let password = generate_password();
+        assert_eq!(password.len(), GENERATED_PASSWORD_LENGTH);
+        assert!(password.chars().any(|c| c.is_ascii_lowercase()));
+        assert!(password.chars().any(|c| c.is_ascii_uppercase()));
+        assert!(password.chars().any(|c| c.is_ascii_digit()));
+        assert!(password.chars().any(|c| !c.is_ascii_alphanumeric()));
+        assert_ne!(password, generate_password());
+    }
+}
@@ -0,0 +1,157 @@
+use super::*;
+
+use crate::db::{MigrationState, MigrationStatus, migration_status};
+use crate::music::rescan_songs;
+use std::collections::HashSet;
+use std::path::Path;
+use uuid::Uuid;
+
+/// Apply pending migrations, or with `--dry-run` only list them.
+pub(crate) async fn migrate(pool: &Pool, mut args: Args) -> CliResult {
+    let dry_run = args.flag("dry-run");
+    args.finish()?;
+    if dry_run {
+        let statuses = migration_status(pool).await.map_err(CliError::Failed)?;
+        let pending: Vec<_> = statuses
+            .iter()
+            .filter(|status| status.state == MigrationState::Pending)
+            .collect();
+        for status in &pending {
+            println!("would apply {}", status.name);
+        }
+        println!("{} pending migrations", pending.len());
+        return check_migrations(&statuses);
+    }
+    let applied = crate::db::migrate(pool).await.map_err(CliError::Failed)?;
+    for name in &applied {
+        println!("applied {name}");
+    }
+    println!("Database schema is up to date");
+    Ok(())
+}
+
+fn check_migrations(statuses: &[MigrationStatus]) -> CliResult {
+    let modified = statuses
+        .iter()
+        .filter(|status| status.state == MigrationState::Modified)
+        .count();
+    if modified > 0 {
+        return Err(CliError::Failed(format!(
+            "{modified} applied migrations no longer match their scripts"
+        )));
+    }
+    Ok(())
+}
+
+/// List every known migration and whether it has run.
+pub(crate) async fn migration_status_report(pool: &Pool, args: Args) -> CliResult {
+    args.finish()?;
+    let statuses = migration_status(pool).await.map_err(CliError::Failed)?;
+    println!("{:<8} {:<40} {:<18} APPLIED AT", "VERSION", "NAME", "STATE");
+    for status in &statuses {
+        println!(
+            "{:<8} {:<40} {:<18} {}",
+            status.version,
+            status.name,
+            status.state.label(),
+            status
+                .applied_at
+                .map(|at| at.to_rfc3339())
+                .unwrap_or_default()
+        );
+    }
+    check_migrations(&statuses)
+}
+
+pub(crate) async fn scan_music(pool: &Pool, args: Args) -> CliResult {
+    args.finish()?;
+    let (scanned, failed) = rescan_songs(pool).await?;
+    record_audit(
+        pool,
+        AuditActor::command_line(),
+        AuditEvent::new("music.scan", "library", "global")
+            .after(serde_json::json!({"scanned": scanned, "failed": failed})),
+    )
+    .await;
+    println!("Scanned {scanned} songs, {failed} failed");
+    if failed > 0 {
+        return Err(CliError::Failed(format!(
+            "{failed} songs could not be read"
+        )));
+    }
+    Ok(())
+}
+
+/// Partial upload ids in `storage_root` that no upload session refers to.
+fn orphaned_partial_uploads(storage_root: &str, sessions: &HashSet<Uuid>) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(temporary_directory(storage_root)) else {
+        return Vec::new();
+    };
+    let mut orphans: Vec<String> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension().and_then(|ext| ext.to_str()) == Some("part")
+                && path
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .and_then(|stem| Uuid::parse_str(stem).ok())
+                    .is_none_or(|id| !sessions.contains(&id))
+        })
+        .map(|path| path.display().to_string())
+        .collect();
+    orphans.sort();
+    orphans
+}
+
+/// Report songs whose files are gone and partial uploads without a session.
+pub(crate) async fn verify_storage(pool: &Pool, args: Args) -> CliResult {
+    args.finish()?;
+    let mut problems = 0usize;
+
+    for root in [STORAGE_ROOT, MUSIC_ROOT] {
+        if !Path::new(root).is_dir() {
+            println!("missing directory: {root}");
+            problems += 1;
+        }
+    }
+
+    let client = get_client(pool).await?;
+    let songs = client
+        .query("SELECT file_path FROM songs ORDER BY file_path", &[])
+        .await
+        .map_err(db_error)?;
+    for row in &songs {
+        let file_path: String = row.get("file_path");
+        if !Path::new(MUSIC_ROOT).join(&file_path).is_file() {
+            println!("missing song file: {file_path}");
+            problems += 1;
+        }
+    }
+
+    let sessions: HashSet<Uuid> = client
+        .query("SELECT id FROM upload_sessions", &[])
+        .await
+        .map_err(db_error)?
+        .iter()
+        .map(|row| row.get("id"))
+        .collect();
+    for root in [STORAGE_ROOT, MUSIC_ROOT] {
+        for orphan in orphaned_partial_uploads(root, &sessions) {
+            println!("orphaned partial upload: {orphan}");
+            problems += 1;
+        }
+    }
+
+    println!(
+        "Checked {} songs and {} upload sessions: {problems} problems",
+        songs.len(),
+        sessions.len()
+    );
+    if problems > 0 {
+        return Err(CliError::Failed(format!(
+            "{problems} storage problems found"
+        )));
+    }
+    Ok(())
+}
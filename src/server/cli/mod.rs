@@ -0,0 +1,223 @@
+// Re-export shared infrastructure for submodules.
+pub(crate) use crate::audit::{AuditActor, AuditEvent, record_audit};
+pub(crate) use crate::shared::*;
+pub(crate) use deadpool_postgres::Pool;
+
+// Submodules
+pub(crate) mod accounts;
+pub(crate) mod maintenance;
+
+use rocket::serde::json::Json;
+use std::process::ExitCode;
+
+const USAGE: &str = "Usage: blackfiles [command]
+
+Without a command, the web server starts.
+
+Commands:
+  user create <username> [--role <role>] [--email <address>] [--password <password> | --password-stdin]
+  user reset-password <username> [--password <password> | --password-stdin]
+  user set-role <username> <role>
+  role list
+  apikey revoke <key_id>
+  db migrate [--dry-run]
+  db status
+  music scan
+  storage verify
+  help
+
+When no password is given, a random one is generated and printed.";
+
+/// Outcome of a failed command: bad arguments print the usage, anything else just the message.
+pub(crate) enum CliError {
+    Usage(String),
+    Failed(String),
+}
+
+impl From<ApiError> for CliError {
+    fn from((_, Json(body)): ApiError) -> Self {
+        let mut message = body
+            .get("error")
+            .and_then(|error| error.as_str())
+            .unwrap_or("Request failed")
+            .to_owned();
+        if let Some(details) = body.get("details").and_then(|details| details.as_array()) {
+            for detail in details {
+                if let Some(text) = detail.get("message").and_then(|text| text.as_str()) {
+                    message.push_str("\n  - ");
+                    message.push_str(text);
+                }
+            }
+        }
+        CliError::Failed(message)
+    }
+}
+
+pub(crate) type CliResult = Result<(), CliError>;
+
+/// Arguments left to consume; options may appear anywhere after the subcommand.
+pub(crate) struct Args {
+    rest: Vec<String>,
+}
+
+impl Args {
+    pub(crate) fn new(args: &[String]) -> Self {
+        Self {
+            rest: args.to_vec(),
+        }
+    }
+
+    /// Take `--name value` or `--name=value`.
+    pub(crate) fn option(&mut self, name: &str) -> Result<Option<String>, CliError> {
+        let flag = format!("--{name}");
+        let inline = format!("--{name}=");
+        let Some(index) = self
+            .rest
+            .iter()
+            .position(|arg| *arg == flag || arg.starts_with(&inline))
+        else {
+            return Ok(None);
+        };
+        let arg = self.rest.remove(index);
+        if let Some(value) = arg.strip_prefix(&inline) {
+            return Ok(Some(value.to_owned()));
+        }
+        if index < self.rest.len() && !self.rest[index].starts_with("--") {
+            return Ok(Some(self.rest.remove(index)));
+        }
+        Err(CliError::Usage(format!("{flag} needs a value")))
+    }
+
+    pub(crate) fn flag(&mut self, name: &str) -> bool {
+        let flag = format!("--{name}");
+        match self.rest.iter().position(|arg| *arg == flag) {
+            Some(index) => {
+                self.rest.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Take the next positional argument, describing it as `what` if it is missing.
+    pub(crate) fn positional(&mut self, what: &str) -> Result<String, CliError> {
+        match self.rest.iter().position(|arg| !arg.starts_with("--")) {
+            Some(index) => Ok(self.rest.remove(index)),
+            None => Err(CliError::Usage(format!("missing {what}"))),
+        }
+    }
+
+    /// Fail on anything the command did not consume.
+    pub(crate) fn finish(self) -> CliResult {
+        match self.rest.first() {
+            Some(arg) => Err(CliError::Usage(format!("unexpected argument '{arg}'"))),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Whether `args` name a command-line task rather than starting the server.
+pub fn is_command(args: &[String]) -> bool {
+    args.first().is_some_and(|command| command != "serve")
+}
+
+/// Run one administrative command against the database, without starting Rocket.
+pub async fn run(args: &[String]) -> ExitCode {
+    let mut args = Args::new(args);
+    let result = match args.positional("command") {
+        Ok(command) => dispatch(&command, args).await,
+        Err(error) => Err(error),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(CliError::Usage(message)) => {
+            eprintln!("error: {message}\n\n{USAGE}");
+            ExitCode::from(2)
+        }
+        Err(CliError::Failed(message)) => {
+            eprintln!("error: {message}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn dispatch(command: &str, mut args: Args) -> CliResult {
+    if matches!(command, "help" | "--help" | "-h") {
+        println!("{USAGE}");
+        return Ok(());
+    }
+    let subcommand = args.positional(&format!("{command} subcommand"))?;
+    let pool = crate::db::init_pool();
+    match (command, subcommand.as_str()) {
+        ("user", "create") => accounts::create_user(&pool, args).await,
+        ("user", "reset-password") => accounts::reset_password(&pool, args).await,
+        ("user", "set-role") => accounts::set_role(&pool, args).await,
+        ("role", "list") => accounts::list_roles(&pool, args).await,
+        ("apikey", "revoke") => accounts::revoke_api_key(&pool, args).await,
+        ("db", "migrate") => maintenance::migrate(&pool, args).await,
+        ("db", "status") => maintenance::migration_status_report(&pool, args).await,
+        ("music", "scan") => maintenance::scan_music(&pool, args).await,
+        ("storage", "verify") => maintenance::verify_storage(&pool, args).await,
+        _ => Err(CliError::Usage(format!(
+            "unknown command '{command} {subcommand}'"
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(values: &[&str]) -> Args {
+        Args::new(
+            &values
+                .iter()
+                .map(|value| value.to_string())
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    #[test]
+    fn options_and_positionals_are_taken_in_any_order() {
+        let mut parsed = args(&[
+            "--role",
+            "editor",
+            "alice",
+            "--email=a@example.com",
+            "--password-stdin",
+        ]);
+        assert_eq!(
+            parsed.option("role").ok().flatten().as_deref(),
+            Some("editor")
+        );
+        assert_eq!(
+            parsed.option("email").ok().flatten().as_deref(),
+            Some("a@example.com")
+        );
+        assert!(parsed.flag("password-stdin"));
+        assert!(!parsed.flag("password-stdin"));
+        assert_eq!(parsed.positional("username").ok().as_deref(), Some("alice"));
+        assert!(parsed.finish().is_ok());
+    }
+
+    #[test]
+    fn missing_values_and_leftovers_are_usage_errors() {
+        let mut parsed = args(&["--role"]);
+        assert!(matches!(parsed.option("role"), Err(CliError::Usage(_))));
+
+        let mut parsed = args(&["alice", "extra"]);
+        parsed.positional("username").ok();
+        assert!(matches!(parsed.finish(), Err(CliError::Usage(_))));
+        assert!(matches!(
+            args(&[]).positional("username"),
+            Err(CliError::Usage(_))
+        ));
+    }
+
+    #[test]
+    fn only_serve_or_no_arguments_start_the_server() {
+        assert!(!is_command(&[]));
+        assert!(!is_command(&["serve".to_owned()]));
+        assert!(is_command(&["db".to_owned(), "migrate".to_owned()]));
+    }
+}
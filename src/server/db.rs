@@ -1,33 +1,128 @@
-use deadpool_postgres::{Config, Pool, Runtime};
+use crate::shared::sha256_hex;
+use chrono::{DateTime, Utc};
+use deadpool_postgres::{Config, Object, Pool, Runtime};
 use tokio_postgres::NoTls;
 
-const FEATURE_SCRIPTS: &[(&str, &str)] = &[
-    ("0000_init.sql", include_str!("../../dbinit/0000_init.sql")),
-    (
-        "0001_dense_role_positions.sql",
-        include_str!("../../dbinit/0001_dense_role_positions.sql"),
-    ),
-    (
-        "0002_core_seed.sql",
-        include_str!("../../dbinit/0002_core_seed.sql"),
-    ),
-    (
-        "0003_upload_links.sql",
-        include_str!("../../dbinit/0003_upload_links.sql"),
-    ),
-    (
-        "0004_upload_sessions.sql",
-        include_str!("../../dbinit/0004_upload_sessions.sql"),
-    ),
-    (
-        "0005_public_upload_sessions.sql",
-        include_str!("../../dbinit/0005_public_upload_sessions.sql"),
-    ),
-    (
-        "0006_music_library.sql",
-        include_str!("../../dbinit/0006_music_library.sql"),
-    ),
-];
+/// One `dbinit/` script, embedded at build time by `build.rs`.
+pub(crate) struct Migration {
+    pub(crate) version: i32,
+    pub(crate) name: &'static str,
+    pub(crate) sql: &'static str,
+}
+
+include!(concat!(env!("OUT_DIR"), "/migrations.rs"));
+
+/// Serializes concurrent runners, such as two replicas starting at once.
+const MIGRATION_LOCK_ID: i64 = 0x626c_6b66_6d69_6772;
+
+impl Migration {
+    fn checksum(&self) -> String {
+        sha256_hex(self.sql)
+    }
+}
+
+struct AppliedMigration {
+    version: i32,
+    name: String,
+    checksum: String,
+    applied_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MigrationState {
+    Applied,
+    Pending,
+    /// Applied, but the embedded script no longer matches what ran.
+    Modified,
+    /// Recorded in the database but not embedded in this binary.
+    Unknown,
+}
+
+impl MigrationState {
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            MigrationState::Applied => "applied",
+            MigrationState::Pending => "pending",
+            MigrationState::Modified => "checksum mismatch",
+            MigrationState::Unknown => "unknown",
+        }
+    }
+}
+
+pub(crate) struct MigrationStatus {
+    pub(crate) version: i32,
+    pub(crate) name: String,
+    pub(crate) state: MigrationState,
+    pub(crate) applied_at: Option<DateTime<Utc>>,
+}
+
+fn compare_migrations(
+    available: &[Migration],
+    applied: &[AppliedMigration],
+) -> Vec<MigrationStatus> {
+    let mut statuses: Vec<MigrationStatus> = available
+        .iter()
+        .map(|migration| {
+            let record = applied
+                .iter()
+                .find(|record| record.version == migration.version);
+            let state = match record {
+                None => MigrationState::Pending,
+                Some(record) if record.checksum == migration.checksum() => MigrationState::Applied,
+                Some(_) => MigrationState::Modified,
+            };
+            MigrationStatus {
+                version: migration.version,
+                name: migration.name.to_owned(),
+                state,
+                applied_at: record.map(|record| record.applied_at),
+            }
+        })
+        .collect();
+    statuses.extend(
+        applied
+            .iter()
+            .filter(|record| {
+                !available
+                    .iter()
+                    .any(|migration| migration.version == record.version)
+            })
+            .map(|record| MigrationStatus {
+                version: record.version,
+                name: record.name.clone(),
+                state: MigrationState::Unknown,
+                applied_at: Some(record.applied_at),
+            }),
+    );
+    statuses.sort_by_key(|status| status.version);
+    statuses
+}
+
+/// Scripts still to run, or an error naming every applied script that was edited since.
+fn pending_migrations<'a>(
+    available: &'a [Migration],
+    statuses: &[MigrationStatus],
+) -> Result<Vec<&'a Migration>, String> {
+    let modified: Vec<&str> = statuses
+        .iter()
+        .filter(|status| status.state == MigrationState::Modified)
+        .map(|status| status.name.as_str())
+        .collect();
+    if !modified.is_empty() {
+        return Err(format!(
+            "applied migrations were changed afterwards (checksum mismatch): {}; add a new script instead of editing one",
+            modified.join(", ")
+        ));
+    }
+    Ok(available
+        .iter()
+        .filter(|migration| {
+            statuses.iter().any(|status| {
+                status.version == migration.version && status.state == MigrationState::Pending
+            })
+        })
+        .collect())
+}
 
 /// Initialize the PostgreSQL connection pool.
 pub fn init_pool() -> Pool {
@@ -46,18 +141,200 @@ pub fn init_pool() -> Pool {
         .expect("Failed to create database pool")
 }
 
-/// Apply every idempotent database feature script before serving requests.
-pub async fn apply_feature_scripts(pool: &Pool) -> Result<(), String> {
-    let client = pool
-        .get()
+async fn connect(pool: &Pool) -> Result<Object, String> {
+    pool.get()
+        .await
+        .map_err(|error| format!("could not acquire database connection: {error}"))
+}
+
+async fn applied_migrations(client: &Object) -> Result<Vec<AppliedMigration>, String> {
+    let tracked: bool = client
+        .query_one(
+            "SELECT to_regclass('schema_migrations') IS NOT NULL AS tracked",
+            &[],
+        )
         .await
-        .map_err(|error| format!("could not acquire database connection: {error}"))?;
+        .map_err(|error| format!("could not read migration history: {error}"))?
+        .get("tracked");
+    if !tracked {
+        return Ok(Vec::new());
+    }
+    let rows = client
+        .query(
+            "SELECT version, name, checksum, applied_at FROM schema_migrations ORDER BY version",
+            &[],
+        )
+        .await
+        .map_err(|error| format!("could not read migration history: {error}"))?;
+    Ok(rows
+        .iter()
+        .map(|row| AppliedMigration {
+            version: row.get("version"),
+            name: row.get("name"),
+            checksum: row.get("checksum"),
+            applied_at: row.get("applied_at"),
+        })
+        .collect())
+}
+
+/// Compare the embedded scripts with the database's history without changing anything.
+pub(crate) async fn migration_status(pool: &Pool) -> Result<Vec<MigrationStatus>, String> {
+    let client = connect(pool).await?;
+    let applied = applied_migrations(&client).await?;
+    Ok(compare_migrations(MIGRATIONS, &applied))
+}
+
+async fn apply_pending(client: &Object) -> Result<Vec<&'static str>, String> {
+    client
+        .batch_execute(
+            "CREATE TABLE IF NOT EXISTS schema_migrations (
+                version INTEGER PRIMARY KEY,
+                name TEXT NOT NULL,
+                checksum CHAR(64) NOT NULL,
+                applied_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                execution_ms BIGINT NOT NULL
+            )",
+        )
+        .await
+        .map_err(|error| format!("could not create schema_migrations: {error}"))?;
+
+    let applied = applied_migrations(client).await?;
+    let statuses = compare_migrations(MIGRATIONS, &applied);
+    let pending = pending_migrations(MIGRATIONS, &statuses)?;
 
-    for (name, script) in FEATURE_SCRIPTS {
-        client.batch_execute(script).await.map_err(|error| {
-            format!("could not apply database feature script {name}: {error:?}")
-        })?;
+    let mut names = Vec::with_capacity(pending.len());
+    for migration in pending {
+        let started = std::time::Instant::now();
+        client
+            .batch_execute(migration.sql)
+            .await
+            .map_err(|error| format!("could not apply migration {}: {error:?}", migration.name))?;
+        let execution_ms = started.elapsed().as_millis() as i64;
+        client
+            .execute(
+                "INSERT INTO schema_migrations (version, name, checksum, execution_ms)
+                 VALUES ($1, $2, $3, $4)",
+                &[
+                    &migration.version,
+                    &migration.name,
+                    &migration.checksum(),
+                    &execution_ms,
+                ],
+            )
+            .await
+            .map_err(|error| format!("could not record migration {}: {error}", migration.name))?;
+        names.push(migration.name);
     }
+    Ok(names)
+}
 
-    Ok(())
+/// Run every script not yet recorded in `schema_migrations`, once, under an advisory lock.
+///
+/// Refuses to run anything if an applied script's checksum no longer matches. Scripts are
+/// executed as written (some manage their own transaction), so each stays idempotent in
+/// case the process dies between running it and recording it.
+pub async fn migrate(pool: &Pool) -> Result<Vec<&'static str>, String> {
+    let client = connect(pool).await?;
+    client
+        .execute("SELECT pg_advisory_lock($1)", &[&MIGRATION_LOCK_ID])
+        .await
+        .map_err(|error| format!("could not acquire migration lock: {error}"))?;
+    let applied = match apply_pending(&client).await {
+        Ok(applied) => applied,
+        Err(error) => {
+            // A failed script can leave the session inside an aborted transaction. Closing
+            // the connection instead of recycling it ends that and releases the lock.
+            drop(Object::take(client));
+            return Err(error);
+        }
+    };
+    // The connection returns to the pool, so the session lock must not outlive this call.
+    if let Err(error) = client
+        .execute("SELECT pg_advisory_unlock($1)", &[&MIGRATION_LOCK_ID])
+        .await
+    {
+        drop(Object::take(client));
+        return Err(format!("could not release migration lock: {error}"));
+    }
+    Ok(applied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AVAILABLE: &[Migration] = &[
+        Migration {
+            version: 0,
+            name: "0000_init.sql",
+            sql: "CREATE TABLE a ();",
+        },
+        Migration {
+            version: 1,
+            name: "0001_more.sql",
+            sql: "CREATE TABLE b ();",
+        },
+        Migration {
+            version: 2,
+            name: "0002_latest.sql",
+            sql: "CREATE TABLE c ();",
+        },
+    ];
+
+    fn applied(version: i32, name: &str, sql: &str) -> AppliedMigration {
+        AppliedMigration {
+            version,
+            name: name.to_owned(),
+            checksum: sha256_hex(sql),
+            applied_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn embedded_migrations_are_ordered_and_include_every_script() {
+        assert!(
+            MIGRATIONS
+                .windows(2)
+                .all(|pair| pair[0].version < pair[1].version)
+        );
+        for name in ["0007_starred_artist.sql", "0008_embedded_cover_art.sql"] {
+            assert!(MIGRATIONS.iter().any(|migration| migration.name == name));
+        }
+    }
+
+    #[test]
+    fn only_unrecorded_scripts_are_pending() {
+        let history = [
+            applied(0, "0000_init.sql", "CREATE TABLE a ();"),
+            applied(9, "0009_removed.sql", "SELECT 1;"),
+        ];
+        let statuses = compare_migrations(AVAILABLE, &history);
+        let states: Vec<_> = statuses
+            .iter()
+            .map(|status| (status.version, status.state))
+            .collect();
+        assert_eq!(
+            states,
+            [
+                (0, MigrationState::Applied),
+                (1, MigrationState::Pending),
+                (2, MigrationState::Pending),
+                (9, MigrationState::Unknown),
+            ]
+        );
+        let pending = pending_migrations(AVAILABLE, &statuses).unwrap();
+        assert_eq!(
+            pending.iter().map(|m| m.version).collect::<Vec<_>>(),
+            [1, 2]
+        );
+    }
+
+    #[test]
+    fn edited_scripts_block_every_migration() {
+        let history = [applied(0, "0000_init.sql", "CREATE TABLE a (id INT);")];
+        let statuses = compare_migrations(AVAILABLE, &history);
+        assert_eq!(statuses[0].state, MigrationState::Modified);
+        let error = pending_migrations(AVAILABLE, &statuses).err().unwrap();
+        assert!(error.contains("0000_init.sql"));
+    }
 }
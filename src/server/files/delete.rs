@@ -9,7 +9,7 @@ pub async fn delete_path(
     user: AuthenticatedUser,
     path: PathBuf,
 ) -> Result<Json<serde_json::Value>, (Status, Json<serde_json::Value>)> {
-    require_permission(pool, user.id, "delete_files").await?;
+    require_permission(pool, &user, "delete_files").await?;
 
     let safe_path = sanitize_path(path).ok_or(bad_request("Invalid path"))?;
     if safe_path.as_os_str().is_empty() {
@@ -32,5 +32,13 @@ pub async fn delete_path(
             .map_err(|_| server_error())?;
     }
 
+    record_audit(
+        pool,
+        &user,
+        AuditEvent::new("file.delete", "file", path_to_web_string(&safe_path))
+            .before(serde_json::json!({"is_dir": metadata.is_dir(), "size": metadata.len()})),
+    )
+    .await;
+
     Ok(Json(serde_json::json!({"success": true})))
 }
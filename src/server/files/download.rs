@@ -10,7 +10,7 @@ pub async fn download(
     user: AuthenticatedUser,
     path: PathBuf,
 ) -> Result<FileResponse, Status> {
-    require_file_permission(pool, user.id, "download_files").await?;
+    require_file_permission(pool, &user, "download_files").await?;
     let safe_path = sanitize_path(path).ok_or(Status::BadRequest)?;
     let full_path = Path::new(STORAGE_ROOT).join(&safe_path);
 
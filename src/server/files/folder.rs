@@ -9,7 +9,7 @@ pub async fn create_folder(
     user: AuthenticatedUser,
     request: Json<CreateFolderRequest>,
 ) -> Result<Json<serde_json::Value>, (Status, Json<serde_json::Value>)> {
-    require_permission(pool, user.id, "create_folders").await?;
+    require_permission(pool, &user, "create_folders").await?;
 
     let name = request.name.trim().to_string();
     if name.is_empty() || name.contains('/') || name.contains('\0') {
@@ -47,5 +47,14 @@ pub async fn create_folder(
         .await
         .map_err(|_| server_error())?;
 
+    let folder_path = path_to_web_string(&safe_parent.join(&name));
+    record_audit(
+        pool,
+        &user,
+        AuditEvent::new("folder.create", "file", &folder_path)
+            .after(serde_json::json!({"path": folder_path})),
+    )
+    .await;
+
     Ok(Json(serde_json::json!({"success": true})))
 }
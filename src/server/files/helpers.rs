@@ -1,16 +1,14 @@
+use crate::auth::{AuthenticatedUser, has_permission};
+use crate::shared::{ApiError, bad_request, forbidden, not_found, server_error};
 use std::path::{Path, PathBuf};
 use tokio::fs;
-use uuid::Uuid;
-
-use crate::auth::has_permission;
-use crate::shared::{ApiError, bad_request, forbidden, not_found, server_error};
 
 pub(crate) async fn require_file_permission(
     pool: &deadpool_postgres::Pool,
-    user_id: Uuid,
+    user: &AuthenticatedUser,
     permission: &str,
 ) -> Result<(), rocket::http::Status> {
-    if has_permission(pool, user_id, permission).await {
+    if has_permission(pool, user, permission).await {
         Ok(())
     } else {
         Err(rocket::http::Status::Forbidden)
@@ -87,7 +87,7 @@ async fn list_entries(
     path: &Path,
     pagination: &PaginationParams,
 ) -> Result<Json<serde_json::Value>, Status> {
-    require_file_permission(pool, user.id, "list_files").await?;
+    require_file_permission(pool, user, "list_files").await?;
     let mut entries = read_dir_entries(path).await?;
     filter_by_search_term(pagination, &mut entries);
 
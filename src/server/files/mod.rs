@@ -1,4 +1,5 @@
 // Re-export shared infrastructure for submodules.
+pub(crate) use crate::audit::{AuditEvent, record_audit};
 pub(crate) use crate::auth::{AuthenticatedUser, require_permission};
 pub(crate) use crate::models::*;
 pub(crate) use crate::shared::*;
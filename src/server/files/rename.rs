@@ -9,7 +9,7 @@ pub async fn rename_path(
     user: AuthenticatedUser,
     request: Json<RenameRequest>,
 ) -> Result<Json<serde_json::Value>, (Status, Json<serde_json::Value>)> {
-    require_permission(pool, user.id, "rename_files").await?;
+    require_permission(pool, &user, "rename_files").await?;
 
     let new_name = request.new_name.trim().to_string();
     if new_name.is_empty() || new_name.contains('/') || new_name.contains('\0') {
@@ -39,5 +39,15 @@ pub async fn rename_path(
         .await
         .map_err(|_| server_error())?;
 
+    let old_path = path_to_web_string(&safe_path);
+    record_audit(
+        pool,
+        &user,
+        AuditEvent::new("file.rename", "file", &old_path)
+            .before(serde_json::json!({"path": old_path}))
+            .after(serde_json::json!({"path": path_to_web_string(&parent.join(&new_name))})),
+    )
+    .await;
+
     Ok(Json(serde_json::json!({"success": true})))
 }
@@ -2,11 +2,13 @@ use deadpool_postgres::Pool;
 use rocket::State;
 use rocket::data::Data;
 use rocket::serde::json::Json;
+use std::net::IpAddr;
 use std::path::{Path, PathBuf};
 use tokio::fs;
 use tokio_postgres::error::SqlState;
 use uuid::Uuid;
 
+use crate::audit::{AuditActor, AuditEvent, record_audit};
 use crate::auth::{AuthenticatedUser, require_permission};
 use crate::shared::*;
 
@@ -20,7 +22,7 @@ pub(crate) async fn list_tus_uploads(
     pool: &State<Pool>,
     user: AuthenticatedUser,
 ) -> Result<Json<Vec<PendingTusUpload>>, ApiError> {
-    require_permission(pool, user.id, "upload_files").await?;
+    require_permission(pool, &user, "upload_files").await?;
     cleanup_expired_uploads(pool).await;
     let client = get_client(pool).await?;
     let rows = client
@@ -51,7 +53,7 @@ pub(crate) async fn create_tus_upload(
     user: AuthenticatedUser,
     headers: TusHeaders,
 ) -> Result<TusResponse, ApiError> {
-    require_permission(pool, user.id, "upload_files").await?;
+    require_permission(pool, &user, "upload_files").await?;
     cleanup_expired_uploads(pool).await;
     create_user_upload(pool, user.id, STORAGE_ROOT, &headers, "/api/uploads").await
 }
@@ -64,7 +66,7 @@ pub(crate) async fn head_tus_upload(
     id: &str,
 ) -> Result<TusResponse, ApiError> {
     let id = parse_upload_id(id)?;
-    require_permission(pool, user.id, "upload_files").await?;
+    require_permission(pool, &user, "upload_files").await?;
     head_user_upload(pool, user.id, id).await
 }
 
@@ -77,11 +79,18 @@ pub(crate) async fn patch_tus_upload(
     data: Data<'_>,
 ) -> Result<TusResponse, ApiError> {
     let id = parse_upload_id(id)?;
-    require_permission(pool, user.id, "upload_files").await?;
+    require_permission(pool, &user, "upload_files").await?;
     let (response, completed) =
         patch_user_upload(pool, user.id, STORAGE_ROOT, &headers, id, data).await?;
     if let Some(target_path) = completed {
         finalize_user_upload(pool, STORAGE_ROOT, id, user.id, &target_path).await?;
+        record_audit(
+            pool,
+            &user,
+            AuditEvent::new("file.upload", "file", &target_path)
+                .after(serde_json::json!({"path": target_path})),
+        )
+        .await;
     }
     Ok(response)
 }
@@ -94,7 +103,7 @@ pub(crate) async fn terminate_tus_upload(
     id: &str,
 ) -> Result<TusResponse, ApiError> {
     let id = parse_upload_id(id)?;
-    require_permission(pool, user.id, "upload_files").await?;
+    require_permission(pool, &user, "upload_files").await?;
     terminate_user_upload(pool, STORAGE_ROOT, user.id, id).await
 }
 
@@ -184,6 +193,7 @@ pub(crate) async fn head_public_tus_upload(
 #[patch("/public/upload-links/<token>/uploads/<id>", data = "<data>")]
 pub(crate) async fn patch_public_tus_upload(
     pool: &State<Pool>,
+    ip: Option<IpAddr>,
     token: &str,
     headers: TusHeaders,
     id: &str,
@@ -223,6 +233,14 @@ pub(crate) async fn patch_public_tus_upload(
 
     if next_offset == progress.length {
         finalize_public_upload(pool, id, link_id, &progress.target_path).await?;
+        record_audit(
+            pool,
+            AuditActor::anonymous(ip),
+            AuditEvent::new("file.upload", "file", &progress.target_path).after(
+                serde_json::json!({"path": progress.target_path, "upload_link_id": link_id}),
+            ),
+        )
+        .await;
     }
     Ok(TusResponse::patched(next_offset))
 }
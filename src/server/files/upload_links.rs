@@ -1,3 +1,4 @@
+use crate::audit::{AuditEvent, record_audit};
 use crate::auth::{AuthenticatedUser, has_permission, require_permission};
 use crate::models::{
     CreateUploadLinkRequest, CreatedUploadLink, PublicTusUpload, PublicUploadLinkStatus, UploadLink,
@@ -76,7 +77,7 @@ pub async fn create_upload_link(
     user: AuthenticatedUser,
     request: Json<CreateUploadLinkRequest>,
 ) -> Result<Json<CreatedUploadLink>, (Status, Json<serde_json::Value>)> {
-    require_permission(pool, user.id, "create_upload_links").await?;
+    require_permission(pool, &user, "create_upload_links").await?;
 
     let target_path = normalize_target_path(&request.target_path)?;
     let token = random_hex::<32>();
@@ -91,6 +92,14 @@ pub async fn create_upload_link(
         )
         .await
         .map_err(db_error)?;
+    let link_id: Uuid = row.get("id");
+    record_audit(
+        pool,
+        &user,
+        AuditEvent::new("upload_link.create", "upload_link", link_id)
+            .after(serde_json::json!({"target_path": target_path})),
+    )
+    .await;
 
     Ok(Json(CreatedUploadLink {
         link: UploadLink {
@@ -112,12 +121,12 @@ pub async fn list_upload_links(
     pool: &State<Pool>,
     user: AuthenticatedUser,
 ) -> Result<Json<Vec<UploadLink>>, (Status, Json<serde_json::Value>)> {
-    let can_view_all = has_permission(pool, user.id, "view_upload_links").await;
-    let can_create = has_permission(pool, user.id, "create_upload_links").await;
+    let can_view_all = has_permission(pool, &user, "view_upload_links").await;
+    let can_create = has_permission(pool, &user, "create_upload_links").await;
     if !can_view_all && !can_create {
         return Err(forbidden());
     }
-    let can_delete_others = has_permission(pool, user.id, "delete_upload_links").await;
+    let can_delete_others = has_permission(pool, &user, "delete_upload_links").await;
 
     let client = get_client(pool).await?;
     let actor = actor_role(&client, user.id).await?;
@@ -180,7 +189,7 @@ pub async fn delete_upload_link(
     let mut client = get_client(pool).await?;
     let row = client
         .query_opt(
-            "SELECT l.created_by_user_id, r.position AS creator_role_position
+            "SELECT l.created_by_user_id, l.target_path, r.position AS creator_role_position
              FROM upload_links l
              JOIN users u ON u.id = l.created_by_user_id
              JOIN roles r ON r.id = u.role_id
@@ -197,7 +206,7 @@ pub async fn delete_upload_link(
 
     if owner.user_id != user.id {
         let actor = actor_role(&client, user.id).await?;
-        let can_delete_others = has_permission(pool, user.id, "delete_upload_links").await;
+        let can_delete_others = has_permission(pool, &user, "delete_upload_links").await;
         if !actor.is_admin && (!can_delete_others || actor.position >= owner.role_position) {
             return Err(forbidden());
         }
@@ -234,6 +243,16 @@ pub async fn delete_upload_link(
         .map_err(db_error)?;
     transaction.commit().await.map_err(db_error)?;
 
+    record_audit(
+        pool,
+        &user,
+        AuditEvent::new("upload_link.delete", "upload_link", id).before(serde_json::json!({
+            "target_path": row.get::<_, String>("target_path"),
+            "created_by_user_id": owner.user_id,
+        })),
+    )
+    .await;
+
     Ok(Json(serde_json::json!({"success": true})))
 }
 
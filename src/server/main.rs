@@ -1,7 +1,9 @@
 #[macro_use]
 extern crate rocket;
 
+mod audit;
 mod auth;
+mod cli;
 mod db;
 mod files;
 mod frontend;
@@ -11,11 +13,20 @@ mod opensubsonic;
 mod shared;
 pub mod test;
 
+use crate::audit::{export_audit_events, list_audit_events, verify_audit_chain};
 use crate::auth::{
-    admin_revoke_api_key, create_api_key, create_default_admin, create_role, create_user,
-    delete_role, delete_user, get_role, list_all_api_keys, list_my_api_keys, list_permissions,
-    list_roles, list_users, login::check_auth, login::login, login::logout, login::me,
-    login::refresh, move_role, revoke_api_key, update_role, update_user_password, update_user_role,
+    CSRF_COOKIE, SigningKeys, accept_invitation, add_group_member, admin_revoke_api_key,
+    complete_password_reset, create_api_key, create_app_password, create_default_admin,
+    create_group, create_invitation, create_password_reset, create_role, create_user, delete_group,
+    delete_role, delete_user, get_api_key_usage, get_group, get_my_api_key_usage,
+    get_password_reset, get_public_invitation, get_role, has_session_cookie, jwks,
+    list_all_api_keys, list_app_passwords, list_groups, list_invitations, list_my_api_keys,
+    list_permissions, list_roles, list_signing_keys, list_users, login::check_auth, login::login,
+    login::logout, login::logout_all, login::me, login::refresh, move_role, new_csrf_cookie,
+    remove_group_member, request_password_reset, revoke_api_key, revoke_app_password,
+    revoke_invitation, revoke_sessions, rotate_signing_key, start_impersonation,
+    stop_impersonation, update_group, update_role, update_user_disabled, update_user_email,
+    update_user_expiry, update_user_password, update_user_role,
 };
 use crate::files::{
     create_folder, create_public_tus_upload, create_tus_upload, create_upload_link, delete_path,
@@ -26,55 +37,114 @@ use crate::files::{
 };
 use crate::frontend::frontend_fallback;
 use crate::music::{
-    add_to_library, create_music_upload, delete_song, get_song_cover, head_music_upload,
-    list_personal_library, list_song_selection, list_songs, music_tus_options, patch_music_upload,
-    remove_from_library, scan_songs, set_library_membership, terminate_music_upload,
-    update_song_cover, update_song_tags,
+    add_to_library, create_music_upload, create_radio_station, create_transcoding_profile,
+    delete_radio_station, delete_song, delete_transcoding_profile, get_song_cover, get_song_lyrics,
+    get_transcoding_defaults, head_music_upload, list_personal_library, list_radio_stations,
+    list_song_selection, list_songs, list_transcoding_profiles, music_tus_options,
+    patch_music_upload, remove_from_library, scan_songs, set_library_membership,
+    set_transcoding_defaults, spawn_replay_gain_analyzer, terminate_music_upload,
+    update_radio_station, update_song_cover, update_song_tags, update_transcoding_profile,
 };
 use crate::opensubsonic::{
-    create_playlist, delete_playlist, get_album, get_album_list, get_album_list2, get_artist,
-    get_artists, get_bookmarks, get_cover_art, get_genres, get_indexes, get_license,
+    create_bookmark, create_internet_radio_station, create_playlist, create_share, delete_bookmark,
+    delete_internet_radio_station, delete_playlist, delete_share, get_album, get_album_list,
+    get_album_list2, get_artist, get_artists, get_bookmarks, get_cover_art, get_genres,
+    get_indexes, get_internet_radio_stations, get_license, get_lyrics, get_lyrics_by_song_id,
     get_music_directory, get_music_folders, get_now_playing, get_open_subsonic_extensions,
-    get_playlist, get_playlists, get_random_songs, get_song, get_starred, get_starred2, ping,
-    scrobble, search2, search3, star, stream, subsonic_auth_error, subsonic_download, unstar,
-    update_playlist,
+    get_play_queue, get_play_queue_by_index, get_playlist, get_playlists, get_public_share,
+    get_random_songs, get_shares, get_song, get_starred, get_starred2, ping, save_play_queue,
+    save_play_queue_by_index, scrobble, search2, search3, set_rating, star, stream,
+    stream_public_share, subsonic_auth_error, subsonic_download, unstar,
+    update_internet_radio_station, update_playlist, update_share,
 };
-use crate::shared::api_error;
+use crate::shared::{AppSecretKey, api_error};
 use rocket::fairing::{Fairing, Info, Kind};
 use rocket::fs::FileServer;
 use rocket::http::uri::Origin;
-use rocket::{Build, Data, Request, Rocket};
+use rocket::{Build, Data, Orbit, Request, Response, Rocket};
+use std::process::ExitCode;
 
 fn prepare_dirs() {
     std::fs::create_dir_all(crate::shared::STORAGE_ROOT).ok();
     std::fs::create_dir_all(crate::shared::MUSIC_ROOT).ok();
+    std::fs::create_dir_all(crate::shared::TRANSCODE_CACHE_ROOT).ok();
     std::fs::create_dir_all(crate::shared::BUILD_ROOT).ok();
 }
 
-#[launch]
-fn rocket() -> _ {
+#[rocket::main]
+async fn main() -> ExitCode {
     dotenvy::dotenv().ok();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if cli::is_command(&args) {
+        return cli::run(&args).await;
+    }
+
+    let secret = match AppSecretKey::from_env() {
+        Ok(secret) => secret,
+        Err(error) => {
+            eprintln!("{error}");
+            return ExitCode::FAILURE;
+        }
+    };
+
     prepare_dirs();
+    match rocket(secret).launch().await {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("{error}");
+            ExitCode::FAILURE
+        }
+    }
+}
 
+/// Rocket configuration that takes the client address from the connection itself.
+///
+/// Client addresses gate API key allow-lists and are recorded in the audit log, so a
+/// forwarded-address header is only honoured when `ROCKET_IP_HEADER` names the one a trusted
+/// reverse proxy sets.
+fn rocket_config() -> rocket::figment::Figment {
+    let figment = rocket::Config::figment();
+    if std::env::var("ROCKET_IP_HEADER").is_ok_and(|header| !header.is_empty()) {
+        figment
+    } else {
+        figment.merge(("ip_header", false))
+    }
+}
+
+fn rocket(secret: AppSecretKey) -> Rocket<Build> {
     let pool = db::init_pool();
 
-    rocket::build()
+    rocket::custom(rocket_config())
         .manage(pool)
-        .attach(DatabaseFeatures)
+        .manage(secret)
+        .manage(SigningKeys::from_env(secret))
+        .attach(DatabaseMigrations)
+        .attach(SigningKeyLoader)
+        .attach(ReplayGainAnalyzer)
         .attach(AdminBootstrap)
         .attach(OpenSubsonicViewCompatibility)
+        .attach(CsrfCookie)
         .mount(
             "/api",
             routes![
                 login,
                 logout,
+                logout_all,
                 me,
                 refresh,
                 check_auth,
+                start_impersonation,
+                stop_impersonation,
                 create_user,
                 list_users,
                 update_user_role,
                 update_user_password,
+                update_user_email,
+                update_user_disabled,
+                update_user_expiry,
+                revoke_sessions,
+                create_password_reset,
                 delete_user,
                 list_roles,
                 get_role,
@@ -83,6 +153,21 @@ fn rocket() -> _ {
                 move_role,
                 delete_role,
                 list_permissions,
+                list_groups,
+                get_group,
+                create_group,
+                update_group,
+                delete_group,
+                add_group_member,
+                remove_group_member,
+                create_invitation,
+                list_invitations,
+                revoke_invitation,
+                get_public_invitation,
+                accept_invitation,
+                request_password_reset,
+                get_password_reset,
+                complete_password_reset,
                 list_root,
                 list_directory,
                 download,
@@ -104,13 +189,26 @@ fn rocket() -> _ {
                 list_upload_links,
                 delete_upload_link,
                 get_public_upload_link,
+                get_public_share,
+                stream_public_share,
                 list_songs,
                 list_song_selection,
                 delete_song,
                 update_song_tags,
+                get_song_lyrics,
                 get_song_cover,
                 update_song_cover,
                 scan_songs,
+                list_transcoding_profiles,
+                create_transcoding_profile,
+                update_transcoding_profile,
+                delete_transcoding_profile,
+                get_transcoding_defaults,
+                set_transcoding_defaults,
+                list_radio_stations,
+                create_radio_station,
+                update_radio_station,
+                delete_radio_station,
                 list_personal_library,
                 add_to_library,
                 remove_from_library,
@@ -123,8 +221,18 @@ fn rocket() -> _ {
                 list_my_api_keys,
                 create_api_key,
                 revoke_api_key,
+                get_my_api_key_usage,
                 list_all_api_keys,
+                get_api_key_usage,
+                list_app_passwords,
+                create_app_password,
+                revoke_app_password,
                 admin_revoke_api_key,
+                list_audit_events,
+                export_audit_events,
+                verify_audit_chain,
+                list_signing_keys,
+                rotate_signing_key,
             ],
         )
         .register("/api", catchers![api_error])
@@ -135,6 +243,22 @@ fn rocket() -> _ {
                 get_license,
                 get_open_subsonic_extensions,
                 get_bookmarks,
+                create_bookmark,
+                delete_bookmark,
+                get_play_queue,
+                save_play_queue,
+                get_play_queue_by_index,
+                save_play_queue_by_index,
+                get_internet_radio_stations,
+                get_lyrics,
+                get_lyrics_by_song_id,
+                create_internet_radio_station,
+                update_internet_radio_station,
+                delete_internet_radio_station,
+                get_shares,
+                create_share,
+                update_share,
+                delete_share,
                 get_music_folders,
                 get_indexes,
                 get_music_directory,
@@ -157,6 +281,7 @@ fn rocket() -> _ {
                 update_playlist,
                 delete_playlist,
                 star,
+                set_rating,
                 unstar,
                 get_starred,
                 get_starred2,
@@ -165,19 +290,20 @@ fn rocket() -> _ {
                 subsonic_auth_error,
             ],
         )
+        .mount("/", routes![jwks])
         .mount("/", FileServer::from(crate::shared::BUILD_ROOT))
         .mount("/", routes![frontend_fallback])
 }
 
-// Fairing to apply the idempotent database feature scripts before bootstrapping users.
+// Fairing to run pending database migrations before bootstrapping users.
 
-struct DatabaseFeatures;
+struct DatabaseMigrations;
 
 #[rocket::async_trait]
-impl Fairing for DatabaseFeatures {
+impl Fairing for DatabaseMigrations {
     fn info(&self) -> Info {
         Info {
-            name: "Database Features",
+            name: "Database Migrations",
             kind: Kind::Ignite,
         }
     }
@@ -186,16 +312,85 @@ impl Fairing for DatabaseFeatures {
         let pool = match rocket.state::<deadpool_postgres::Pool>() {
             Some(pool) => pool.clone(),
             None => {
-                eprintln!("Database features: DB pool not available");
+                eprintln!("Database migrations: DB pool not available");
                 return Err(rocket);
             }
         };
-        if let Err(error) = db::apply_feature_scripts(&pool).await {
-            eprintln!("Database features: {error}");
+        match db::migrate(&pool).await {
+            Ok(applied) => {
+                for name in applied {
+                    println!("Database migrations: applied {name}");
+                }
+                Ok(rocket)
+            }
+            Err(error) => {
+                eprintln!("Database migrations: {error}");
+                Err(rocket)
+            }
+        }
+    }
+}
+
+// Fairing to load token signing keys once the schema exists, then keep them fresh.
+
+struct SigningKeyLoader;
+
+#[rocket::async_trait]
+impl Fairing for SigningKeyLoader {
+    fn info(&self) -> Info {
+        Info {
+            name: "Signing Keys",
+            kind: Kind::Ignite | Kind::Liftoff,
+        }
+    }
+
+    async fn on_ignite(&self, rocket: Rocket<Build>) -> Result<Rocket<Build>, Rocket<Build>> {
+        let (Some(pool), Some(keys)) = (
+            rocket.state::<deadpool_postgres::Pool>(),
+            rocket.state::<SigningKeys>(),
+        ) else {
+            eprintln!("Signing keys: DB pool not available");
+            return Err(rocket);
+        };
+        let result = match pool.get().await {
+            Ok(client) => keys.load(&client).await,
+            Err(error) => Err(error.to_string()),
+        };
+        if let Err(error) = result {
+            eprintln!("Signing keys: {error}");
             return Err(rocket);
         }
         Ok(rocket)
     }
+
+    async fn on_liftoff(&self, rocket: &Rocket<Orbit>) {
+        if let (Some(pool), Some(keys)) = (
+            rocket.state::<deadpool_postgres::Pool>(),
+            rocket.state::<SigningKeys>(),
+        ) {
+            keys.spawn_refresh(pool.clone());
+        }
+    }
+}
+
+// Fairing to start the background ReplayGain analyser once the server is up.
+
+struct ReplayGainAnalyzer;
+
+#[rocket::async_trait]
+impl Fairing for ReplayGainAnalyzer {
+    fn info(&self) -> Info {
+        Info {
+            name: "ReplayGain Analyzer",
+            kind: Kind::Liftoff,
+        }
+    }
+
+    async fn on_liftoff(&self, rocket: &Rocket<Orbit>) {
+        if let Some(pool) = rocket.state::<deadpool_postgres::Pool>() {
+            spawn_replay_gain_analyzer(pool.clone());
+        }
+    }
 }
 
 // Fairing to bootstrap admin user on startup
@@ -224,6 +419,40 @@ impl Fairing for AdminBootstrap {
     }
 }
 
+// Fairing to hand a CSRF cookie to sessions that lack one, such as those signed in
+// before CSRF protection existed. It runs on error responses too, so a client
+// whose access token has expired can still refresh.
+
+struct CsrfCookie;
+
+#[rocket::async_trait]
+impl Fairing for CsrfCookie {
+    fn info(&self) -> Info {
+        Info {
+            name: "CSRF Cookie",
+            kind: Kind::Response,
+        }
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        if !request.uri().path().starts_with("/api/")
+            || request.cookies().get(CSRF_COOKIE).is_some()
+            || !has_session_cookie(request.cookies())
+        {
+            return;
+        }
+        let prefix = format!("{CSRF_COOKIE}=");
+        if response
+            .headers()
+            .get("Set-Cookie")
+            .any(|cookie| cookie.starts_with(&prefix))
+        {
+            return;
+        }
+        response.adjoin_header(new_csrf_cookie());
+    }
+}
+
 struct OpenSubsonicViewCompatibility;
 
 #[rocket::async_trait]
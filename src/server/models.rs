@@ -13,6 +13,10 @@ pub struct User {
     pub password_hash: String,
     pub role_id: i32,
     pub role_name: String,
+    pub email: Option<String>,
+    pub disabled_at: Option<DateTime<Utc>>,
+    pub disabled_reason: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -42,6 +46,7 @@ pub struct CreateUserRequest {
     pub username: String,
     pub password: String,
     pub role_name: Option<String>,
+    pub email: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -56,6 +61,25 @@ pub struct UpdateUserPasswordRequest {
     pub password: String,
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct UpdateUserEmailRequest {
+    pub email: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct UpdateUserDisabledRequest {
+    pub disabled: bool,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct UpdateUserExpiryRequest {
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
 // JWT Claims
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -64,10 +88,38 @@ pub struct Claims {
     pub sub: String, // user_id
     pub username: String,
     pub role: String,
+    /// `users.token_version` at issue time; a mismatch means the token was revoked.
+    #[serde(default)]
+    pub ver: i32,
+    /// Set on impersonation tokens: the admin acting as `sub`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub act: Option<ActorClaim>,
     pub exp: usize,
     pub iat: usize,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ActorClaim {
+    pub sub: String,
+    pub username: String,
+    /// The actor's `users.token_version`, so signing them out also ends the impersonation.
+    pub ver: i32,
+    /// `impersonation_events.session_id` of the impersonation this token belongs to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<Uuid>,
+}
+
+/// The real user behind an impersonated request.
+#[derive(Debug, Clone, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Impersonator {
+    pub id: Uuid,
+    pub username: String,
+    #[serde(skip)]
+    pub session_id: Option<Uuid>,
+}
+
 // Roles & Permissions
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -135,6 +187,79 @@ pub struct Permission {
     pub group_name: String,
 }
 
+/// Where an effective permission comes from: the user's role or one of their groups.
+#[derive(Debug, Clone, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct PermissionSource {
+    pub kind: &'static str,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct EffectivePermission {
+    pub name: String,
+    pub sources: Vec<PermissionSource>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct MeResponse {
+    pub user: User,
+    pub permissions: Vec<EffectivePermission>,
+    pub impersonated_by: Option<Impersonator>,
+}
+
+// User groups
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Group {
+    pub id: i32,
+    pub name: String,
+    pub display_name: String,
+    pub description: Option<String>,
+    pub permissions: Vec<String>,
+    pub member_count: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct GroupMember {
+    pub user_id: Uuid,
+    pub username: String,
+    pub added_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct GroupWithMembers {
+    #[serde(flatten)]
+    pub group: Group,
+    pub members: Vec<GroupMember>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CreateGroupRequest {
+    pub name: String,
+    pub display_name: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub permissions: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct UpdateGroupRequest {
+    pub display_name: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub permissions: Vec<String>,
+}
+
 // One-time upload links
 
 #[derive(Debug, Deserialize)]
@@ -191,6 +316,79 @@ pub struct PublicUploadLinkStatus {
     pub session: Option<PublicTusUpload>,
 }
 
+// Invitation links
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CreateInvitationRequest {
+    pub role_name: Option<String>,
+    pub expires_in_hours: Option<i64>,
+    pub max_uses: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Invitation {
+    pub id: Uuid,
+    pub role_name: String,
+    pub role_display_name: String,
+    pub created_by_user_id: Uuid,
+    pub created_by_username: String,
+    pub max_uses: i32,
+    pub use_count: i32,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CreatedInvitation {
+    pub invitation: Invitation,
+    pub token: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct PublicInvitationStatus {
+    pub role_display_name: String,
+    pub expires_at: DateTime<Utc>,
+    pub remaining_uses: i32,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct AcceptInvitationRequest {
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CreatedPasswordReset {
+    pub token: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ForgotPasswordRequest {
+    /// Username or email address of the account.
+    pub login: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct PublicPasswordResetStatus {
+    pub username: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CompletePasswordResetRequest {
+    pub password: String,
+}
+
 // Pagination
 
 #[derive(Debug, Deserialize, rocket::form::FromForm)]
@@ -225,4 +423,5 @@ pub enum AuthError {
     MissingToken,
     InvalidToken,
     ExpiredToken,
+    CsrfMismatch,
 }
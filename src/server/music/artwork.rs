@@ -1,3 +1,4 @@
+use super::{AuditEvent, record_audit};
 use crate::auth::{AuthenticatedUser, require_permission};
 use crate::shared::{
     ApiError, MUSIC_ROOT, bad_request, db_error, get_client, not_found, server_error, status_error,
@@ -241,9 +242,14 @@ pub(crate) async fn update_song_cover(
     id: &str,
     data: Data<'_>,
 ) -> Result<Json<super::SongResponse>, ApiError> {
-    require_permission(pool, user.id, "music_edit_tags").await?;
+    require_permission(pool, &user, "music_edit_tags").await?;
     let song_id = Uuid::parse_str(id).map_err(|_| not_found("Invalid song ID"))?;
     let (client, path) = song_artwork_path(pool, song_id, false).await?;
+    let had_cover: bool = client
+        .query_one("SELECT has_cover_art FROM songs WHERE id = $1", &[&song_id])
+        .await
+        .map_err(db_error)?
+        .get("has_cover_art");
 
     let bytes = data
         .open(10.mebibytes())
@@ -260,8 +266,12 @@ pub(crate) async fn update_song_cover(
     if bytes.is_empty() {
         return Err(bad_request("Cover image is empty"));
     }
+    let cover_bytes = bytes.len();
 
-    match tokio::task::spawn_blocking(move || write_embedded_artwork(&path, bytes)).await {
+    let lock = super::tag_write_lock(&path);
+    let written = lock.lock().await;
+    let write_path = path.clone();
+    match tokio::task::spawn_blocking(move || write_embedded_artwork(&write_path, bytes)).await {
         Ok(Ok(())) => {}
         Ok(Err(ArtworkError::InvalidImage)) => return Err(bad_request("Invalid cover image")),
         Ok(Err(error)) => {
@@ -274,17 +284,31 @@ pub(crate) async fn update_song_cover(
         }
     }
 
+    let size_bytes = tokio::fs::metadata(&path)
+        .await
+        .map(|metadata| metadata.len() as i64)
+        .map_err(|_| server_error())?;
     client
         .execute(
-            "UPDATE songs SET has_cover_art = TRUE, updated_at = NOW() WHERE id = $1",
-            &[&song_id],
+            "UPDATE songs SET has_cover_art = TRUE, size_bytes = $2, updated_at = NOW()
+             WHERE id = $1",
+            &[&song_id, &size_bytes],
         )
         .await
         .map_err(db_error)?;
+    drop(written);
     let row = client
         .query_one("SELECT * FROM songs WHERE id = $1", &[&song_id])
         .await
         .map_err(db_error)?;
+    record_audit(
+        pool,
+        &user,
+        AuditEvent::new("song.cover.update", "song", song_id)
+            .before(serde_json::json!({"has_cover_art": had_cover}))
+            .after(serde_json::json!({"has_cover_art": true, "cover_bytes": cover_bytes})),
+    )
+    .await;
     Ok(Json(super::row_to_song(&row)))
 }
 
@@ -0,0 +1,208 @@
+use super::*;
+
+use lofty::tag::Tag;
+use std::collections::HashSet;
+use uuid::Uuid;
+
+// ── Artist and genre credits ──
+
+/// Artists and genres of a song, in tag order; each artist with its `song_artists.role`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SongCredits {
+    pub artists: Vec<(String, &'static str)>,
+    pub genres: Vec<String>,
+}
+
+/// Markers that introduce featured artists in an artist tag, matched case-insensitively.
+const FEATURING_MARKERS: &[&str] = &[
+    " featuring ",
+    " feat. ",
+    " feat ",
+    " ft. ",
+    " ft ",
+    " (featuring ",
+    " (feat. ",
+    " (ft. ",
+    " [feat. ",
+];
+
+/// Split multi-value tag text on `;` and NUL, dropping blanks and repeated names.
+///
+/// `/`, `,` and `&` are left alone because they appear in names such as "AC/DC".
+pub(crate) fn split_values<'a>(values: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut seen = HashSet::new();
+    values
+        .into_iter()
+        .flat_map(|value| value.split([';', '\0']))
+        .map(str::trim)
+        .filter(|value| !value.is_empty() && seen.insert(value.to_lowercase()))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Split "A feat. B & C" (or "A (feat. B, C)") into the main artist and the featured ones.
+pub(crate) fn split_featured(artist: &str) -> (String, Vec<String>) {
+    let lower = artist.to_lowercase();
+    let found = FEATURING_MARKERS
+        .iter()
+        .filter_map(|marker| lower.find(marker).map(|at| (at, marker.len())))
+        .min();
+    // Lowercasing can change byte lengths; only split where the offsets still line up.
+    let Some((at, len)) = found.filter(|&(at, len)| {
+        lower.len() == artist.len()
+            && artist.is_char_boundary(at)
+            && artist.is_char_boundary(at + len)
+    }) else {
+        return (artist.trim().to_string(), Vec::new());
+    };
+    let main = artist[..at].trim();
+    let featured = artist[at + len..].trim().trim_end_matches([')', ']']);
+    let featured = split_values(
+        featured
+            .split(", ")
+            .flat_map(|part| part.split(" & "))
+            .flat_map(|part| part.split(" and ")),
+    );
+    if main.is_empty() {
+        return (artist.trim().to_string(), Vec::new());
+    }
+    (main.to_string(), featured)
+}
+
+fn push_artists(credits: &mut SongCredits, names: Vec<String>, role: &'static str) {
+    for name in names {
+        if !credits
+            .artists
+            .iter()
+            .any(|(existing, existing_role)| *existing_role == role && *existing == name)
+        {
+            credits.artists.push((name, role));
+        }
+    }
+}
+
+/// Credits from a tag; `artist` is the stored artist text, used when the tag has none.
+pub(crate) fn read_credits(tag: Option<&Tag>, artist: &str) -> SongCredits {
+    let mut credits = SongCredits::default();
+    let strings = |key: ItemKey| -> Vec<String> {
+        tag.map(|t| split_values(t.get_strings(key)))
+            .unwrap_or_default()
+    };
+
+    // An explicit ARTISTS tag already lists each artist; otherwise split the display text.
+    let listed = strings(ItemKey::TrackArtists);
+    if listed.is_empty() {
+        let display = strings(ItemKey::TrackArtist);
+        let display = if display.is_empty() {
+            vec![artist.to_string()]
+        } else {
+            display
+        };
+        for value in display {
+            let (main, featured) = split_featured(&value);
+            push_artists(&mut credits, vec![main], "artist");
+            push_artists(&mut credits, featured, "featured");
+        }
+    } else {
+        push_artists(&mut credits, listed, "artist");
+    }
+    push_artists(&mut credits, strings(ItemKey::AlbumArtist), "albumartist");
+    push_artists(&mut credits, strings(ItemKey::Composer), "composer");
+    push_artists(&mut credits, strings(ItemKey::Performer), "performer");
+    push_artists(&mut credits, strings(ItemKey::Conductor), "conductor");
+    push_artists(&mut credits, strings(ItemKey::Lyricist), "lyricist");
+    credits.genres = strings(ItemKey::Genre);
+    credits
+}
+
+/// Replace the artist and genre rows of a song.
+pub(crate) async fn store_credits(
+    client: &deadpool_postgres::Object,
+    song_id: Uuid,
+    credits: &SongCredits,
+) -> Result<(), tokio_postgres::Error> {
+    client
+        .execute("DELETE FROM song_artists WHERE song_id = $1", &[&song_id])
+        .await?;
+    client
+        .execute("DELETE FROM song_genres WHERE song_id = $1", &[&song_id])
+        .await?;
+    for (position, (name, role)) in credits.artists.iter().enumerate() {
+        let artist_id: Uuid = client
+            .query_one(
+                "INSERT INTO artists (name) VALUES ($1) \
+                 ON CONFLICT (name) DO UPDATE SET name = EXCLUDED.name RETURNING id",
+                &[name],
+            )
+            .await?
+            .get("id");
+        client
+            .execute(
+                "INSERT INTO song_artists (song_id, artist_id, role, position) \
+                 VALUES ($1, $2, $3, $4) ON CONFLICT DO NOTHING",
+                &[&song_id, &artist_id, role, &(position as i16)],
+            )
+            .await?;
+    }
+    for (position, genre) in credits.genres.iter().enumerate() {
+        client
+            .execute(
+                "INSERT INTO song_genres (song_id, genre, position) VALUES ($1, $2, $3) \
+                 ON CONFLICT DO NOTHING",
+                &[&song_id, genre, &(position as i16)],
+            )
+            .await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_multi_value_tags() {
+        assert_eq!(
+            split_values(["Rock; Pop", "rock", "Jazz\0Blues", " "]),
+            vec!["Rock", "Pop", "Jazz", "Blues"]
+        );
+        assert_eq!(split_values(["AC/DC"]), vec!["AC/DC"]);
+    }
+
+    #[test]
+    fn splits_featured_artists() {
+        assert_eq!(
+            split_featured("A feat. B & C"),
+            ("A".to_string(), vec!["B".to_string(), "C".to_string()])
+        );
+        assert_eq!(
+            split_featured("A (Feat. B, C and D)"),
+            (
+                "A".to_string(),
+                vec!["B".to_string(), "C".to_string(), "D".to_string()]
+            )
+        );
+        assert_eq!(
+            split_featured("A ft B"),
+            ("A".to_string(), vec!["B".to_string()])
+        );
+        assert_eq!(
+            split_featured("Simon & Garfunkel"),
+            ("Simon & Garfunkel".to_string(), Vec::new())
+        );
+        assert_eq!(
+            split_featured("Feat. Someone"),
+            ("Feat. Someone".to_string(), Vec::new())
+        );
+    }
+
+    #[test]
+    fn untagged_songs_credit_the_stored_artist() {
+        let credits = read_credits(None, "A feat. B");
+        assert_eq!(
+            credits.artists,
+            vec![("A".to_string(), "artist"), ("B".to_string(), "featured")]
+        );
+        assert!(credits.genres.is_empty());
+    }
+}
@@ -20,6 +20,10 @@ pub(crate) fn row_to_song(row: &tokio_postgres::Row) -> SongResponse {
         size_bytes: row.get("size_bytes"),
         format: row.get("format"),
         bitrate_kbps: row.get("bitrate_kbps"),
+        sample_rate: row.get("sample_rate"),
+        bit_depth: row.get("bit_depth"),
+        channel_count: row.get("channel_count"),
+        codec: row.get("codec"),
         has_cover_art: row.get("has_cover_art"),
         in_library: row.try_get("in_library").unwrap_or(false),
         created_at: row
@@ -31,8 +35,12 @@ pub(crate) fn row_to_song(row: &tokio_postgres::Row) -> SongResponse {
     }
 }
 
+/// Lossless songs above either limit count as hi-res.
+const HIRES_SAMPLE_RATE: i32 = 48_000;
+const HIRES_BIT_DEPTH: i16 = 16;
+
 #[allow(clippy::too_many_arguments)]
-#[get("/music/songs?<page>&<limit>&<search>&<artist>&<album>&<genre>")]
+#[get("/music/songs?<page>&<limit>&<search>&<artist>&<album>&<genre>&<lossless>&<hires>")]
 pub(crate) async fn list_songs(
     pool: &State<Pool>,
     user: AuthenticatedUser,
@@ -42,6 +50,8 @@ pub(crate) async fn list_songs(
     artist: Option<String>,
     album: Option<String>,
     genre: Option<String>,
+    lossless: Option<bool>,
+    hires: Option<bool>,
 ) -> Result<Json<SongListResponse>, (Status, Json<serde_json::Value>)> {
     let page = Page::new(page, limit);
     let client = get_client(pool).await?;
@@ -73,6 +83,21 @@ pub(crate) async fn list_songs(
         params.push(g);
         idx += 1;
     }
+    // `true` keeps only matching songs, `false` only the others.
+    let lossless_codecs = LOSSLESS_CODECS;
+    if let Some(only) = lossless {
+        conditions.push(format!("(COALESCE(codec = ANY(${idx}), FALSE) = {only})"));
+        params.push(&lossless_codecs);
+        idx += 1;
+    }
+    if let Some(only) = hires {
+        conditions.push(format!(
+            "(COALESCE(codec = ANY(${idx}) AND (sample_rate > {HIRES_SAMPLE_RATE} \
+              OR bit_depth > {HIRES_BIT_DEPTH}), FALSE) = {only})"
+        ));
+        params.push(&lossless_codecs);
+        idx += 1;
+    }
 
     let where_clause = if conditions.is_empty() {
         String::new()
@@ -167,7 +192,7 @@ pub(crate) async fn delete_song(
     user: AuthenticatedUser,
     id: &str,
 ) -> Result<Json<serde_json::Value>, (Status, Json<serde_json::Value>)> {
-    require_permission(pool, user.id, "music_delete").await?;
+    require_permission(pool, &user, "music_delete").await?;
     let song_id = Uuid::parse_str(id).map_err(|_| not_found("Invalid song ID"))?;
     let client = get_client(pool).await?;
     let row = client
@@ -186,5 +211,12 @@ pub(crate) async fn delete_song(
         .execute("DELETE FROM songs WHERE id = $1", &[&song_id])
         .await
         .map_err(db_error)?;
+    record_audit(
+        pool,
+        &user,
+        AuditEvent::new("song.delete", "song", song_id)
+            .before(serde_json::json!({"file_path": file_path})),
+    )
+    .await;
     Ok(Json(serde_json::json!({"message": "Song deleted"})))
 }
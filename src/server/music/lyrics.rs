@@ -0,0 +1,437 @@
+use super::*;
+
+use lofty::config::{ParseOptions, WriteOptions};
+use lofty::id3::v2::{Frame, FrameId, SyncTextContentType, SynchronizedTextFrame, TimestampFormat};
+use lofty::mpeg::MpegFile;
+use lofty::tag::Tag;
+use std::borrow::Cow;
+use std::path::Path;
+use uuid::Uuid;
+
+// ── Types ──
+
+/// One line of lyrics; `start` is the offset in milliseconds for synced lyrics.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct LyricLine {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start: Option<u32>,
+    pub value: String,
+}
+
+/// A song's unsynced or synced lyrics and where they were read from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SongLyrics {
+    pub synced: bool,
+    pub source: &'static str,
+    pub lang: String,
+    pub lines: Vec<LyricLine>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct LyricsResponse {
+    pub lyrics: Option<String>,
+    pub synced_lyrics: Option<String>,
+}
+
+const UNKNOWN_LANGUAGE: &str = "und";
+
+// ── LRC ──
+
+/// Parse `mm:ss`, `mm:ss.xx` or `mm:ss.xxx` into milliseconds.
+fn parse_timestamp(value: &str) -> Option<u32> {
+    let (minutes, rest) = value.split_once(':')?;
+    let (seconds, fraction) = rest.split_once('.').unwrap_or((rest, ""));
+    let all_digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    if !all_digits(minutes) || !all_digits(seconds) || seconds.len() > 2 {
+        return None;
+    }
+    if !fraction.is_empty() && (!all_digits(fraction) || fraction.len() > 3) {
+        return None;
+    }
+    let minutes: u32 = minutes.parse().ok()?;
+    let seconds: u32 = seconds.parse().ok()?;
+    if seconds >= 60 {
+        return None;
+    }
+    let millis = match fraction.len() {
+        0 => 0,
+        1 => fraction.parse::<u32>().ok()? * 100,
+        2 => fraction.parse::<u32>().ok()? * 10,
+        _ => fraction.parse::<u32>().ok()?,
+    };
+    minutes
+        .checked_mul(60_000)?
+        .checked_add(seconds * 1000 + millis)
+}
+
+/// Parse LRC text into lines ordered by start time.
+///
+/// A line may carry several timestamps (`[00:12.00][00:48.00]Chorus`); `[offset:±ms]` shifts
+/// every line and other ID tags (`[ar:...]`, `[ti:...]`) are ignored. Returns no lines when the
+/// text has no timestamps, so plain lyrics can be told apart from LRC.
+pub(crate) fn parse_lrc(text: &str) -> Vec<LyricLine> {
+    let mut offset = 0i64;
+    let mut timed = Vec::new();
+    for raw in text.lines() {
+        let mut rest = raw.trim();
+        let mut starts = Vec::new();
+        while let Some(body) = rest.strip_prefix('[') {
+            let Some((tag, after)) = body.split_once(']') else {
+                break;
+            };
+            if let Some(start) = parse_timestamp(tag.trim()) {
+                starts.push(start);
+            } else if let Some(value) = tag.strip_prefix("offset:") {
+                offset = value.trim().parse().unwrap_or(0);
+            }
+            rest = after;
+        }
+        for start in starts {
+            timed.push((start, rest.trim().to_string()));
+        }
+    }
+    // A positive offset makes lyrics appear sooner.
+    let mut lines: Vec<LyricLine> = timed
+        .into_iter()
+        .map(|(start, value)| LyricLine {
+            start: Some((i64::from(start) - offset).clamp(0, i64::from(u32::MAX)) as u32),
+            value,
+        })
+        .collect();
+    lines.sort_by_key(|line| line.start);
+    lines
+}
+
+/// Render synced lines back to LRC text for editing.
+pub(crate) fn to_lrc(lines: &[LyricLine]) -> String {
+    lines
+        .iter()
+        .map(|line| {
+            let start = line.start.unwrap_or(0);
+            format!(
+                "[{:02}:{:02}.{:02}]{}",
+                start / 60_000,
+                start / 1000 % 60,
+                start % 1000 / 10,
+                line.value
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Split plain lyrics into lines, keeping blank lines between verses.
+pub(crate) fn plain_lines(text: &str) -> Vec<LyricLine> {
+    text.trim()
+        .lines()
+        .map(|line| LyricLine {
+            start: None,
+            value: line.trim_end().to_string(),
+        })
+        .collect()
+}
+
+/// Plain text of a set of lyrics, one line per line.
+pub(crate) fn lyrics_text(lines: &[LyricLine]) -> String {
+    lines
+        .iter()
+        .map(|line| line.value.as_str())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// ── Extraction ──
+
+/// The `.lrc` file next to a track: same directory and file stem.
+pub(crate) fn lrc_sidecar_path(path: &Path) -> std::path::PathBuf {
+    path.with_extension("lrc")
+}
+
+fn is_mpeg(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("mp3"))
+}
+
+/// Synced lyrics from an ID3v2 SYLT frame with millisecond timestamps.
+fn read_sylt(path: &Path) -> Option<SongLyrics> {
+    let mut file = std::fs::File::open(path).ok()?;
+    let mpeg = MpegFile::read_from(&mut file, ParseOptions::new()).ok()?;
+    let Some(Frame::Binary(frame)) = mpeg.id3v2()?.get(&FrameId::Valid(Cow::Borrowed("SYLT")))
+    else {
+        return None;
+    };
+    let sylt = SynchronizedTextFrame::parse(&frame.data, frame.flags()).ok()?;
+    if sylt.timestamp_format != TimestampFormat::MS
+        || sylt.content_type != SyncTextContentType::Lyrics
+    {
+        return None;
+    }
+    let lines: Vec<LyricLine> = sylt
+        .content
+        .into_iter()
+        .map(|(start, value)| LyricLine {
+            start: Some(start),
+            value: value.trim_end().to_string(),
+        })
+        .collect();
+    if lines.is_empty() {
+        return None;
+    }
+    let lang = std::str::from_utf8(&sylt.language)
+        .ok()
+        .filter(|lang| lang.bytes().all(|b| b.is_ascii_alphabetic()))
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_else(|| UNKNOWN_LANGUAGE.to_string());
+    Some(SongLyrics {
+        synced: true,
+        source: "tag",
+        lang,
+        lines,
+    })
+}
+
+/// Remove the SYLT synced lyrics frame from an MP3's ID3v2 tag, if it has one.
+pub(crate) fn remove_sylt(path: &Path) -> Result<(), String> {
+    if !is_mpeg(path) {
+        return Ok(());
+    }
+    let mut file = std::fs::File::open(path).map_err(|e| format!("Cannot open file: {e}"))?;
+    let mut mpeg = MpegFile::read_from(&mut file, ParseOptions::new())
+        .map_err(|e| format!("Cannot read file: {e}"))?;
+    let Some(id3v2) = mpeg.id3v2_mut() else {
+        return Ok(());
+    };
+    if id3v2.remove(&FrameId::Valid(Cow::Borrowed("SYLT"))).count() == 0 {
+        return Ok(());
+    }
+    id3v2
+        .save_to_path(path, WriteOptions::default())
+        .map_err(|e| format!("Cannot write tags: {e}"))
+}
+
+/// Collect the lyrics of a track from its tag, an ID3v2 SYLT frame and an `.lrc` sidecar.
+///
+/// An `.lrc` file takes precedence over synced lyrics embedded in the file.
+pub(crate) async fn read_lyrics(path: &Path, tag: Option<&Tag>) -> Vec<SongLyrics> {
+    let mut found = Vec::new();
+    let embedded = tag.and_then(|t| {
+        t.get_string(ItemKey::UnsyncLyrics)
+            .or_else(|| t.get_string(ItemKey::Lyrics))
+    });
+    if let Some(text) = embedded.filter(|text| !text.trim().is_empty()) {
+        // Vorbis comments and MP4 atoms often carry LRC text in the plain lyrics field.
+        let timed = parse_lrc(text);
+        let synced = !timed.is_empty();
+        found.push(SongLyrics {
+            synced,
+            source: "tag",
+            lang: UNKNOWN_LANGUAGE.to_string(),
+            lines: if synced { timed } else { plain_lines(text) },
+        });
+    }
+
+    let sidecar = match fs::read_to_string(lrc_sidecar_path(path)).await {
+        Ok(text) => Some(parse_lrc(&text)).filter(|lines| !lines.is_empty()),
+        Err(_) => None,
+    };
+    let synced = match sidecar {
+        Some(lines) => Some(SongLyrics {
+            synced: true,
+            source: "lrc",
+            lang: UNKNOWN_LANGUAGE.to_string(),
+            lines,
+        }),
+        None if is_mpeg(path) => read_sylt(path),
+        None => None,
+    };
+    if let Some(synced) = synced {
+        found.retain(|lyrics| !lyrics.synced);
+        found.push(synced);
+    }
+    found
+}
+
+// ── Storage ──
+
+/// Replace the stored lyrics of a song.
+pub(crate) async fn store_lyrics(
+    client: &deadpool_postgres::Object,
+    song_id: Uuid,
+    lyrics: &[SongLyrics],
+) -> Result<(), tokio_postgres::Error> {
+    client
+        .execute("DELETE FROM lyrics WHERE song_id = $1", &[&song_id])
+        .await?;
+    for entry in lyrics {
+        let lines = serde_json::to_value(&entry.lines).unwrap_or_default();
+        client
+            .execute(
+                "INSERT INTO lyrics (song_id, synced, source, lang, lines) \
+                 VALUES ($1, $2, $3, $4, $5)",
+                &[&song_id, &entry.synced, &entry.source, &entry.lang, &lines],
+            )
+            .await?;
+    }
+    Ok(())
+}
+
+/// Stored lyrics of a song, unsynced first.
+pub(crate) async fn song_lyrics(
+    client: &deadpool_postgres::Object,
+    song_id: Uuid,
+) -> Result<Vec<SongLyrics>, tokio_postgres::Error> {
+    let rows = client
+        .query(
+            "SELECT synced, source, lang, lines FROM lyrics WHERE song_id = $1 ORDER BY synced",
+            &[&song_id],
+        )
+        .await?;
+    Ok(rows
+        .iter()
+        .map(|row| SongLyrics {
+            synced: row.get("synced"),
+            source: if row.get::<_, String>("source") == "lrc" {
+                "lrc"
+            } else {
+                "tag"
+            },
+            lang: row.get("lang"),
+            lines: serde_json::from_value(row.get("lines")).unwrap_or_default(),
+        })
+        .collect())
+}
+
+/// Current lyrics of a song in the form the tag editor accepts them.
+#[get("/music/songs/<id>/lyrics")]
+pub(crate) async fn get_song_lyrics(
+    pool: &State<Pool>,
+    _user: AuthenticatedUser,
+    id: &str,
+) -> Result<Json<LyricsResponse>, ApiError> {
+    let song_id = Uuid::parse_str(id).map_err(|_| not_found("Invalid song ID"))?;
+    let client = get_client(pool).await?;
+    client
+        .query_opt("SELECT 1 FROM songs WHERE id = $1", &[&song_id])
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| not_found("Song not found"))?;
+    let stored = song_lyrics(&client, song_id).await.map_err(db_error)?;
+    Ok(Json(LyricsResponse {
+        lyrics: stored
+            .iter()
+            .find(|lyrics| !lyrics.synced)
+            .map(|lyrics| lyrics_text(&lyrics.lines)),
+        synced_lyrics: stored
+            .iter()
+            .find(|lyrics| lyrics.synced)
+            .map(|lyrics| to_lrc(&lyrics.lines)),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timed(start: u32, value: &str) -> LyricLine {
+        LyricLine {
+            start: Some(start),
+            value: value.to_string(),
+        }
+    }
+
+    #[test]
+    fn parses_lrc_timestamps_and_repeated_lines() {
+        let lines = parse_lrc(
+            "[ar:Someone]\n[ti:Song]\n[00:01.5]First\n[00:12.34][01:02.345]Chorus\n[00:05]Second\n",
+        );
+        assert_eq!(
+            lines,
+            vec![
+                timed(1500, "First"),
+                timed(5000, "Second"),
+                timed(12340, "Chorus"),
+                timed(62345, "Chorus"),
+            ]
+        );
+    }
+
+    #[test]
+    fn applies_lrc_offset() {
+        let lines = parse_lrc("[offset:+500]\n[00:01.00]Early\n[00:00.20]Clamped");
+        assert_eq!(lines, vec![timed(0, "Clamped"), timed(500, "Early")]);
+    }
+
+    #[test]
+    fn plain_text_is_not_lrc() {
+        assert!(parse_lrc("Just some words\n[Chorus]\nMore words").is_empty());
+        assert!(parse_lrc("[00:61.00]Bad seconds").is_empty());
+    }
+
+    #[test]
+    fn lrc_round_trips() {
+        let lines = vec![timed(1500, "First"), timed(62340, "Second")];
+        let text = to_lrc(&lines);
+        assert_eq!(text, "[00:01.50]First\n[01:02.34]Second");
+        assert_eq!(parse_lrc(&text), lines);
+    }
+
+    #[test]
+    fn removes_sylt_from_mp3() {
+        use lofty::TextEncoding;
+        use lofty::id3::v2::{BinaryFrame, Id3v2Tag};
+
+        let path = std::env::temp_dir().join(format!("bf-sylt-{}.mp3", Uuid::new_v4()));
+        // Silent MPEG-1 Layer III frames: 128 kbps, 44.1 kHz, 417 bytes each.
+        let mut frame = vec![0u8; 417];
+        frame[..4].copy_from_slice(&[0xFF, 0xFB, 0x90, 0x00]);
+        std::fs::write(&path, frame.repeat(8)).expect("test file should be written");
+
+        let sylt = SynchronizedTextFrame::new(
+            TextEncoding::UTF8,
+            *b"eng",
+            TimestampFormat::MS,
+            SyncTextContentType::Lyrics,
+            None,
+            vec![(1500, "First".to_string())],
+        )
+        .as_bytes(WriteOptions::default())
+        .expect("SYLT frame should encode");
+        let mut tag = Id3v2Tag::new();
+        tag.set_title("Song".to_string());
+        tag.insert(Frame::Binary(BinaryFrame::new(
+            FrameId::Valid(Cow::Borrowed("SYLT")),
+            sylt,
+        )));
+        tag.save_to_path(&path, WriteOptions::default())
+            .expect("tag should be written");
+        assert_eq!(
+            read_sylt(&path).map(|lyrics| lyrics.lines),
+            Some(vec![timed(1500, "First")])
+        );
+
+        let removed = remove_sylt(&path);
+        let remaining = read_sylt(&path);
+        let mut file = std::fs::File::open(&path).expect("test file should open");
+        let title = MpegFile::read_from(&mut file, ParseOptions::new())
+            .ok()
+            .and_then(|mpeg| {
+                mpeg.id3v2()
+                    .and_then(|tag| tag.title().map(|t| t.to_string()))
+            });
+        std::fs::remove_file(&path).ok();
+        assert_eq!(removed, Ok(()));
+        assert_eq!(remaining, None);
+        assert_eq!(title.as_deref(), Some("Song"));
+    }
+
+    #[test]
+    fn plain_lyrics_keep_verse_breaks() {
+        let lines = plain_lines("\nOne\r\n\nTwo  \n");
+        let values: Vec<&str> = lines.iter().map(|line| line.value.as_str()).collect();
+        assert_eq!(values, vec!["One", "", "Two"]);
+        assert!(lines.iter().all(|line| line.start.is_none()));
+    }
+}
@@ -1,4 +1,5 @@
 // Re-export shared infrastructure for sub-modules.
+pub(crate) use crate::audit::{AuditEvent, record_audit};
 pub(crate) use crate::auth::{AuthenticatedUser, require_permission};
 pub(crate) use crate::shared::*;
 pub(crate) use deadpool_postgres::Pool;
@@ -11,11 +12,19 @@ pub(crate) use tokio::fs;
 
 // Sub-modules
 pub(crate) mod artwork;
+pub(crate) mod credits;
 pub(crate) mod crud;
 pub(crate) mod library;
+pub(crate) mod lyrics;
+pub(crate) mod radio;
+pub(crate) mod replay_gain;
 pub(crate) mod tags;
+pub(crate) mod transcoding;
 pub(crate) mod upload;
 
 // Re-exports for parent (main.rs)
 pub(crate) use artwork::*;
-pub(crate) use {crud::*, library::*, tags::*, upload::*};
+pub(crate) use {
+    credits::*, crud::*, library::*, lyrics::*, radio::*, replay_gain::*, tags::*, transcoding::*,
+    upload::*,
+};
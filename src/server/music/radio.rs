@@ -0,0 +1,268 @@
+use super::*;
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+const MAX_URL_LENGTH: usize = 2048;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub(crate) struct RadioStation {
+    pub id: Uuid,
+    pub name: String,
+    pub stream_url: String,
+    pub homepage_url: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub(crate) struct RadioStationRequest {
+    name: String,
+    stream_url: String,
+    homepage_url: Option<String>,
+}
+
+/// A station's validated, trimmed fields.
+pub(crate) struct RadioStationInput {
+    name: String,
+    stream_url: String,
+    homepage_url: Option<String>,
+}
+
+fn valid_url(url: &str) -> bool {
+    (url.starts_with("http://") || url.starts_with("https://"))
+        && url.len() <= MAX_URL_LENGTH
+        && !url.chars().any(char::is_whitespace)
+}
+
+pub(crate) fn validate_radio_station(
+    name: &str,
+    stream_url: &str,
+    homepage_url: Option<&str>,
+) -> Result<RadioStationInput, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Name is required".to_string());
+    }
+    let stream_url = stream_url.trim();
+    if !valid_url(stream_url) {
+        return Err("Stream URL must be an http or https URL".to_string());
+    }
+    let homepage_url = homepage_url.map(str::trim).filter(|url| !url.is_empty());
+    if homepage_url.is_some_and(|url| !valid_url(url)) {
+        return Err("Homepage URL must be an http or https URL".to_string());
+    }
+    Ok(RadioStationInput {
+        name: name.to_string(),
+        stream_url: stream_url.to_string(),
+        homepage_url: homepage_url.map(str::to_string),
+    })
+}
+
+const STATION_COLUMNS: &str = "id, name, stream_url, homepage_url, created_at, updated_at";
+
+fn row_to_station(row: &tokio_postgres::Row) -> RadioStation {
+    RadioStation {
+        id: row.get("id"),
+        name: row.get("name"),
+        stream_url: row.get("stream_url"),
+        homepage_url: row.get("homepage_url"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    }
+}
+
+pub(crate) async fn radio_stations(
+    client: &deadpool_postgres::Object,
+) -> Result<Vec<RadioStation>, tokio_postgres::Error> {
+    let rows = client
+        .query(
+            &format!("SELECT {STATION_COLUMNS} FROM internet_radio_stations ORDER BY name"),
+            &[],
+        )
+        .await?;
+    Ok(rows.iter().map(row_to_station).collect())
+}
+
+pub(crate) async fn insert_radio_station(
+    client: &deadpool_postgres::Object,
+    input: &RadioStationInput,
+) -> Result<RadioStation, tokio_postgres::Error> {
+    let row = client
+        .query_one(
+            &format!(
+                "INSERT INTO internet_radio_stations (name, stream_url, homepage_url)
+                 VALUES ($1, $2, $3)
+                 RETURNING {STATION_COLUMNS}"
+            ),
+            &[&input.name, &input.stream_url, &input.homepage_url],
+        )
+        .await?;
+    Ok(row_to_station(&row))
+}
+
+/// The station before and after the update, or `None` if it does not exist.
+pub(crate) async fn replace_radio_station(
+    client: &deadpool_postgres::Object,
+    id: Uuid,
+    input: &RadioStationInput,
+) -> Result<Option<(RadioStation, RadioStation)>, tokio_postgres::Error> {
+    let Some(before) = client
+        .query_opt(
+            &format!("SELECT {STATION_COLUMNS} FROM internet_radio_stations WHERE id = $1"),
+            &[&id],
+        )
+        .await?
+    else {
+        return Ok(None);
+    };
+    let after = client
+        .query_opt(
+            &format!(
+                "UPDATE internet_radio_stations
+                 SET name = $1, stream_url = $2, homepage_url = $3, updated_at = NOW()
+                 WHERE id = $4
+                 RETURNING {STATION_COLUMNS}"
+            ),
+            &[&input.name, &input.stream_url, &input.homepage_url, &id],
+        )
+        .await?;
+    Ok(after.map(|after| (row_to_station(&before), row_to_station(&after))))
+}
+
+pub(crate) async fn remove_radio_station(
+    client: &deadpool_postgres::Object,
+    id: Uuid,
+) -> Result<Option<RadioStation>, tokio_postgres::Error> {
+    let row = client
+        .query_opt(
+            &format!(
+                "DELETE FROM internet_radio_stations WHERE id = $1 RETURNING {STATION_COLUMNS}"
+            ),
+            &[&id],
+        )
+        .await?;
+    Ok(row.as_ref().map(row_to_station))
+}
+
+pub(crate) fn radio_created_event(station: &RadioStation) -> AuditEvent {
+    AuditEvent::new("radio_station.create", "radio_station", station.id)
+        .after(serde_json::json!(station))
+}
+
+pub(crate) fn radio_updated_event(before: &RadioStation, after: &RadioStation) -> AuditEvent {
+    AuditEvent::new("radio_station.update", "radio_station", after.id)
+        .before(serde_json::json!(before))
+        .after(serde_json::json!(after))
+}
+
+pub(crate) fn radio_deleted_event(station: &RadioStation) -> AuditEvent {
+    AuditEvent::new("radio_station.delete", "radio_station", station.id)
+        .before(serde_json::json!(station))
+}
+
+fn station_id(id: &str) -> Result<Uuid, ApiError> {
+    Uuid::parse_str(id).map_err(|_| not_found("Radio station not found"))
+}
+
+fn station_input(request: &RadioStationRequest) -> Result<RadioStationInput, ApiError> {
+    validate_radio_station(
+        &request.name,
+        &request.stream_url,
+        request.homepage_url.as_deref(),
+    )
+    .map_err(|message| bad_request(&message))
+}
+
+/// GET /api/music/radio - Every internet radio station
+#[get("/music/radio")]
+pub(crate) async fn list_radio_stations(
+    pool: &State<Pool>,
+    _user: AuthenticatedUser,
+) -> Result<Json<Vec<RadioStation>>, ApiError> {
+    let client = get_client(pool).await?;
+    Ok(Json(radio_stations(&client).await.map_err(db_error)?))
+}
+
+/// POST /api/music/radio - Add a station
+#[post("/music/radio", data = "<request>")]
+pub(crate) async fn create_radio_station(
+    pool: &State<Pool>,
+    user: AuthenticatedUser,
+    request: Json<RadioStationRequest>,
+) -> Result<Json<RadioStation>, ApiError> {
+    require_permission(pool, &user, "manage_radio").await?;
+    let input = station_input(&request)?;
+
+    let client = get_client(pool).await?;
+    let station = insert_radio_station(&client, &input)
+        .await
+        .map_err(db_error)?;
+    record_audit(pool, &user, radio_created_event(&station)).await;
+    Ok(Json(station))
+}
+
+/// PUT /api/music/radio/<id> - Replace a station's name and URLs
+#[put("/music/radio/<id>", data = "<request>")]
+pub(crate) async fn update_radio_station(
+    pool: &State<Pool>,
+    user: AuthenticatedUser,
+    id: &str,
+    request: Json<RadioStationRequest>,
+) -> Result<Json<RadioStation>, ApiError> {
+    require_permission(pool, &user, "manage_radio").await?;
+    let id = station_id(id)?;
+    let input = station_input(&request)?;
+
+    let client = get_client(pool).await?;
+    let (before, after) = replace_radio_station(&client, id, &input)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| not_found("Radio station not found"))?;
+    record_audit(pool, &user, radio_updated_event(&before, &after)).await;
+    Ok(Json(after))
+}
+
+/// DELETE /api/music/radio/<id> - Remove a station
+#[delete("/music/radio/<id>")]
+pub(crate) async fn delete_radio_station(
+    pool: &State<Pool>,
+    user: AuthenticatedUser,
+    id: &str,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    require_permission(pool, &user, "manage_radio").await?;
+    let id = station_id(id)?;
+
+    let client = get_client(pool).await?;
+    let station = remove_radio_station(&client, id)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| not_found("Radio station not found"))?;
+    record_audit(pool, &user, radio_deleted_event(&station)).await;
+    Ok(Json(
+        serde_json::json!({"message": "Radio station deleted"}),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stations_need_a_name_and_web_urls() {
+        let input = validate_radio_station("  Jazz FM ", " https://stream.example/jazz ", Some(""))
+            .expect("station is valid");
+        assert_eq!(input.name, "Jazz FM");
+        assert_eq!(input.stream_url, "https://stream.example/jazz");
+        assert_eq!(input.homepage_url, None);
+
+        assert!(validate_radio_station(" ", "https://stream.example", None).is_err());
+        assert!(validate_radio_station("Jazz", "file:///etc/passwd", None).is_err());
+        assert!(validate_radio_station("Jazz", "https://a b", None).is_err());
+        assert!(
+            validate_radio_station("Jazz", "http://stream.example", Some("ftp://home")).is_err()
+        );
+    }
+}
@@ -0,0 +1,379 @@
+use super::*;
+
+use ebur128::{EbuR128, Mode};
+use lofty::config::WriteOptions;
+use lofty::probe::Probe;
+use std::path::{Path, PathBuf};
+use symphonia::core::codecs::audio::AudioDecoderOptions;
+use symphonia::core::errors::Error as DecodeError;
+use symphonia::core::formats::probe::Hint;
+use symphonia::core::formats::{FormatOptions, TrackType};
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use uuid::Uuid;
+
+// ── ReplayGain analysis ──
+
+/// ReplayGain 2.0 reference loudness in LUFS.
+const REFERENCE_LUFS: f64 = -18.0;
+/// Pause between checks for new songs once everything is analysed.
+const REPLAY_GAIN_IDLE_SECS: u64 = 300;
+
+/// A gain in dB and the linear sample peak it applies to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct GainPeak {
+    pub gain: f64,
+    pub peak: f64,
+}
+
+/// Whether analysed values are also written into the files' tags (`REPLAY_GAIN_WRITE_TAGS`).
+fn write_tags_enabled() -> bool {
+    std::env::var("REPLAY_GAIN_WRITE_TAGS")
+        .is_ok_and(|value| matches!(value.trim(), "1" | "true" | "yes"))
+}
+
+/// Gain that brings `loudness` to the reference level; silence has none.
+pub(crate) fn gain_for_loudness(loudness: f64) -> Option<f64> {
+    loudness.is_finite().then_some(REFERENCE_LUFS - loudness)
+}
+
+fn peak_of(meter: &EbuR128) -> f64 {
+    (0..meter.channels())
+        .filter_map(|channel| meter.sample_peak(channel).ok())
+        .fold(0.0, f64::max)
+}
+
+fn track_gain(meter: &EbuR128) -> Option<GainPeak> {
+    let gain = gain_for_loudness(meter.loudness_global().ok()?)?;
+    Some(GainPeak {
+        gain,
+        peak: peak_of(meter),
+    })
+}
+
+/// Album gain over the gated loudness of all tracks together, not an average of track gains.
+fn album_gain(meters: &[&EbuR128]) -> Option<GainPeak> {
+    if meters.is_empty() {
+        return None;
+    }
+    let loudness = EbuR128::loudness_global_multiple(meters.iter().copied()).ok()?;
+    Some(GainPeak {
+        gain: gain_for_loudness(loudness)?,
+        peak: meters
+            .iter()
+            .map(|meter| peak_of(meter))
+            .fold(0.0, f64::max),
+    })
+}
+
+/// Decode a whole file and measure its loudness and sample peak.
+fn analyze_file(path: &Path) -> Result<EbuR128, String> {
+    let file = std::fs::File::open(path).map_err(|e| format!("Cannot open file: {e}"))?;
+    let stream = MediaSourceStream::new(Box::new(file), Default::default());
+    let mut hint = Hint::new();
+    if let Some(extension) = path.extension().and_then(|e| e.to_str()) {
+        hint.with_extension(extension);
+    }
+    let mut format = symphonia::default::get_probe()
+        .probe(
+            &hint,
+            stream,
+            FormatOptions::default(),
+            MetadataOptions::default(),
+        )
+        .map_err(|e| format!("Cannot determine format: {e}"))?;
+    let track = format
+        .default_track(TrackType::Audio)
+        .ok_or("No audio track")?;
+    let track_id = track.id;
+    let params = track
+        .codec_params
+        .as_ref()
+        .and_then(|params| params.audio())
+        .ok_or("No audio codec parameters")?;
+    let mut decoder = symphonia::default::get_codecs()
+        .make_audio_decoder(params, &AudioDecoderOptions::default())
+        .map_err(|e| format!("Unsupported codec: {e}"))?;
+
+    let mut meter: Option<EbuR128> = None;
+    let mut samples: Vec<f32> = Vec::new();
+    loop {
+        let packet = match format.next_packet() {
+            Ok(Some(packet)) => packet,
+            Ok(None) => break,
+            Err(DecodeError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(format!("Cannot read audio: {e}")),
+        };
+        if packet.track_id != track_id {
+            continue;
+        }
+        let buffer = match decoder.decode(&packet) {
+            Ok(buffer) => buffer,
+            // A corrupt frame is skipped, as players do.
+            Err(DecodeError::DecodeError(_)) => continue,
+            Err(e) => return Err(format!("Cannot decode audio: {e}")),
+        };
+        let channels = buffer.spec().channels().count() as u32;
+        let rate = buffer.spec().rate();
+        if meter.is_none() {
+            let mode = Mode::I | Mode::SAMPLE_PEAK | Mode::HISTOGRAM;
+            meter = Some(
+                EbuR128::new(channels, rate, mode)
+                    .map_err(|e| format!("Cannot measure loudness: {e}"))?,
+            );
+        }
+        let Some(meter) = meter.as_mut() else {
+            continue;
+        };
+        if meter.channels() != channels || meter.rate() != rate {
+            meter
+                .change_parameters(channels, rate)
+                .map_err(|e| format!("Cannot measure loudness: {e}"))?;
+        }
+        samples.resize(buffer.samples_interleaved(), 0.0);
+        buffer.copy_to_slice_interleaved(&mut samples);
+        meter
+            .add_frames_f32(&samples)
+            .map_err(|e| format!("Cannot measure loudness: {e}"))?;
+    }
+    meter.ok_or_else(|| "No audio decoded".to_string())
+}
+
+fn format_gain(gain: f64) -> String {
+    format!("{gain:.2} dB")
+}
+
+fn format_peak(peak: f64) -> String {
+    format!("{peak:.6}")
+}
+
+fn write_replay_gain_tags(
+    path: &Path,
+    track: GainPeak,
+    album: Option<GainPeak>,
+) -> Result<(), String> {
+    let mut tagged_file = Probe::open(path)
+        .map_err(|e| format!("Cannot open file: {e}"))?
+        .guess_file_type()
+        .map_err(|e| format!("Cannot determine file type: {e}"))?
+        .read()
+        .map_err(|e| format!("Cannot read file: {e}"))?;
+    let tag = tagged_file
+        .primary_tag_mut()
+        .ok_or("No writable tag found")?;
+    tag.insert_text(ItemKey::ReplayGainTrackGain, format_gain(track.gain));
+    tag.insert_text(ItemKey::ReplayGainTrackPeak, format_peak(track.peak));
+    match album {
+        Some(album) => {
+            tag.insert_text(ItemKey::ReplayGainAlbumGain, format_gain(album.gain));
+            tag.insert_text(ItemKey::ReplayGainAlbumPeak, format_peak(album.peak));
+        }
+        None => {
+            tag.remove_key(ItemKey::ReplayGainAlbumGain);
+            tag.remove_key(ItemKey::ReplayGainAlbumPeak);
+        }
+    }
+    tag.save_to_path(path, WriteOptions::default())
+        .map_err(|e| format!("Cannot write tags: {e}"))
+}
+
+/// Rewrite a file's ReplayGain tags under its tag write lock, returning its new size.
+fn write_replay_gain_tags_locked(
+    path: &Path,
+    track: GainPeak,
+    album: Option<GainPeak>,
+) -> Result<i64, String> {
+    let _written = tag_write_lock(path).blocking_lock_owned();
+    write_replay_gain_tags(path, track, album)?;
+    std::fs::metadata(path)
+        .map(|metadata| metadata.len() as i64)
+        .map_err(|e| format!("Cannot stat file: {e}"))
+}
+
+/// A song's measured track gain, and its file size when writing tags changed it.
+type TrackResult = (Uuid, Option<GainPeak>, Option<i64>);
+
+/// Analyse every song of one album, returning per-song track gains and the album gain.
+fn analyze_album(
+    songs: Vec<(Uuid, String, PathBuf)>,
+    write_tags: bool,
+) -> (Vec<TrackResult>, Option<GainPeak>) {
+    let measured: Vec<(Uuid, String, PathBuf, Option<EbuR128>)> = songs
+        .into_iter()
+        .map(|(id, file_path, path)| {
+            let meter = analyze_file(&path)
+                .inspect_err(|e| eprintln!("ReplayGain: cannot analyse {file_path}: {e}"))
+                .ok();
+            (id, file_path, path, meter)
+        })
+        .collect();
+    let meters: Vec<&EbuR128> = measured
+        .iter()
+        .filter_map(|(_, _, _, meter)| meter.as_ref())
+        .collect();
+    let album = album_gain(&meters);
+
+    let tracks = measured
+        .iter()
+        .map(|(id, file_path, path, meter)| {
+            let track = meter.as_ref().and_then(track_gain);
+            let size_bytes = match track {
+                Some(track) if write_tags => write_replay_gain_tags_locked(path, track, album)
+                    .inspect_err(|e| eprintln!("ReplayGain: cannot write tags to {file_path}: {e}"))
+                    .ok(),
+                _ => None,
+            };
+            (*id, track, size_bytes)
+        })
+        .collect();
+    (tracks, album)
+}
+
+/// Analyse the next album with unanalysed songs; `Ok(false)` when there is none.
+async fn analyze_next_album(pool: &Pool) -> Result<bool, String> {
+    let client = pool
+        .get()
+        .await
+        .map_err(|e| format!("Database connection error: {e}"))?;
+    let Some(pending) = client
+        .query_opt(
+            "SELECT artist, album FROM songs WHERE replay_gain_analyzed_at IS NULL LIMIT 1",
+            &[],
+        )
+        .await
+        .map_err(|e| format!("Database error: {e}"))?
+    else {
+        return Ok(false);
+    };
+    let artist: String = pending.get("artist");
+    let album: String = pending.get("album");
+    // The whole album is measured again so its album gain covers every track.
+    let rows = client
+        .query(
+            "SELECT id, file_path FROM songs WHERE artist = $1 AND album = $2",
+            &[&artist, &album],
+        )
+        .await
+        .map_err(|e| format!("Database error: {e}"))?;
+    drop(client);
+
+    let songs = rows
+        .iter()
+        .map(|row| {
+            let file_path: String = row.get("file_path");
+            let path = Path::new(MUSIC_ROOT).join(&file_path);
+            (row.get("id"), file_path, path)
+        })
+        .collect();
+    let write_tags = write_tags_enabled();
+    let (tracks, album_result) =
+        tokio::task::spawn_blocking(move || analyze_album(songs, write_tags))
+            .await
+            .map_err(|e| format!("Analysis task failed: {e}"))?;
+
+    let client = pool
+        .get()
+        .await
+        .map_err(|e| format!("Database connection error: {e}"))?;
+    let album_gain = album_result.map(|album| album.gain as f32);
+    let album_peak = album_result.map(|album| album.peak as f32);
+    for (id, track, size_bytes) in tracks {
+        client
+            .execute(
+                "UPDATE songs SET replay_gain_track_gain = $2, replay_gain_track_peak = $3, \
+                 replay_gain_album_gain = $4, replay_gain_album_peak = $5, \
+                 size_bytes = COALESCE($6, size_bytes), replay_gain_analyzed_at = NOW() \
+                 WHERE id = $1",
+                &[
+                    &id,
+                    &track.map(|track| track.gain as f32),
+                    &track.map(|track| track.peak as f32),
+                    &track.and(album_gain),
+                    &track.and(album_peak),
+                    &size_bytes,
+                ],
+            )
+            .await
+            .map_err(|e| format!("Database error: {e}"))?;
+    }
+    Ok(true)
+}
+
+/// Have the analyzer measure an album again, such as after a track joined or left it.
+pub(crate) async fn requeue_album_replay_gain(
+    client: &deadpool_postgres::Object,
+    artist: &str,
+    album: &str,
+) -> Result<u64, tokio_postgres::Error> {
+    client
+        .execute(
+            "UPDATE songs SET replay_gain_analyzed_at = NULL WHERE artist = $1 AND album = $2",
+            &[&artist, &album],
+        )
+        .await
+}
+
+/// Analyse new songs in the background, one album at a time.
+pub(crate) fn spawn_replay_gain_analyzer(pool: Pool) {
+    tokio::spawn(async move {
+        loop {
+            match analyze_next_album(&pool).await {
+                Ok(true) => continue,
+                Ok(false) => {}
+                Err(error) => eprintln!("ReplayGain: {error}"),
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(REPLAY_GAIN_IDLE_SECS)).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meter_with_tone(amplitude: f32, seconds: usize) -> EbuR128 {
+        let rate = 48_000;
+        let mut meter = EbuR128::new(2, rate, Mode::I | Mode::SAMPLE_PEAK | Mode::HISTOGRAM)
+            .expect("meter should accept stereo at 48 kHz");
+        let frames: Vec<f32> = (0..rate as usize * seconds)
+            .flat_map(|i| {
+                let sample = amplitude
+                    * (2.0 * std::f32::consts::PI * 1000.0 * i as f32 / rate as f32).sin();
+                [sample, sample]
+            })
+            .collect();
+        meter
+            .add_frames_f32(&frames)
+            .expect("meter should accept frames");
+        meter
+    }
+
+    #[test]
+    fn gain_targets_reference_loudness() {
+        assert_eq!(gain_for_loudness(-18.0), Some(0.0));
+        assert_eq!(gain_for_loudness(-9.5), Some(-8.5));
+        assert_eq!(gain_for_loudness(f64::NEG_INFINITY), None);
+    }
+
+    #[test]
+    fn quieter_tracks_get_more_gain() {
+        let loud = meter_with_tone(0.5, 3);
+        let quiet = meter_with_tone(0.25, 3);
+        let loud_gain = track_gain(&loud).expect("loud tone should have a gain");
+        let quiet_gain = track_gain(&quiet).expect("quiet tone should have a gain");
+        assert!((quiet_gain.gain - loud_gain.gain - 6.02).abs() < 0.1);
+        assert!((loud_gain.peak - 0.5).abs() < 0.01);
+
+        let album = album_gain(&[&loud, &quiet]).expect("album should have a gain");
+        assert!(album.gain > loud_gain.gain && album.gain < quiet_gain.gain);
+        assert_eq!(album.peak, loud_gain.peak);
+        assert!(album_gain(&[]).is_none());
+    }
+
+    #[test]
+    fn tag_values_use_replay_gain_notation() {
+        assert_eq!(format_gain(-6.5432), "-6.54 dB");
+        assert_eq!(format_gain(1.0), "1.00 dB");
+        assert_eq!(format_peak(0.98765432), "0.987654");
+    }
+}
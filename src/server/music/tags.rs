@@ -1,10 +1,19 @@
 use super::*;
 
+use lofty::config::ParseOptions;
+use lofty::file::FileType;
+use lofty::mp4::{Mp4Codec, Mp4File};
 use lofty::probe::Probe;
-use lofty::tag::ItemKey;
-use std::path::Path;
+use lofty::tag::{ItemKey, TagType};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock, Mutex, Weak};
 use uuid::Uuid;
 
+/// One lock per file whose tags are being rewritten; see [`tag_write_lock`].
+static TAG_WRITE_LOCKS: LazyLock<Mutex<HashMap<PathBuf, Weak<tokio::sync::Mutex<()>>>>> =
+    LazyLock::new(Default::default);
+
 // ── Response types ──
 
 #[derive(Debug, Serialize)]
@@ -24,6 +33,10 @@ pub struct SongResponse {
     pub size_bytes: i64,
     pub format: Option<String>,
     pub bitrate_kbps: Option<i16>,
+    pub sample_rate: Option<i32>,
+    pub bit_depth: Option<i16>,
+    pub channel_count: Option<i16>,
+    pub codec: Option<String>,
     pub has_cover_art: bool,
     pub in_library: bool,
     pub created_at: String,
@@ -63,10 +76,69 @@ pub struct UpdateTagsRequest {
     pub year: Option<i16>,
     pub track_number: Option<i16>,
     pub disc_number: Option<i16>,
+    /// Plain lyrics stored in the tag; an empty string removes them.
+    pub lyrics: Option<String>,
+    /// Synced lyrics as LRC text, stored in the `.lrc` file next to the track; an empty string
+    /// removes it.
+    pub synced_lyrics: Option<String>,
 }
 
 // ── Tag scanner ──
 
+/// Codecs stored in `songs.codec` that reproduce the original samples exactly.
+pub(crate) const LOSSLESS_CODECS: &[&str] = &["flac", "alac", "pcm", "ape", "wavpack"];
+
+/// Codec implied by the container; MP4 holds several, so it has to be read from the file.
+fn file_type_codec(file_type: &FileType) -> Option<&'static str> {
+    match file_type {
+        FileType::Aac => Some("aac"),
+        FileType::Aiff | FileType::Wav => Some("pcm"),
+        FileType::Ape => Some("ape"),
+        FileType::Flac => Some("flac"),
+        FileType::Mpeg => Some("mp3"),
+        FileType::Mpc => Some("musepack"),
+        FileType::Opus => Some("opus"),
+        FileType::Vorbis => Some("vorbis"),
+        FileType::Speex => Some("speex"),
+        FileType::WavPack => Some("wavpack"),
+        _ => None,
+    }
+}
+
+fn mp4_codec(path: &Path) -> Option<&'static str> {
+    let mut file = std::fs::File::open(path).ok()?;
+    let mp4 = Mp4File::read_from(&mut file, ParseOptions::new()).ok()?;
+    match mp4.properties().codec() {
+        Mp4Codec::AAC => Some("aac"),
+        Mp4Codec::ALAC => Some("alac"),
+        Mp4Codec::MP3 => Some("mp3"),
+        Mp4Codec::FLAC => Some("flac"),
+        _ => None,
+    }
+}
+
+fn audio_codec(file_type: &FileType, path: &Path) -> Option<&'static str> {
+    match file_type {
+        FileType::Mp4 => mp4_codec(path),
+        other => file_type_codec(other),
+    }
+}
+
+/// The lock held while rewriting the tags of the file at `path`, so the tag editor, cover
+/// uploads and the ReplayGain analyzer don't overwrite each other's changes.
+pub(crate) fn tag_write_lock(path: &Path) -> Arc<tokio::sync::Mutex<()>> {
+    let mut locks = TAG_WRITE_LOCKS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    locks.retain(|_, lock| lock.strong_count() > 0);
+    if let Some(lock) = locks.get(path).and_then(Weak::upgrade) {
+        return lock;
+    }
+    let lock = Arc::new(tokio::sync::Mutex::new(()));
+    locks.insert(path.to_path_buf(), Arc::downgrade(&lock));
+    lock
+}
+
 pub(crate) async fn scan_and_insert_song(pool: &Pool, relative_path: &str) -> Result<Uuid, String> {
     let full_path = Path::new(MUSIC_ROOT).join(relative_path);
 
@@ -112,6 +184,10 @@ pub(crate) async fn scan_and_insert_song(pool: &Pool, relative_path: &str) -> Re
 
     let duration_seconds = properties.duration().as_secs_f32();
     let bitrate_kbps = properties.audio_bitrate().map(|b| (b / 1000) as i16);
+    let sample_rate = properties.sample_rate().map(|rate| rate as i32);
+    let bit_depth = properties.bit_depth().map(i16::from);
+    let channel_count = properties.channels().map(i16::from);
+    let codec = audio_codec(&tagged_file.file_type(), &full_path);
 
     let metadata = fs::metadata(&full_path)
         .await
@@ -123,28 +199,42 @@ pub(crate) async fn scan_and_insert_song(pool: &Pool, relative_path: &str) -> Re
         .and_then(|e| e.to_str())
         .map(|e| e.to_lowercase());
     let has_cover_art = has_embedded_artwork(tag);
+    let lyrics = read_lyrics(&full_path, tag).await;
+    let credits = read_credits(tag, &artist);
 
     let client = pool
         .get()
         .await
         .map_err(|e| format!("Database connection error: {e}"))?;
-    let song_id = Uuid::new_v4();
-
-    client
-        .execute(
-            "INSERT INTO songs (id, file_path, title, artist, album, album_artist, genre, year,
+    // A changed file or album needs new ReplayGain values, and the album it left a new
+    // album gain.
+    let row = client
+        .query_one(
+            "WITH previous AS (SELECT artist, album FROM songs WHERE file_path = $2)
+         INSERT INTO songs (id, file_path, title, artist, album, album_artist, genre, year,
                             track_number, disc_number, duration_seconds, size_bytes, format,
-                            bitrate_kbps, has_cover_art)
-         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
+                            bitrate_kbps, has_cover_art, sample_rate, bit_depth, channel_count,
+                            codec)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17,
+                 $18, $19)
          ON CONFLICT (file_path) DO UPDATE SET
             title = EXCLUDED.title, artist = EXCLUDED.artist, album = EXCLUDED.album,
             album_artist = EXCLUDED.album_artist, genre = EXCLUDED.genre, year = EXCLUDED.year,
             track_number = EXCLUDED.track_number, disc_number = EXCLUDED.disc_number,
             duration_seconds = EXCLUDED.duration_seconds, size_bytes = EXCLUDED.size_bytes,
             format = EXCLUDED.format, bitrate_kbps = EXCLUDED.bitrate_kbps,
-            has_cover_art = EXCLUDED.has_cover_art, updated_at = NOW()",
+            has_cover_art = EXCLUDED.has_cover_art, sample_rate = EXCLUDED.sample_rate,
+            bit_depth = EXCLUDED.bit_depth, channel_count = EXCLUDED.channel_count,
+            codec = EXCLUDED.codec, updated_at = NOW(),
+            replay_gain_analyzed_at = CASE
+                WHEN (songs.artist, songs.album, songs.size_bytes)
+                     = (EXCLUDED.artist, EXCLUDED.album, EXCLUDED.size_bytes)
+                THEN songs.replay_gain_analyzed_at
+            END
+         RETURNING id, (SELECT artist FROM previous) AS previous_artist,
+                   (SELECT album FROM previous) AS previous_album",
             &[
-                &song_id,
+                &Uuid::new_v4(),
                 &relative_path,
                 &title,
                 &artist,
@@ -159,14 +249,105 @@ pub(crate) async fn scan_and_insert_song(pool: &Pool, relative_path: &str) -> Re
                 &format,
                 &bitrate_kbps,
                 &has_cover_art,
+                &sample_rate,
+                &bit_depth,
+                &channel_count,
+                &codec,
             ],
         )
         .await
         .map_err(|e| format!("Database error: {e}"))?;
+    let song_id: Uuid = row.get("id");
+    let previous_artist: Option<String> = row.get("previous_artist");
+    let previous_album: Option<String> = row.get("previous_album");
+    if let (Some(previous_artist), Some(previous_album)) = (previous_artist, previous_album)
+        && (previous_artist != artist || previous_album != album)
+    {
+        requeue_album_replay_gain(&client, &previous_artist, &previous_album)
+            .await
+            .map_err(|e| format!("Database error: {e}"))?;
+    }
+    store_lyrics(&client, song_id, &lyrics)
+        .await
+        .map_err(|e| format!("Database error: {e}"))?;
+    store_credits(&client, song_id, &credits)
+        .await
+        .map_err(|e| format!("Database error: {e}"))?;
 
     Ok(song_id)
 }
 
+/// Re-read the lyrics and artist and genre credits of a track after its tags changed.
+async fn refresh_song_details(
+    client: &deadpool_postgres::Object,
+    song_id: Uuid,
+    path: &Path,
+    artist: &str,
+) -> Result<(), String> {
+    let tagged_file = Probe::open(path)
+        .map_err(|e| format!("Cannot open file: {e}"))?
+        .guess_file_type()
+        .map_err(|e| format!("Cannot determine file type: {e}"))?
+        .read()
+        .map_err(|e| format!("Cannot read tags: {e}"))?;
+    let tag = tagged_file.primary_tag();
+    let lyrics = read_lyrics(path, tag).await;
+    let credits = read_credits(tag, artist);
+    store_lyrics(client, song_id, &lyrics)
+        .await
+        .map_err(|e| format!("Database error: {e}"))?;
+    store_credits(client, song_id, &credits)
+        .await
+        .map_err(|e| format!("Database error: {e}"))
+}
+
+/// Re-read the lyrics of the tracks that the `.lrc` file at `lrc_path` (relative to the music
+/// root) sits next to, returning their ids.
+pub(crate) async fn refresh_sidecar_lyrics(
+    pool: &Pool,
+    lrc_path: &str,
+) -> Result<Vec<Uuid>, String> {
+    let lrc = Path::new(lrc_path);
+    let prefix = format!("{}.", lrc.with_extension("").to_string_lossy());
+    let client = pool
+        .get()
+        .await
+        .map_err(|e| format!("Database connection error: {e}"))?;
+    let rows = client
+        .query(
+            "SELECT id, file_path, artist FROM songs WHERE left(file_path, length($1)) = $1",
+            &[&prefix],
+        )
+        .await
+        .map_err(|e| format!("Database error: {e}"))?;
+    let mut refreshed = Vec::new();
+    for row in &rows {
+        let file_path: String = row.get("file_path");
+        if lrc_sidecar_path(Path::new(&file_path)) != lrc {
+            continue;
+        }
+        let song_id: Uuid = row.get("id");
+        let artist: String = row.get("artist");
+        let full_path = Path::new(MUSIC_ROOT).join(&file_path);
+        refresh_song_details(&client, song_id, &full_path, &artist).await?;
+        refreshed.push(song_id);
+    }
+    Ok(refreshed)
+}
+
+pub(super) fn tag_snapshot(row: &tokio_postgres::Row) -> serde_json::Value {
+    serde_json::json!({
+        "title": row.get::<_, String>("title"),
+        "artist": row.get::<_, String>("artist"),
+        "album": row.get::<_, String>("album"),
+        "album_artist": row.get::<_, Option<String>>("album_artist"),
+        "genre": row.get::<_, Option<String>>("genre"),
+        "year": row.get::<_, Option<i16>>("year"),
+        "track_number": row.get::<_, Option<i16>>("track_number"),
+        "disc_number": row.get::<_, Option<i16>>("disc_number"),
+    })
+}
+
 #[put("/music/songs/<id>/tags", data = "<req>")]
 pub(crate) async fn update_song_tags(
     pool: &State<Pool>,
@@ -174,7 +355,7 @@ pub(crate) async fn update_song_tags(
     id: &str,
     req: Json<UpdateTagsRequest>,
 ) -> Result<Json<SongResponse>, (Status, Json<serde_json::Value>)> {
-    require_permission(pool, user.id, "music_edit_tags").await?;
+    require_permission(pool, &user, "music_edit_tags").await?;
     let song_id = Uuid::parse_str(id).map_err(|_| not_found("Invalid song ID"))?;
     let client = get_client(pool).await?;
     let row = client.query_opt(
@@ -193,29 +374,54 @@ pub(crate) async fn update_song_tags(
     let disc_number = req.disc_number.or_else(|| row.get("disc_number"));
 
     let full_path = Path::new(MUSIC_ROOT).join(&file_path);
+    let lock = tag_write_lock(&full_path);
+    let written = lock.lock().await;
     if let Err(error) = write_file_tags(&full_path, &req).await {
         eprintln!("Failed to write tags to {file_path}: {error}");
         return Err(server_error());
     }
+    let size_bytes = fs::metadata(&full_path)
+        .await
+        .map(|metadata| metadata.len() as i64)
+        .map_err(|_| server_error())?;
 
     client.execute(
-        "UPDATE songs SET title=$1,artist=$2,album=$3,album_artist=$4,genre=$5,year=$6,track_number=$7,disc_number=$8,updated_at=NOW() WHERE id=$9",
-        &[&title,&artist,&album,&album_artist,&genre,&year,&track_number,&disc_number,&song_id],
+        "UPDATE songs SET title=$1,artist=$2,album=$3,album_artist=$4,genre=$5,year=$6,track_number=$7,disc_number=$8,size_bytes=$10,updated_at=NOW() WHERE id=$9",
+        &[&title,&artist,&album,&album_artist,&genre,&year,&track_number,&disc_number,&song_id,&size_bytes],
     ).await.map_err(db_error)?;
+    drop(written);
+    let previous_artist: &str = row.get("artist");
+    let previous_album: &str = row.get("album");
+    if previous_artist != artist || previous_album != album {
+        // Both albums' album gains change with their track lists.
+        requeue_album_replay_gain(&client, previous_artist, previous_album)
+            .await
+            .map_err(db_error)?;
+        requeue_album_replay_gain(&client, artist, album)
+            .await
+            .map_err(db_error)?;
+    }
+    if let Err(error) = refresh_song_details(&client, song_id, &full_path, artist).await {
+        eprintln!("Failed to refresh lyrics and credits of {file_path}: {error}");
+    }
 
     let updated = client
         .query_one("SELECT * FROM songs WHERE id = $1", &[&song_id])
         .await
         .map_err(db_error)?;
+    record_audit(
+        pool,
+        &user,
+        AuditEvent::new("song.tags.update", "song", song_id)
+            .before(tag_snapshot(&row))
+            .after(tag_snapshot(&updated)),
+    )
+    .await;
     Ok(Json(row_to_song(&updated)))
 }
 
-#[post("/music/scan")]
-pub(crate) async fn scan_songs(
-    pool: &State<Pool>,
-    user: AuthenticatedUser,
-) -> Result<Json<serde_json::Value>, (Status, Json<serde_json::Value>)> {
-    require_permission(pool, user.id, "music_upload").await?;
+/// Re-read the tags of every known song, returning how many were scanned and how many failed.
+pub(crate) async fn rescan_songs(pool: &Pool) -> Result<(i64, i64), ApiError> {
     let client = get_client(pool).await?;
     let rows = client
         .query("SELECT file_path FROM songs ORDER BY file_path", &[])
@@ -233,6 +439,23 @@ pub(crate) async fn scan_songs(
             }
         }
     }
+    Ok((scanned, failed))
+}
+
+#[post("/music/scan")]
+pub(crate) async fn scan_songs(
+    pool: &State<Pool>,
+    user: AuthenticatedUser,
+) -> Result<Json<serde_json::Value>, (Status, Json<serde_json::Value>)> {
+    require_permission(pool, &user, "music_upload").await?;
+    let (scanned, failed) = rescan_songs(pool).await?;
+    record_audit(
+        pool,
+        &user,
+        AuditEvent::new("music.scan", "library", "global")
+            .after(serde_json::json!({"scanned": scanned, "failed": failed})),
+    )
+    .await;
     Ok(Json(
         serde_json::json!({"message": "Scan complete", "scanned": scanned, "failed": failed}),
     ))
@@ -277,9 +500,77 @@ async fn write_file_tags(path: &Path, req: &UpdateTagsRequest) -> Result<(), Str
     if let Some(v) = req.disc_number {
         tag.set_disk(v as u32);
     }
+    if req.lyrics.is_none()
+        && req
+            .synced_lyrics
+            .as_deref()
+            .is_some_and(|v| v.trim().is_empty())
+    {
+        // Clearing synced lyrics also clears LRC text kept in the plain lyrics field.
+        for key in [ItemKey::UnsyncLyrics, ItemKey::Lyrics] {
+            if tag
+                .get_string(key)
+                .is_some_and(|text| !parse_lrc(text).is_empty())
+            {
+                tag.remove_key(key);
+            }
+        }
+    }
+    if let Some(ref v) = req.lyrics {
+        // ID3v2 only knows unsynced lyrics (USLT); other formats use the plain lyrics field.
+        let key = if tag.tag_type() == TagType::Id3v2 {
+            ItemKey::UnsyncLyrics
+        } else {
+            ItemKey::Lyrics
+        };
+        tag.remove_key(ItemKey::UnsyncLyrics);
+        tag.remove_key(ItemKey::Lyrics);
+        if !v.trim().is_empty() {
+            tag.insert_text(key, v.trim().to_string());
+        }
+    }
 
     use lofty::config::WriteOptions;
     tag.save_to_path(path, WriteOptions::default())
         .map_err(|e| format!("Cannot write tags: {e}"))?;
+
+    if let Some(ref v) = req.synced_lyrics {
+        let sidecar = lrc_sidecar_path(path);
+        if v.trim().is_empty() {
+            match fs::remove_file(&sidecar).await {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                    return Err(format!("Cannot remove lyrics file: {e}"));
+                }
+                _ => {}
+            }
+            // Otherwise an MP3's SYLT frame would be read back in place of the removed file.
+            remove_sylt(path)?;
+        } else {
+            fs::write(&sidecar, format!("{}\n", v.trim()))
+                .await
+                .map_err(|e| format!("Cannot write lyrics file: {e}"))?;
+        }
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn containers_map_to_codecs() {
+        assert_eq!(file_type_codec(&FileType::Mpeg), Some("mp3"));
+        assert_eq!(file_type_codec(&FileType::Wav), Some("pcm"));
+        // MP4 may hold AAC or ALAC, so its codec comes from the file.
+        assert_eq!(file_type_codec(&FileType::Mp4), None);
+
+        let lossless = |file_type| {
+            file_type_codec(&file_type).is_some_and(|codec| LOSSLESS_CODECS.contains(&codec))
+        };
+        assert!(lossless(FileType::Flac));
+        assert!(lossless(FileType::Aiff));
+        assert!(!lossless(FileType::Opus));
+        assert!(!lossless(FileType::Aac));
+    }
+}
@@ -0,0 +1,843 @@
+use super::*;
+
+use chrono::{DateTime, Utc};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, LazyLock};
+use tokio::io::{AsyncReadExt, AsyncWriteExt, DuplexStream};
+use tokio::sync::Semaphore;
+use tokio_postgres::error::SqlState;
+use uuid::Uuid;
+
+const TRANSCODE_CHUNK_SIZE: usize = 64 * 1024;
+const DEFAULT_CACHE_MB: u64 = 1024;
+
+/// Running ffmpeg processes, limited to `TRANSCODE_MAX_PROCESSES` (default: one per CPU).
+static FFMPEG_SLOTS: LazyLock<Arc<Semaphore>> =
+    LazyLock::new(|| Arc::new(Semaphore::new(max_ffmpeg_processes())));
+
+/// How ffmpeg produces one target format, and how it is served.
+pub(crate) struct Encoder {
+    codec: &'static str,
+    muxer: &'static str,
+    pub(crate) content_type: &'static str,
+    lossless: bool,
+}
+
+pub(crate) fn encoder(target_format: &str) -> Option<Encoder> {
+    let (codec, muxer, content_type, lossless) = match target_format {
+        "mp3" => ("libmp3lame", "mp3", "audio/mpeg", false),
+        "opus" => ("libopus", "opus", "audio/ogg", false),
+        "ogg" => ("libvorbis", "ogg", "audio/ogg", false),
+        "aac" => ("aac", "adts", "audio/aac", false),
+        "flac" => ("flac", "flac", "audio/flac", true),
+        _ => return None,
+    };
+    Some(Encoder {
+        codec,
+        muxer,
+        content_type,
+        lossless,
+    })
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub(crate) struct TranscodingProfile {
+    pub id: Uuid,
+    pub name: String,
+    /// Source formats the profile applies to; empty means any.
+    pub source_formats: Vec<String>,
+    pub target_format: String,
+    pub bitrate_kbps: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl TranscodingProfile {
+    fn accepts(&self, source_format: &str) -> bool {
+        self.source_formats.is_empty()
+            || self
+                .source_formats
+                .iter()
+                .any(|format| format.eq_ignore_ascii_case(source_format))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub(crate) struct TranscodingProfileRequest {
+    name: String,
+    #[serde(default)]
+    source_formats: Vec<String>,
+    target_format: String,
+    bitrate_kbps: i32,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub(crate) struct TranscodingDefaultsRequest {
+    profile_ids: Vec<Uuid>,
+}
+
+const PROFILE_COLUMNS: &str =
+    "p.id, p.name, p.source_formats, p.target_format, p.bitrate_kbps, p.created_at, p.updated_at";
+
+fn row_to_profile(row: &tokio_postgres::Row) -> TranscodingProfile {
+    TranscodingProfile {
+        id: row.get("id"),
+        name: row.get("name"),
+        source_formats: row.get("source_formats"),
+        target_format: row.get("target_format"),
+        bitrate_kbps: row.get("bitrate_kbps"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    }
+}
+
+pub(crate) async fn transcoding_profiles(
+    client: &deadpool_postgres::Object,
+) -> Result<Vec<TranscodingProfile>, tokio_postgres::Error> {
+    let rows = client
+        .query(
+            &format!("SELECT {PROFILE_COLUMNS} FROM transcoding_profiles p ORDER BY p.name"),
+            &[],
+        )
+        .await?;
+    Ok(rows.iter().map(row_to_profile).collect())
+}
+
+pub(crate) async fn default_transcoding_profiles(
+    client: &deadpool_postgres::Object,
+    user_id: Uuid,
+) -> Result<Vec<TranscodingProfile>, tokio_postgres::Error> {
+    let rows = client
+        .query(
+            &format!(
+                "SELECT {PROFILE_COLUMNS} FROM transcoding_profiles p
+                 JOIN user_transcoding_profiles utp ON utp.profile_id = p.id
+                 WHERE utp.user_id = $1 ORDER BY utp.position"
+            ),
+            &[&user_id],
+        )
+        .await?;
+    Ok(rows.iter().map(row_to_profile).collect())
+}
+
+/// Trimmed name and lower-cased, de-duplicated source formats of a valid profile.
+fn validate_profile(
+    request: &TranscodingProfileRequest,
+) -> Result<(String, Vec<String>), ApiError> {
+    let name = request.name.trim();
+    if name.is_empty() || name.len() > 64 {
+        return Err(bad_request("Name must be between 1 and 64 characters"));
+    }
+    if encoder(&request.target_format).is_none() {
+        return Err(bad_request(
+            "target_format must be one of mp3, opus, ogg, aac or flac",
+        ));
+    }
+    if !(32..=640).contains(&request.bitrate_kbps) {
+        return Err(bad_request("bitrate_kbps must be between 32 and 640"));
+    }
+    let mut source_formats: Vec<String> = Vec::new();
+    for format in &request.source_formats {
+        let format = format.trim().to_ascii_lowercase();
+        if format.is_empty() {
+            return Err(bad_request("Source formats must not be empty"));
+        }
+        if !source_formats.contains(&format) {
+            source_formats.push(format);
+        }
+    }
+    Ok((name.to_string(), source_formats))
+}
+
+fn profile_write_error(error: tokio_postgres::Error) -> ApiError {
+    if error.code() == Some(&SqlState::UNIQUE_VIOLATION) {
+        conflict("A transcoding profile with that name already exists")
+    } else {
+        db_error(error)
+    }
+}
+
+fn profile_id(id: &str) -> Result<Uuid, ApiError> {
+    Uuid::parse_str(id).map_err(|_| not_found("Transcoding profile not found"))
+}
+
+/// GET /api/music/transcoding/profiles - Every profile, so users can pick their defaults
+#[get("/music/transcoding/profiles")]
+pub(crate) async fn list_transcoding_profiles(
+    pool: &State<Pool>,
+    _user: AuthenticatedUser,
+) -> Result<Json<Vec<TranscodingProfile>>, ApiError> {
+    let client = get_client(pool).await?;
+    Ok(Json(transcoding_profiles(&client).await.map_err(db_error)?))
+}
+
+/// POST /api/music/transcoding/profiles - Create a profile
+#[post("/music/transcoding/profiles", data = "<request>")]
+pub(crate) async fn create_transcoding_profile(
+    pool: &State<Pool>,
+    user: AuthenticatedUser,
+    request: Json<TranscodingProfileRequest>,
+) -> Result<Json<TranscodingProfile>, ApiError> {
+    require_permission(pool, &user, "manage_transcoding").await?;
+    let (name, source_formats) = validate_profile(&request)?;
+
+    let client = get_client(pool).await?;
+    let row = client
+        .query_one(
+            &format!(
+                "INSERT INTO transcoding_profiles AS p (name, source_formats, target_format, bitrate_kbps)
+                 VALUES ($1, $2, $3, $4)
+                 RETURNING {PROFILE_COLUMNS}"
+            ),
+            &[
+                &name,
+                &source_formats,
+                &request.target_format,
+                &request.bitrate_kbps,
+            ],
+        )
+        .await
+        .map_err(profile_write_error)?;
+    let profile = row_to_profile(&row);
+
+    record_audit(
+        pool,
+        &user,
+        AuditEvent::new(
+            "transcoding_profile.create",
+            "transcoding_profile",
+            profile.id,
+        )
+        .after(serde_json::json!(profile)),
+    )
+    .await;
+    Ok(Json(profile))
+}
+
+/// PUT /api/music/transcoding/profiles/<id> - Replace a profile's settings
+#[put("/music/transcoding/profiles/<id>", data = "<request>")]
+pub(crate) async fn update_transcoding_profile(
+    pool: &State<Pool>,
+    user: AuthenticatedUser,
+    id: &str,
+    request: Json<TranscodingProfileRequest>,
+) -> Result<Json<TranscodingProfile>, ApiError> {
+    require_permission(pool, &user, "manage_transcoding").await?;
+    let id = profile_id(id)?;
+    let (name, source_formats) = validate_profile(&request)?;
+
+    let client = get_client(pool).await?;
+    let before = client
+        .query_opt(
+            &format!("SELECT {PROFILE_COLUMNS} FROM transcoding_profiles p WHERE p.id = $1"),
+            &[&id],
+        )
+        .await
+        .map_err(db_error)?
+        .map(|row| row_to_profile(&row))
+        .ok_or_else(|| not_found("Transcoding profile not found"))?;
+    let row = client
+        .query_opt(
+            &format!(
+                "UPDATE transcoding_profiles p
+                 SET name = $1, source_formats = $2, target_format = $3, bitrate_kbps = $4,
+                     updated_at = NOW()
+                 WHERE p.id = $5
+                 RETURNING {PROFILE_COLUMNS}"
+            ),
+            &[
+                &name,
+                &source_formats,
+                &request.target_format,
+                &request.bitrate_kbps,
+                &id,
+            ],
+        )
+        .await
+        .map_err(profile_write_error)?
+        .ok_or_else(|| not_found("Transcoding profile not found"))?;
+    let profile = row_to_profile(&row);
+
+    record_audit(
+        pool,
+        &user,
+        AuditEvent::new("transcoding_profile.update", "transcoding_profile", id)
+            .before(serde_json::json!(before))
+            .after(serde_json::json!(profile)),
+    )
+    .await;
+    Ok(Json(profile))
+}
+
+/// DELETE /api/music/transcoding/profiles/<id> - Delete a profile; users lose it as a default
+#[delete("/music/transcoding/profiles/<id>")]
+pub(crate) async fn delete_transcoding_profile(
+    pool: &State<Pool>,
+    user: AuthenticatedUser,
+    id: &str,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    require_permission(pool, &user, "manage_transcoding").await?;
+    let id = profile_id(id)?;
+
+    let client = get_client(pool).await?;
+    let row = client
+        .query_opt(
+            &format!(
+                "DELETE FROM transcoding_profiles p WHERE p.id = $1 RETURNING {PROFILE_COLUMNS}"
+            ),
+            &[&id],
+        )
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| not_found("Transcoding profile not found"))?;
+
+    record_audit(
+        pool,
+        &user,
+        AuditEvent::new("transcoding_profile.delete", "transcoding_profile", id)
+            .before(serde_json::json!(row_to_profile(&row))),
+    )
+    .await;
+    Ok(Json(
+        serde_json::json!({"message": "Transcoding profile deleted"}),
+    ))
+}
+
+/// GET /api/music/transcoding/defaults - The caller's default profiles, in order
+#[get("/music/transcoding/defaults")]
+pub(crate) async fn get_transcoding_defaults(
+    pool: &State<Pool>,
+    user: AuthenticatedUser,
+) -> Result<Json<Vec<TranscodingProfile>>, ApiError> {
+    let client = get_client(pool).await?;
+    Ok(Json(
+        default_transcoding_profiles(&client, user.id)
+            .await
+            .map_err(db_error)?,
+    ))
+}
+
+/// PUT /api/music/transcoding/defaults - Replace the caller's default profiles
+#[put("/music/transcoding/defaults", data = "<request>")]
+pub(crate) async fn set_transcoding_defaults(
+    pool: &State<Pool>,
+    user: AuthenticatedUser,
+    request: Json<TranscodingDefaultsRequest>,
+) -> Result<Json<Vec<TranscodingProfile>>, ApiError> {
+    let mut client = get_client(pool).await?;
+    let transaction = client.transaction().await.map_err(db_error)?;
+    transaction
+        .execute(
+            "DELETE FROM user_transcoding_profiles WHERE user_id = $1",
+            &[&user.id],
+        )
+        .await
+        .map_err(db_error)?;
+    for (position, profile_id) in request.profile_ids.iter().enumerate() {
+        let inserted = transaction
+            .execute(
+                "INSERT INTO user_transcoding_profiles (user_id, profile_id, position)
+                 SELECT $1, id, $3 FROM transcoding_profiles WHERE id = $2
+                 ON CONFLICT DO NOTHING",
+                &[&user.id, profile_id, &(position as i32)],
+            )
+            .await
+            .map_err(db_error)?;
+        if inserted == 0 && !request.profile_ids[..position].contains(profile_id) {
+            return Err(not_found("Transcoding profile not found"));
+        }
+    }
+    transaction.commit().await.map_err(db_error)?;
+
+    let client = get_client(pool).await?;
+    Ok(Json(
+        default_transcoding_profiles(&client, user.id)
+            .await
+            .map_err(db_error)?,
+    ))
+}
+
+// ── Stream planning ──
+
+/// What a client asked for on `stream`.
+#[derive(Debug, Default)]
+pub(crate) struct StreamOptions {
+    pub(crate) max_bitrate: Option<i32>,
+    pub(crate) format: Option<String>,
+    pub(crate) time_offset: Option<i32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct TranscodePlan {
+    pub(crate) target_format: String,
+    pub(crate) bitrate_kbps: i32,
+    pub(crate) offset_seconds: i32,
+}
+
+/// Decide whether a song must be transcoded, and how.
+///
+/// An explicit `format` picks the first profile producing it (or named after it); `raw`
+/// never transcodes. Otherwise the user's defaults apply. A `maxBitRate` the original
+/// exceeds, or a `timeOffset`, falls back to any matching profile. `None` means the
+/// original file is sent as is.
+pub(crate) fn plan_transcode(
+    source_format: &str,
+    source_bitrate: Option<i32>,
+    options: &StreamOptions,
+    defaults: &[TranscodingProfile],
+    profiles: &[TranscodingProfile],
+) -> Option<TranscodePlan> {
+    let requested = options.format.as_deref().map(str::to_ascii_lowercase);
+    if requested.as_deref() == Some("raw") {
+        return None;
+    }
+    let max_bitrate = options.max_bitrate.filter(|bitrate| *bitrate > 0);
+    let offset_seconds = options.time_offset.unwrap_or(0).max(0);
+    let exceeds_max =
+        max_bitrate.is_some_and(|max| source_bitrate.is_none_or(|bitrate| bitrate > max));
+
+    let matching = |profile: &&TranscodingProfile| profile.accepts(source_format);
+    let chosen = match requested.as_deref() {
+        Some(format) => profiles
+            .iter()
+            .filter(matching)
+            .find(|profile| profile.target_format == format)
+            .or_else(|| profiles.iter().filter(matching).find(|p| p.name == format)),
+        None => defaults.iter().find(matching),
+    };
+    let chosen = chosen.or_else(|| {
+        if exceeds_max || offset_seconds > 0 {
+            defaults
+                .iter()
+                .find(matching)
+                .or_else(|| profiles.iter().find(matching))
+        } else {
+            None
+        }
+    })?;
+
+    let bitrate_kbps = max_bitrate.map_or(chosen.bitrate_kbps, |max| chosen.bitrate_kbps.min(max));
+    let already_fits = chosen.target_format.eq_ignore_ascii_case(source_format)
+        && source_bitrate.is_some_and(|bitrate| bitrate <= bitrate_kbps);
+    if already_fits && offset_seconds == 0 {
+        return None;
+    }
+    Some(TranscodePlan {
+        target_format: chosen.target_format.clone(),
+        bitrate_kbps,
+        offset_seconds,
+    })
+}
+
+/// Approximate size of a lossy transcode, for clients that send `estimateContentLength`.
+pub(crate) fn estimated_length(duration_seconds: Option<f32>, plan: &TranscodePlan) -> Option<u64> {
+    if encoder(&plan.target_format)?.lossless {
+        return None;
+    }
+    let remaining = f64::from(duration_seconds?) - f64::from(plan.offset_seconds);
+    (remaining > 0.0).then(|| (remaining * f64::from(plan.bitrate_kbps) * 1000.0 / 8.0) as u64)
+}
+
+// ── ffmpeg and the transcode cache ──
+
+fn ffmpeg_path() -> String {
+    std::env::var("FFMPEG_PATH").unwrap_or_else(|_| "ffmpeg".to_string())
+}
+
+fn max_ffmpeg_processes() -> usize {
+    std::env::var("TRANSCODE_MAX_PROCESSES")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .filter(|max| *max > 0)
+        .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, usize::from))
+}
+
+fn cache_limit_bytes() -> u64 {
+    std::env::var("TRANSCODE_CACHE_MB")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_CACHE_MB)
+        * 1024
+        * 1024
+}
+
+fn ffmpeg_args(input: &Path, plan: &TranscodePlan, encoder: &Encoder) -> Vec<String> {
+    let mut args: Vec<String> = ["-nostdin", "-v", "error"].map(String::from).to_vec();
+    if plan.offset_seconds > 0 {
+        args.extend(["-ss".to_string(), plan.offset_seconds.to_string()]);
+    }
+    args.extend(["-i", &input.to_string_lossy(), "-map", "0:a:0", "-vn"].map(String::from));
+    args.extend(["-c:a", encoder.codec].map(String::from));
+    if !encoder.lossless {
+        args.extend(["-b:a".to_string(), format!("{}k", plan.bitrate_kbps)]);
+    }
+    args.extend(["-f", encoder.muxer, "pipe:1"].map(String::from));
+    args
+}
+
+/// Arguments that copy a finished transcode from `offset_seconds` on without re-encoding.
+fn ffmpeg_seek_args(cached: &Path, offset_seconds: i32, encoder: &Encoder) -> Vec<String> {
+    let mut args: Vec<String> = ["-nostdin", "-v", "error"].map(String::from).to_vec();
+    args.extend(["-ss".to_string(), offset_seconds.to_string()]);
+    args.extend(
+        [
+            "-i",
+            &cached.to_string_lossy(),
+            "-map",
+            "0:a:0",
+            "-c:a",
+            "copy",
+        ]
+        .map(String::from),
+    );
+    args.extend(["-f", encoder.muxer, "pipe:1"].map(String::from));
+    args
+}
+
+/// Where the whole-file transcode of this song version is kept; requests with a
+/// `timeOffset` seek within it rather than getting their own entry.
+pub(crate) fn transcode_cache_path(
+    song_id: Uuid,
+    song_version: &str,
+    plan: &TranscodePlan,
+) -> PathBuf {
+    let key = sha256_hex(&format!(
+        "{song_id}:{song_version}:{}:{}",
+        plan.target_format, plan.bitrate_kbps
+    ));
+    Path::new(TRANSCODE_CACHE_ROOT).join(format!("{key}.{}", plan.target_format))
+}
+
+/// Delete the oldest cached transcodes until the cache fits `TRANSCODE_CACHE_MB`.
+async fn prune_transcode_cache() {
+    let Ok(mut entries) = fs::read_dir(TRANSCODE_CACHE_ROOT).await else {
+        return;
+    };
+    let mut cached = Vec::new();
+    let mut total = 0u64;
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let path = entry.path();
+        if path.extension().and_then(|ext| ext.to_str()) == Some("part") {
+            continue;
+        }
+        let Ok(metadata) = entry.metadata().await else {
+            continue;
+        };
+        if metadata.is_file() {
+            total += metadata.len();
+            cached.push((metadata.modified().ok(), metadata.len(), path));
+        }
+    }
+    cached.sort_by_key(|(modified, _, _)| *modified);
+
+    let limit = cache_limit_bytes();
+    for (_, len, path) in cached {
+        if total <= limit {
+            break;
+        }
+        if fs::remove_file(&path).await.is_ok() {
+            total -= len;
+        }
+    }
+}
+
+fn plan_encoder(plan: &TranscodePlan) -> std::io::Result<Encoder> {
+    encoder(&plan.target_format).ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("unknown target format {}", plan.target_format),
+        )
+    })
+}
+
+/// Start ffmpeg on `input` and return its output as it is produced.
+///
+/// A transcode of the whole file is also written next to `cache_path` and moved into place
+/// once ffmpeg finishes cleanly. If the client goes away first, ffmpeg is stopped and
+/// nothing is cached. Fails when `TRANSCODE_MAX_PROCESSES` transcodes are already running.
+pub(crate) fn spawn_transcode(
+    input: &Path,
+    plan: &TranscodePlan,
+    cache_path: PathBuf,
+) -> std::io::Result<DuplexStream> {
+    let encoder = plan_encoder(plan)?;
+    let cache_path = (plan.offset_seconds == 0).then_some(cache_path);
+    spawn_ffmpeg(ffmpeg_args(input, plan, &encoder), cache_path)
+}
+
+/// Stream the cached whole-file transcode at `cached` from the plan's offset on.
+pub(crate) fn seek_cached_transcode(
+    cached: &Path,
+    plan: &TranscodePlan,
+) -> std::io::Result<DuplexStream> {
+    let encoder = plan_encoder(plan)?;
+    spawn_ffmpeg(
+        ffmpeg_seek_args(cached, plan.offset_seconds, &encoder),
+        None,
+    )
+}
+
+fn spawn_ffmpeg(args: Vec<String>, cache_path: Option<PathBuf>) -> std::io::Result<DuplexStream> {
+    let slot = FFMPEG_SLOTS
+        .clone()
+        .try_acquire_owned()
+        .map_err(|_| std::io::Error::other("too many transcodes are running"))?;
+    let mut child = tokio::process::Command::new(ffmpeg_path())
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .kill_on_drop(true)
+        .spawn()?;
+    let mut stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| std::io::Error::other("ffmpeg stdout is not piped"))?;
+    let (mut client, reader) = tokio::io::duplex(TRANSCODE_CHUNK_SIZE);
+
+    tokio::spawn(async move {
+        let partial = cache_path
+            .as_ref()
+            .map(|path| path.with_extension(format!("{}.part", random_hex::<8>())));
+        let mut cache = match &partial {
+            Some(partial) => fs::File::create(partial).await.ok(),
+            None => None,
+        };
+        let mut buffer = vec![0u8; TRANSCODE_CHUNK_SIZE];
+        let finished = loop {
+            match stdout.read(&mut buffer).await {
+                Ok(0) => break true,
+                Ok(read) => {
+                    if client.write_all(&buffer[..read]).await.is_err() {
+                        break false;
+                    }
+                    if let Some(file) = cache.as_mut()
+                        && file.write_all(&buffer[..read]).await.is_err()
+                    {
+                        cache = None;
+                    }
+                }
+                Err(_) => break false,
+            }
+        };
+        drop(client);
+
+        let succeeded = if finished {
+            child.wait().await.is_ok_and(|status| status.success())
+        } else {
+            child.kill().await.ok();
+            false
+        };
+        drop(slot);
+
+        let (Some(partial), Some(cache_path)) = (partial, cache_path) else {
+            return;
+        };
+        let cached = match cache {
+            Some(mut file) if succeeded => {
+                file.flush().await.is_ok() && fs::rename(&partial, &cache_path).await.is_ok()
+            }
+            _ => false,
+        };
+        if cached {
+            prune_transcode_cache().await;
+        } else {
+            fs::remove_file(&partial).await.ok();
+        }
+    });
+
+    Ok(reader)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(name: &str, sources: &[&str], target: &str, bitrate: i32) -> TranscodingProfile {
+        TranscodingProfile {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            source_formats: sources.iter().map(|s| s.to_string()).collect(),
+            target_format: target.to_string(),
+            bitrate_kbps: bitrate,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn options(
+        max_bitrate: Option<i32>,
+        format: Option<&str>,
+        offset: Option<i32>,
+    ) -> StreamOptions {
+        StreamOptions {
+            max_bitrate,
+            format: format.map(String::from),
+            time_offset: offset,
+        }
+    }
+
+    #[test]
+    fn defaults_apply_only_to_matching_sources() {
+        let opus = profile("flac-to-opus-128", &["flac", "wav"], "opus", 128);
+        let profiles = [opus.clone(), profile("mp3-320", &[], "mp3", 320)];
+        let defaults = [opus];
+
+        let plan = plan_transcode(
+            "flac",
+            Some(900),
+            &options(None, None, None),
+            &defaults,
+            &profiles,
+        );
+        assert_eq!(
+            plan,
+            Some(TranscodePlan {
+                target_format: "opus".into(),
+                bitrate_kbps: 128,
+                offset_seconds: 0
+            })
+        );
+        assert_eq!(
+            plan_transcode(
+                "mp3",
+                Some(320),
+                &options(None, None, None),
+                &defaults,
+                &profiles
+            ),
+            None
+        );
+        assert_eq!(
+            plan_transcode(
+                "flac",
+                Some(900),
+                &options(None, Some("raw"), None),
+                &defaults,
+                &profiles
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn requested_format_and_max_bitrate_are_honoured() {
+        let profiles = [
+            profile("flac-to-opus-128", &["flac"], "opus", 128),
+            profile("mp3-320", &[], "mp3", 320),
+        ];
+
+        let plan = plan_transcode(
+            "flac",
+            Some(900),
+            &options(Some(192), Some("mp3"), None),
+            &[],
+            &profiles,
+        )
+        .expect("mp3 was requested");
+        assert_eq!(
+            (plan.target_format.as_str(), plan.bitrate_kbps),
+            ("mp3", 192)
+        );
+
+        // Already small enough in the requested format.
+        assert_eq!(
+            plan_transcode(
+                "mp3",
+                Some(128),
+                &options(Some(192), Some("mp3"), None),
+                &[],
+                &profiles
+            ),
+            None
+        );
+
+        // No defaults, but the original exceeds maxBitRate.
+        let plan = plan_transcode(
+            "flac",
+            Some(900),
+            &options(Some(96), None, None),
+            &[],
+            &profiles,
+        )
+        .expect("original exceeds maxBitRate");
+        assert_eq!(
+            (plan.target_format.as_str(), plan.bitrate_kbps),
+            ("opus", 96)
+        );
+
+        let plan = plan_transcode(
+            "mp3",
+            Some(128),
+            &options(None, None, Some(30)),
+            &[],
+            &profiles,
+        )
+        .expect("timeOffset needs a transcode");
+        assert_eq!(plan.offset_seconds, 30);
+    }
+
+    #[test]
+    fn ffmpeg_arguments_seek_before_input_and_skip_bitrate_for_lossless() {
+        let plan = TranscodePlan {
+            target_format: "mp3".into(),
+            bitrate_kbps: 192,
+            offset_seconds: 15,
+        };
+        let args = ffmpeg_args(
+            Path::new("a/b.flac"),
+            &plan,
+            &encoder("mp3").expect("mp3 is supported"),
+        );
+        assert_eq!(
+            args.join(" "),
+            "-nostdin -v error -ss 15 -i a/b.flac -map 0:a:0 -vn -c:a libmp3lame -b:a 192k -f mp3 pipe:1"
+        );
+
+        let plan = TranscodePlan {
+            target_format: "flac".into(),
+            bitrate_kbps: 320,
+            offset_seconds: 0,
+        };
+        let args = ffmpeg_args(
+            Path::new("a.wav"),
+            &plan,
+            &encoder("flac").expect("flac is supported"),
+        );
+        assert!(!args.contains(&"-b:a".to_string()));
+        assert_eq!(estimated_length(Some(60.0), &plan), None);
+    }
+
+    #[test]
+    fn offsets_seek_within_the_whole_file_transcode() {
+        let whole = TranscodePlan {
+            target_format: "opus".into(),
+            bitrate_kbps: 128,
+            offset_seconds: 0,
+        };
+        let seeking = TranscodePlan {
+            offset_seconds: 90,
+            ..whole.clone()
+        };
+        let song_id = Uuid::nil();
+        let cached = transcode_cache_path(song_id, "1:2", &whole);
+        assert_eq!(transcode_cache_path(song_id, "1:2", &seeking), cached);
+
+        let args = ffmpeg_seek_args(
+            &cached,
+            seeking.offset_seconds,
+            &encoder("opus").expect("opus is supported"),
+        );
+        assert_eq!(
+            args.join(" "),
+            format!(
+                "-nostdin -v error -ss 90 -i {} -map 0:a:0 -c:a copy -f opus pipe:1",
+                cached.display()
+            )
+        );
+    }
+}
@@ -3,6 +3,7 @@ use rocket::State;
 use rocket::data::Data;
 use uuid::Uuid;
 
+use super::{AuditEvent, record_audit};
 use crate::auth::{AuthenticatedUser, require_permission};
 use crate::shared::*;
 
@@ -17,7 +18,7 @@ pub(crate) async fn create_music_upload(
     user: AuthenticatedUser,
     headers: TusHeaders,
 ) -> Result<TusResponse, ApiError> {
-    require_permission(pool, user.id, "music_upload").await?;
+    require_permission(pool, &user, "music_upload").await?;
     cleanup_expired_uploads(pool).await;
     create_user_upload(pool, user.id, MUSIC_ROOT, &headers, "/api/music/uploads").await
 }
@@ -30,7 +31,7 @@ pub(crate) async fn head_music_upload(
     id: &str,
 ) -> Result<TusResponse, ApiError> {
     let id = parse_upload_id(id)?;
-    require_permission(pool, user.id, "music_upload").await?;
+    require_permission(pool, &user, "music_upload").await?;
     head_user_upload(pool, user.id, id).await
 }
 
@@ -43,11 +44,11 @@ pub(crate) async fn patch_music_upload(
     data: Data<'_>,
 ) -> Result<TusResponse, ApiError> {
     let id = parse_upload_id(id)?;
-    require_permission(pool, user.id, "music_upload").await?;
+    require_permission(pool, &user, "music_upload").await?;
     let (response, completed) =
         patch_user_upload(pool, user.id, MUSIC_ROOT, &headers, id, data).await?;
     if let Some(target_path) = completed {
-        finalize_music_upload(pool, id, user.id, &target_path).await?;
+        finalize_music_upload(pool, &user, id, &target_path).await?;
     }
     Ok(response)
 }
@@ -60,19 +61,74 @@ pub(crate) async fn terminate_music_upload(
     id: &str,
 ) -> Result<TusResponse, ApiError> {
     let id = parse_upload_id(id)?;
-    require_permission(pool, user.id, "music_upload").await?;
+    require_permission(pool, &user, "music_upload").await?;
     terminate_user_upload(pool, MUSIC_ROOT, user.id, id).await
 }
 
 async fn finalize_music_upload(
     pool: &Pool,
+    user: &AuthenticatedUser,
     id: Uuid,
-    user_id: Uuid,
     target_path: &str,
 ) -> Result<(), ApiError> {
-    finalize_user_upload(pool, MUSIC_ROOT, id, user_id, target_path).await?;
+    if is_lrc(target_path) {
+        finalize_user_upload(pool, MUSIC_ROOT, id, user.id, target_path).await?;
+        let songs = match super::refresh_sidecar_lyrics(pool, target_path).await {
+            Ok(songs) => songs,
+            Err(error) => {
+                eprintln!("Failed to read lyrics from {target_path}: {error}");
+                Vec::new()
+            }
+        };
+        record_audit(
+            pool,
+            user,
+            AuditEvent::new("lyrics.upload", "file", target_path)
+                .after(serde_json::json!({"path": target_path, "song_ids": songs})),
+        )
+        .await;
+        return Ok(());
+    }
+
+    // A song row can outlive its file when the file was removed outside the library.
+    let before = song_snapshot(pool, target_path).await?;
+    finalize_user_upload(pool, MUSIC_ROOT, id, user.id, target_path).await?;
     if let Err(error) = super::scan_and_insert_song(pool, target_path).await {
         eprintln!("Failed to scan tags for {target_path}: {error}");
     }
+    let after = song_snapshot(pool, target_path).await?;
+
+    let mut event = AuditEvent::new("song.upload", "file", target_path).after(serde_json::json!({
+        "path": target_path,
+        "song": after,
+    }));
+    if let Some(before) = before {
+        event = event.before(serde_json::json!({"path": target_path, "song": before}));
+    }
+    record_audit(pool, user, event).await;
     Ok(())
 }
+
+/// Synced lyrics for the track with the same name, rather than a track of its own.
+fn is_lrc(path: &str) -> bool {
+    std::path::Path::new(path)
+        .extension()
+        .is_some_and(|ext| ext == "lrc")
+}
+
+/// The id and tags of the song stored at `file_path`, if there is one.
+async fn song_snapshot(
+    pool: &Pool,
+    file_path: &str,
+) -> Result<Option<serde_json::Value>, ApiError> {
+    let client = get_client(pool).await?;
+    let row = client
+        .query_opt("SELECT * FROM songs WHERE file_path = $1", &[&file_path])
+        .await
+        .map_err(db_error)?;
+    Ok(row.map(|row| {
+        let mut snapshot = super::tag_snapshot(&row);
+        snapshot["id"] = serde_json::json!(row.get::<_, Uuid>("id").to_string());
+        snapshot
+    }))
+}
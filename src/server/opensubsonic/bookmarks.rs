@@ -1,32 +1,206 @@
 use super::*;
 
+// ── Bookmarks ──
+
 #[derive(Debug, Serialize)]
 #[serde(crate = "rocket::serde")]
 pub struct BookmarksResponse {
-    pub bookmarks: EmptyResponse,
+    pub bookmarks: BookmarkList,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct BookmarkList {
+    pub bookmark: Vec<BookmarkEntry>,
+}
+
+/// A saved play position in milliseconds, with the bookmarked song as `entry`.
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct BookmarkEntry {
+    pub position: i64,
+    pub username: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    pub created: String,
+    pub changed: String,
+    pub entry: SongEntry,
 }
 
 #[get("/getBookmarks")]
-pub(crate) fn get_bookmarks(_user: SubsonicUser) -> Json<serde_json::Value> {
+pub(crate) async fn get_bookmarks(
+    pool: &State<Pool>,
+    user: SubsonicUser,
+) -> Json<serde_json::Value> {
+    let Ok(client) = pool.get().await else {
+        return db_err_resp();
+    };
+    // Songs since removed from the personal library keep their row but are not listed.
+    let Ok(rows) = client
+        .query(
+            &format!(
+                "SELECT s.*, b.position_ms, b.comment, {}, {}, \
+                        b.created_at AS bookmark_created_at, b.updated_at AS bookmark_updated_at \
+                 FROM bookmarks b \
+                 JOIN songs s ON s.id = b.song_id \
+                 JOIN user_songs us ON us.song_id = b.song_id AND us.user_id = b.user_id \
+                 WHERE b.user_id = $1 \
+                 ORDER BY b.updated_at DESC",
+                song_rating_columns("$1", "s.id"),
+                song_credit_columns("s.id")
+            ),
+            &[&user.id],
+        )
+        .await
+    else {
+        return db_err_resp();
+    };
+
+    let bookmark = rows
+        .iter()
+        .map(|row| BookmarkEntry {
+            position: row.get("position_ms"),
+            username: user.username.clone(),
+            comment: row.get("comment"),
+            created: row
+                .get::<_, chrono::DateTime<Utc>>("bookmark_created_at")
+                .to_rfc3339(),
+            changed: row
+                .get::<_, chrono::DateTime<Utc>>("bookmark_updated_at")
+                .to_rfc3339(),
+            entry: row_to_song_entry(row),
+        })
+        .collect();
     ok_resp(BookmarksResponse {
-        bookmarks: EmptyResponse {},
+        bookmarks: BookmarkList { bookmark },
     })
 }
 
+#[get("/createBookmark")]
+pub(crate) async fn create_bookmark(
+    pool: &State<Pool>,
+    user: SubsonicUser,
+    query: SubsonicQuery,
+) -> Json<serde_json::Value> {
+    let Some(id) = query.first("id") else {
+        return param_err("Required parameter 'id' is missing");
+    };
+    let Some(position) = query.first("position") else {
+        return param_err("Required parameter 'position' is missing");
+    };
+    let Some(position) = position.parse::<i64>().ok().filter(|ms| *ms >= 0) else {
+        return param_err("Parameter 'position' must be a non-negative number of milliseconds");
+    };
+    let Ok(song) = Uuid::parse_str(&id) else {
+        return not_found_resp();
+    };
+    let comment = query.first("comment");
+    let Ok(client) = pool.get().await else {
+        return db_err_resp();
+    };
+    match in_personal_library(&client, user.id, song).await {
+        Ok(true) => {}
+        _ => return not_found_resp(),
+    }
+
+    if client
+        .execute(
+            "INSERT INTO bookmarks (user_id, song_id, position_ms, comment) \
+             VALUES ($1, $2, $3, $4) \
+             ON CONFLICT (user_id, song_id) DO UPDATE \
+             SET position_ms = EXCLUDED.position_ms, comment = EXCLUDED.comment, \
+                 updated_at = NOW()",
+            &[&user.id, &song, &position, &comment],
+        )
+        .await
+        .is_err()
+    {
+        return db_err_resp();
+    }
+    ok_empty_resp()
+}
+
+#[get("/deleteBookmark")]
+pub(crate) async fn delete_bookmark(
+    pool: &State<Pool>,
+    user: SubsonicUser,
+    query: SubsonicQuery,
+) -> Json<serde_json::Value> {
+    let Some(id) = query.first("id") else {
+        return param_err("Required parameter 'id' is missing");
+    };
+    let Ok(song) = Uuid::parse_str(&id) else {
+        return not_found_resp();
+    };
+    let Ok(client) = pool.get().await else {
+        return db_err_resp();
+    };
+    match client
+        .execute(
+            "DELETE FROM bookmarks WHERE user_id = $1 AND song_id = $2",
+            &[&user.id, &song],
+        )
+        .await
+    {
+        Ok(0) => not_found_resp(),
+        Ok(_) => ok_empty_resp(),
+        Err(_) => db_err_resp(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
-    fn get_bookmarks_returns_an_empty_bookmarks_container() {
-        let response = get_bookmarks(SubsonicUser {
-            id: Uuid::nil(),
-            username: "test".into(),
+    fn bookmarks_serialize_with_position_and_song_entry() {
+        let response = ok_resp(BookmarksResponse {
+            bookmarks: BookmarkList {
+                bookmark: vec![BookmarkEntry {
+                    position: 90_500,
+                    username: "test".into(),
+                    comment: None,
+                    created: "2026-01-01T00:00:00+00:00".into(),
+                    changed: "2026-01-02T00:00:00+00:00".into(),
+                    entry: SongEntry {
+                        id: Uuid::nil().to_string(),
+                        title: "Mix".into(),
+                        artist: "DJ".into(),
+                        album: "Sets".into(),
+                        track: None,
+                        year: None,
+                        genre: None,
+                        cover_art: None,
+                        duration: None,
+                        bitrate: None,
+                        size: None,
+                        content_type: "audio/mpeg".into(),
+                        is_dir: false,
+                        is_video: false,
+                        sampling_rate: Some(44_100),
+                        bit_depth: None,
+                        channel_count: Some(2),
+                        rating: Rating::default(),
+                        replay_gain: Some(ReplayGain {
+                            track_gain: Some(-6.5),
+                            track_peak: Some(0.5),
+                            ..ReplayGain::default()
+                        }),
+                        credits: EntryCredits::default(),
+                    },
+                }],
+            },
         })
         .into_inner();
-        let body = &response["subsonic-response"];
+        let bookmark = &response["subsonic-response"]["bookmarks"]["bookmark"][0];
 
-        assert_eq!(body["status"], "ok");
-        assert_eq!(body["bookmarks"], serde_json::json!({}));
+        assert_eq!(bookmark["position"], 90_500);
+        assert_eq!(bookmark["username"], "test");
+        assert!(bookmark.get("comment").is_none());
+        assert_eq!(bookmark["entry"]["title"], "Mix");
+        assert_eq!(bookmark["entry"]["samplingRate"], 44_100);
+        assert!(bookmark["entry"].get("bitDepth").is_none());
+        assert_eq!(bookmark["entry"]["replayGain"]["trackGain"], -6.5);
+        assert!(bookmark["entry"]["replayGain"].get("albumGain").is_none());
     }
 }
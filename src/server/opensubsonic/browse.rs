@@ -47,6 +47,8 @@ pub struct IndexEntry {
 pub struct ArtistRef {
     pub id: String,
     pub name: String,
+    #[serde(flatten)]
+    pub rating: Rating,
 }
 
 #[derive(Debug, Serialize)]
@@ -106,6 +108,8 @@ pub enum ChildEntry {
         is_dir: bool,
         #[serde(rename = "isVideo")]
         is_video: bool,
+        #[serde(flatten)]
+        rating: Rating,
     },
 }
 
@@ -132,6 +136,8 @@ pub struct ArtistResponse {
 pub struct ArtistDetail {
     pub id: String,
     pub name: String,
+    #[serde(flatten)]
+    pub rating: Rating,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub album: Option<Vec<AlbumRef>>,
 }
@@ -148,6 +154,8 @@ pub struct AlbumInfo {
     pub genre: Option<String>,
     #[serde(rename = "coverArt", skip_serializing_if = "Option::is_none")]
     pub cover_art: Option<String>,
+    #[serde(flatten)]
+    pub rating: Rating,
 }
 
 impl AlbumInfo {
@@ -167,6 +175,7 @@ impl AlbumInfo {
             year: row.try_get("year").ok(),
             genre: row.try_get("genre").ok(),
             cover_art,
+            rating: Rating::from_row(row),
         }
     }
 }
@@ -228,6 +237,46 @@ pub struct SongEntry {
     pub is_dir: bool,
     #[serde(rename = "isVideo")]
     pub is_video: bool,
+    #[serde(rename = "samplingRate", skip_serializing_if = "Option::is_none")]
+    pub sampling_rate: Option<i32>,
+    #[serde(rename = "bitDepth", skip_serializing_if = "Option::is_none")]
+    pub bit_depth: Option<i16>,
+    #[serde(rename = "channelCount", skip_serializing_if = "Option::is_none")]
+    pub channel_count: Option<i16>,
+    #[serde(flatten)]
+    pub rating: Rating,
+    #[serde(rename = "replayGain", skip_serializing_if = "Option::is_none")]
+    pub replay_gain: Option<ReplayGain>,
+    #[serde(flatten)]
+    pub credits: EntryCredits,
+}
+
+/// OpenSubsonic `replayGain`: gains in dB, peaks as linear sample values.
+#[derive(Debug, Serialize, Clone, Default, PartialEq)]
+#[serde(crate = "rocket::serde")]
+pub struct ReplayGain {
+    #[serde(rename = "trackGain", skip_serializing_if = "Option::is_none")]
+    pub track_gain: Option<f32>,
+    #[serde(rename = "albumGain", skip_serializing_if = "Option::is_none")]
+    pub album_gain: Option<f32>,
+    #[serde(rename = "trackPeak", skip_serializing_if = "Option::is_none")]
+    pub track_peak: Option<f32>,
+    #[serde(rename = "albumPeak", skip_serializing_if = "Option::is_none")]
+    pub album_peak: Option<f32>,
+}
+
+impl ReplayGain {
+    /// Read the analysed `replay_gain_*` columns; `None` until the song has been analysed.
+    pub(super) fn from_row(row: &tokio_postgres::Row) -> Option<Self> {
+        let column = |name: &str| row.try_get::<_, Option<f32>>(name).ok().flatten();
+        let gain = Self {
+            track_gain: column("replay_gain_track_gain"),
+            album_gain: column("replay_gain_album_gain"),
+            track_peak: column("replay_gain_track_peak"),
+            album_peak: column("replay_gain_album_peak"),
+        };
+        (gain != Self::default()).then_some(gain)
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -304,9 +353,11 @@ pub(crate) async fn get_indexes(pool: &State<Pool>, user: SubsonicUser) -> Json<
 
     let Ok(rows) = client
         .query(
-            "SELECT DISTINCT UPPER(LEFT(s.artist, 1)) AS letter FROM songs s \
-             JOIN user_songs us ON s.id = us.song_id \
-             WHERE us.user_id = $1 ORDER BY letter",
+            &format!(
+                "SELECT DISTINCT UPPER(LEFT(la.artist, 1)) AS letter FROM ({}) la \
+                 ORDER BY letter",
+                library_artists("$1")
+            ),
             &[&user.id],
         )
         .await
@@ -319,8 +370,12 @@ pub(crate) async fn get_indexes(pool: &State<Pool>, user: SubsonicUser) -> Json<
         let letter: String = letter_row.get("letter");
         let artists_rows = match client
             .query(
-                "SELECT DISTINCT s.artist FROM songs s JOIN user_songs us ON s.id = us.song_id \
-             WHERE us.user_id = $1 AND UPPER(LEFT(s.artist, 1)) = $2 ORDER BY s.artist",
+                &format!(
+                    "SELECT la.artist, {} FROM ({}) la \
+                     WHERE UPPER(LEFT(la.artist, 1)) = $2 ORDER BY la.artist",
+                    artist_rating_columns("$1", "la.artist"),
+                    library_artists("$1")
+                ),
                 &[&user.id, &letter],
             )
             .await
@@ -335,6 +390,7 @@ pub(crate) async fn get_indexes(pool: &State<Pool>, user: SubsonicUser) -> Json<
                 ArtistRef {
                     id: artist_id(&name),
                     name,
+                    rating: Rating::from_row(r),
                 }
             })
             .collect();
@@ -365,9 +421,7 @@ pub(crate) async fn get_music_directory(
         // Root: list distinct artists
         let Ok(rows) = client
             .query(
-                "SELECT DISTINCT s.artist FROM songs s \
-                 JOIN user_songs us ON s.id = us.song_id \
-                 WHERE us.user_id = $1 ORDER BY s.artist",
+                &format!("{} ORDER BY artist", library_artists("$1")),
                 &[&user.id],
             )
             .await
@@ -393,9 +447,12 @@ pub(crate) async fn get_music_directory(
         // Artist: list albums
         let Ok(rows) = client
             .query(
-                "SELECT DISTINCT s.album, s.artist, s.year, s.genre FROM songs s \
-                 JOIN user_songs us ON s.id = us.song_id \
-                 WHERE us.user_id = $1 AND s.artist = $2 ORDER BY s.album",
+                &format!(
+                    "SELECT DISTINCT s.album, s.artist, s.year, s.genre FROM songs s \
+                     JOIN user_songs us ON s.id = us.song_id \
+                     WHERE us.user_id = $1 AND {} ORDER BY s.album",
+                    song_credits_artist("s.id", "$2")
+                ),
                 &[&user.id, &artist_name],
             )
             .await
@@ -423,9 +480,13 @@ pub(crate) async fn get_music_directory(
         if let Some((artist_name, album_name)) = rest.split_once('|') {
             let Ok(rows) = client
                 .query(
-                    "SELECT s.* FROM songs s JOIN user_songs us ON s.id = us.song_id \
-                     WHERE us.user_id = $1 AND s.artist = $2 AND s.album = $3 \
-                     ORDER BY s.track_number, s.title",
+                    &format!(
+                        "SELECT s.*, {}, {} FROM songs s JOIN user_songs us ON s.id = us.song_id \
+                         WHERE us.user_id = $1 AND s.artist = $2 AND s.album = $3 \
+                         ORDER BY s.track_number, s.title",
+                        song_rating_columns("$1", "s.id"),
+                        song_credit_columns("s.id")
+                    ),
                     &[&user.id, &artist_name, &album_name],
                 )
                 .await
@@ -452,6 +513,7 @@ pub(crate) async fn get_music_directory(
                         content_type: s.content_type,
                         is_dir: false,
                         is_video: false,
+                        rating: s.rating,
                     }
                 })
                 .collect();
@@ -488,9 +550,11 @@ pub(crate) async fn get_artists(pool: &State<Pool>, user: SubsonicUser) -> Json<
 
     let Ok(rows) = client
         .query(
-            "SELECT DISTINCT s.artist FROM songs s \
-             JOIN user_songs us ON s.id = us.song_id \
-             WHERE us.user_id = $1 ORDER BY s.artist",
+            &format!(
+                "SELECT la.artist, {} FROM ({}) la ORDER BY la.artist",
+                artist_rating_columns("$1", "la.artist"),
+                library_artists("$1")
+            ),
             &[&user.id],
         )
         .await
@@ -511,6 +575,7 @@ pub(crate) async fn get_artists(pool: &State<Pool>, user: SubsonicUser) -> Json<
         index_map.entry(letter).or_default().push(ArtistRef {
             id: artist_id(&name),
             name: name.clone(),
+            rating: Rating::from_row(row),
         });
     }
 
@@ -537,14 +602,22 @@ pub(crate) async fn get_artist(
         return not_found_resp();
     };
 
+    // The credit picks the albums; their totals cover every song on them.
     let Ok(album_rows) = client
         .query(
-            "SELECT DISTINCT s.album, s.artist, s.year, s.genre, \
-                    COUNT(*) as song_count, SUM(s.duration_seconds) as total_dur, \
-                    BOOL_OR(s.has_cover_art) as has_cover \
-             FROM songs s JOIN user_songs us ON s.id = us.song_id \
-             WHERE us.user_id = $1 AND s.artist = $2 \
-             GROUP BY s.album, s.artist, s.year, s.genre ORDER BY s.year, s.album",
+            &format!(
+                "SELECT DISTINCT s.album, s.artist, s.year, s.genre, \
+                        COUNT(*) as song_count, SUM(s.duration_seconds) as total_dur, \
+                        BOOL_OR(s.has_cover_art) as has_cover, {} \
+                 FROM songs s JOIN user_songs us ON s.id = us.song_id \
+                 WHERE us.user_id = $1 AND (s.artist, s.album) IN ( \
+                     SELECT c.artist, c.album FROM songs c \
+                     JOIN user_songs cu ON c.id = cu.song_id \
+                     WHERE cu.user_id = $1 AND {}) \
+                 GROUP BY s.album, s.artist, s.year, s.genre ORDER BY s.year, s.album",
+                album_rating_columns("$1", "s.artist", "s.album"),
+                song_credits_artist("c.id", "$2")
+            ),
             &[&user.id, &artist_name],
         )
         .await
@@ -560,11 +633,15 @@ pub(crate) async fn get_artist(
             duration: r.get("total_dur"),
         })
         .collect();
+    let Ok(rating) = artist_rating(&client, user.id, &artist_name).await else {
+        return db_err_resp();
+    };
 
     ok_resp(ArtistResponse {
         artist: ArtistDetail {
             id: artist_id(&artist_name),
             name: artist_name,
+            rating,
             album: (!albums.is_empty()).then_some(albums),
         },
     })
@@ -586,15 +663,22 @@ pub(crate) async fn get_album(
 
     let Ok(song_rows) = client
         .query(
-            "SELECT s.* FROM songs s JOIN user_songs us ON s.id = us.song_id \
-             WHERE us.user_id = $1 AND s.artist = $2 AND s.album = $3 \
-             ORDER BY s.track_number, s.title",
+            &format!(
+                "SELECT s.*, {}, {} FROM songs s JOIN user_songs us ON s.id = us.song_id \
+                 WHERE us.user_id = $1 AND s.artist = $2 AND s.album = $3 \
+                 ORDER BY s.track_number, s.title",
+                song_rating_columns("$1", "s.id"),
+                song_credit_columns("s.id")
+            ),
             &[&user.id, &artist_name, &album_name],
         )
         .await
     else {
         return db_err_resp();
     };
+    let Ok(rating) = album_rating(&client, user.id, &artist_name, &album_name).await else {
+        return db_err_resp();
+    };
 
     let songs: Vec<SongEntry> = song_rows.iter().map(row_to_song_entry).collect();
     let total_dur: f64 = song_rows
@@ -619,6 +703,7 @@ pub(crate) async fn get_album(
                 year,
                 genre,
                 cover_art: has_cover.then(|| id.clone()),
+                rating,
             },
             song_count,
             duration: (total_dur > 0.0).then_some(total_dur),
@@ -643,8 +728,12 @@ pub(crate) async fn get_song(
 
     let row = match client
         .query_opt(
-            "SELECT s.* FROM songs s JOIN user_songs us ON s.id = us.song_id \
-             WHERE us.user_id = $1 AND s.id = $2",
+            &format!(
+                "SELECT s.*, {}, {} FROM songs s JOIN user_songs us ON s.id = us.song_id \
+                 WHERE us.user_id = $1 AND s.id = $2",
+                song_rating_columns("$1", "s.id"),
+                song_credit_columns("s.id")
+            ),
             &[&user.id, &song_uuid],
         )
         .await
@@ -684,17 +773,26 @@ async fn album_list(
         return db_err_resp();
     };
 
+    let genre_filter = format!("AND {}", song_has_genre("s.id", "$2"));
     let mut params: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = vec![&user.id];
-    let (filter, order) = match query.kind.as_str() {
-        "newest" => ("", "s.artist, s.album, s.created_at DESC"),
-        "alphabeticalByName" => ("", "s.artist, s.album, s.album"),
-        "alphabeticalByArtist" => ("", "s.artist, s.album"),
+    // `pick` chooses the song row standing in for each album and must start with the
+    // DISTINCT ON columns; `order` then sorts those albums `a`.
+    const BY_ALBUM: &str = "s.artist, s.album";
+    let (filter, pick, order) = match query.kind.as_str() {
+        "newest" => (
+            "",
+            "s.artist, s.album, s.created_at DESC",
+            "a.artist, a.album",
+        ),
+        "alphabeticalByName" => ("", "s.artist, s.album, s.album", "a.artist, a.album"),
+        "alphabeticalByArtist" => ("", BY_ALBUM, "a.artist, a.album"),
         "byYear" => {
             params.push(&from_year);
             params.push(&to_year);
             (
                 "AND s.year BETWEEN $2 AND $3",
-                "s.year DESC, s.artist, s.album",
+                BY_ALBUM,
+                "a.year DESC, a.artist, a.album",
             )
         }
         "byGenre" => {
@@ -702,13 +800,19 @@ async fn album_list(
                 return param_err("Genre parameter required for byGenre list");
             };
             params.push(genre);
-            ("AND s.genre = $2", "s.artist, s.album")
+            (genre_filter.as_str(), BY_ALBUM, "a.artist, a.album")
         }
-        "random" => ("", "RANDOM()"),
+        "random" => ("", BY_ALBUM, "RANDOM()"),
         "frequent" => (
             "",
+            BY_ALBUM,
             "(SELECT COUNT(*) FROM scrobbles sc \
-             WHERE sc.song_id = s.id AND sc.user_id = $1) DESC, s.artist, s.album",
+              WHERE sc.song_id = a.id AND sc.user_id = $1) DESC, a.artist, a.album",
+        ),
+        "highest" => (
+            "",
+            BY_ALBUM,
+            "user_rating DESC NULLS LAST, average_rating DESC NULLS LAST, a.artist, a.album",
         ),
         _ => return param_err("Invalid list type"),
     };
@@ -717,15 +821,19 @@ async fn album_list(
     params.push(&skip);
     params.push(&limit);
     let sql = format!(
-        "SELECT DISTINCT ON (s.artist, s.album) \
-                s.artist, s.album, s.year, s.genre, \
-                COUNT(*) OVER w AS song_count, \
-                SUM(s.duration_seconds) OVER w AS total_dur, \
-                BOOL_OR(s.has_cover_art) OVER w AS has_cover \
-         FROM songs s JOIN user_songs us ON s.id = us.song_id \
-         WHERE us.user_id = $1 {filter} \
-         WINDOW w AS (PARTITION BY s.artist, s.album) \
-         ORDER BY {order} OFFSET ${offset_parameter} LIMIT ${limit_parameter}"
+        "SELECT a.*, {ratings} FROM ( \
+             SELECT DISTINCT ON (s.artist, s.album) \
+                    s.id, s.artist, s.album, s.year, s.genre, \
+                    COUNT(*) OVER w AS song_count, \
+                    SUM(s.duration_seconds) OVER w AS total_dur, \
+                    BOOL_OR(s.has_cover_art) OVER w AS has_cover \
+             FROM songs s JOIN user_songs us ON s.id = us.song_id \
+             WHERE us.user_id = $1 {filter} \
+             WINDOW w AS (PARTITION BY s.artist, s.album) \
+             ORDER BY {pick} \
+         ) a \
+         ORDER BY {order} OFFSET ${offset_parameter} LIMIT ${limit_parameter}",
+        ratings = album_rating_columns("$1", "a.artist", "a.album"),
     );
     let Ok(rows) = client.query(&sql, &params).await else {
         return db_err_resp();
@@ -771,11 +879,12 @@ pub(crate) async fn get_genres(pool: &State<Pool>, user: SubsonicUser) -> Json<s
 
     let Ok(rows) = client
         .query(
-            "SELECT s.genre, COUNT(*) AS song_count, \
+            "SELECT g.genre, COUNT(*) AS song_count, \
                     COUNT(DISTINCT s.album) AS album_count \
-             FROM songs s JOIN user_songs us ON s.id = us.song_id \
-             WHERE us.user_id = $1 AND s.genre IS NOT NULL AND s.genre != '' \
-             GROUP BY s.genre ORDER BY s.genre",
+             FROM song_genres g JOIN songs s ON s.id = g.song_id \
+             JOIN user_songs us ON s.id = us.song_id \
+             WHERE us.user_id = $1 \
+             GROUP BY g.genre ORDER BY g.genre",
             &[&user.id],
         )
         .await
@@ -863,11 +972,17 @@ mod tests {
             content_type: "audio/mpeg".into(),
             is_dir: false,
             is_video: false,
+            rating: Rating {
+                user_rating: Some(5),
+                average_rating: None,
+            },
         })
         .expect("directory song should serialize");
 
         assert_eq!(song["coverArt"], "cover-id");
         assert_eq!(song["bitRate"], 320);
+        assert_eq!(song["userRating"], 5);
+        assert!(song.get("averageRating").is_none());
         assert!(song.get("cover_art").is_none());
         assert!(song.get("bitrate").is_none());
     }
@@ -0,0 +1,147 @@
+use super::*;
+
+use rocket::serde::Deserialize;
+
+// ── Artist and genre credits ──
+
+/// Roles listed in a song entry's `artists`; featured artists are artists of the song too.
+pub(super) const SONG_ARTIST_ROLES: &str = "('artist', 'featured')";
+
+/// Roles that list an artist in the artist index and under `getArtist`.
+const INDEX_ARTIST_ROLES: &str = "('artist', 'featured', 'albumartist')";
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(crate = "rocket::serde")]
+pub struct CreditArtist {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(crate = "rocket::serde")]
+pub struct CreditGenre {
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(crate = "rocket::serde")]
+pub struct Contributor {
+    pub role: String,
+    pub artist: CreditArtist,
+}
+
+/// OpenSubsonic `artists`, `albumArtists`, `genres` and `contributors` of a song entry.
+#[derive(Debug, Serialize, Clone, Default, PartialEq)]
+#[serde(crate = "rocket::serde")]
+pub struct EntryCredits {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub artists: Option<Vec<CreditArtist>>,
+    #[serde(rename = "albumArtists", skip_serializing_if = "Option::is_none")]
+    pub album_artists: Option<Vec<CreditArtist>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub genres: Option<Vec<CreditGenre>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contributors: Option<Vec<Contributor>>,
+}
+
+impl EntryCredits {
+    /// Read the `credit_*` columns; queries without them give no credits.
+    pub(super) fn from_row(row: &tokio_postgres::Row) -> Self {
+        fn column<T: rocket::serde::DeserializeOwned>(
+            row: &tokio_postgres::Row,
+            name: &str,
+        ) -> Option<T> {
+            let value: serde_json::Value = row.try_get(name).ok()?;
+            serde_json::from_value(value).ok()
+        }
+        Self {
+            artists: column(row, "credit_artists"),
+            album_artists: column(row, "credit_album_artists"),
+            genres: column(row, "credit_genres"),
+            contributors: column(row, "credit_contributors"),
+        }
+    }
+}
+
+fn artist_json_agg(song: &str, roles: &str) -> String {
+    format!(
+        "(SELECT json_agg(json_build_object('id', 'ar:' || a.name, 'name', a.name) \
+                          ORDER BY sa.position) \
+          FROM song_artists sa JOIN artists a ON a.id = sa.artist_id \
+          WHERE sa.song_id = {song} AND sa.role IN {roles})"
+    )
+}
+
+/// Credit columns for the song id expression `song`. Songs without an album artist credit
+/// fall back to their artists, as Subsonic albums do.
+pub(super) fn song_credit_columns(song: &str) -> String {
+    let artists = artist_json_agg(song, SONG_ARTIST_ROLES);
+    let album_artists = artist_json_agg(song, "('albumartist')");
+    format!(
+        "COALESCE({artists}, '[]') AS credit_artists, \
+         COALESCE({album_artists}, {artists}, '[]') AS credit_album_artists, \
+         COALESCE((SELECT json_agg(json_build_object('name', g.genre) ORDER BY g.position) \
+                   FROM song_genres g WHERE g.song_id = {song}), '[]') AS credit_genres, \
+         COALESCE((SELECT json_agg(json_build_object( \
+                       'role', sa.role, \
+                       'artist', json_build_object('id', 'ar:' || a.name, 'name', a.name)) \
+                       ORDER BY sa.role, sa.position) \
+                   FROM song_artists sa JOIN artists a ON a.id = sa.artist_id \
+                   WHERE sa.song_id = {song} AND sa.role <> 'albumartist'), '[]') \
+           AS credit_contributors"
+    )
+}
+
+/// Names of the artists credited on the songs of `user`, as an `artist` column.
+pub(super) fn library_artists(user: &str) -> String {
+    format!(
+        "SELECT DISTINCT a.name AS artist FROM artists a \
+         JOIN song_artists sa ON sa.artist_id = a.id \
+         JOIN user_songs us ON us.song_id = sa.song_id \
+         WHERE us.user_id = {user} AND sa.role IN {INDEX_ARTIST_ROLES}"
+    )
+}
+
+/// Condition that the song `song` credits the artist named `artist`.
+pub(super) fn song_credits_artist(song: &str, artist: &str) -> String {
+    format!(
+        "EXISTS (SELECT 1 FROM song_artists sa JOIN artists a ON a.id = sa.artist_id \
+                 WHERE sa.song_id = {song} AND a.name = {artist} \
+                   AND sa.role IN {INDEX_ARTIST_ROLES})"
+    )
+}
+
+/// Condition that the song `song` is tagged with the genre `genre`.
+pub(super) fn song_has_genre(song: &str, genre: &str) -> String {
+    format!("EXISTS (SELECT 1 FROM song_genres g WHERE g.song_id = {song} AND g.genre = {genre})")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn credits_are_omitted_unless_selected() {
+        let none = serde_json::to_value(EntryCredits::default()).unwrap_or_default();
+        assert_eq!(none, serde_json::json!({}));
+
+        let artist = CreditArtist {
+            id: artist_id("B"),
+            name: "B".into(),
+        };
+        let credits = serde_json::to_value(EntryCredits {
+            artists: Some(vec![artist.clone()]),
+            album_artists: Some(Vec::new()),
+            genres: Some(vec![CreditGenre { name: "Pop".into() }]),
+            contributors: Some(vec![Contributor {
+                role: "featured".into(),
+                artist,
+            }]),
+        })
+        .unwrap_or_default();
+        assert_eq!(credits["artists"][0]["id"], "ar:B");
+        assert_eq!(credits["albumArtists"], serde_json::json!([]));
+        assert_eq!(credits["genres"][0]["name"], "Pop");
+        assert_eq!(credits["contributors"][0]["role"], "featured");
+    }
+}
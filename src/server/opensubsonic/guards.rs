@@ -1,5 +1,11 @@
 use super::*;
 
+use crate::auth::{
+    ACTIVE_USER_SQL, ApiKeyRejection, authenticate_api_key, check_permission, subsonic_token,
+    verify_app_password,
+};
+use crate::shared::AppSecretKey;
+
 // ── Error forwarding ──
 
 type AuthErrorSlot = std::sync::Mutex<Option<serde_json::Value>>;
@@ -21,6 +27,29 @@ fn api_err<T>(request: &Request<'_>, code: i32, msg: &str) -> Outcome<T, ()> {
 pub struct SubsonicUser {
     pub id: Uuid,
     pub username: String,
+    /// Permissions the request is limited to when it authenticated with a scoped API key.
+    pub scopes: Option<Vec<String>>,
+}
+
+impl SubsonicUser {
+    /// Whether the credential used for this request may exercise `permission` at all.
+    pub(crate) fn has_scope(&self, permission: &str) -> bool {
+        self.scopes
+            .as_ref()
+            .is_none_or(|scopes| scopes.iter().any(|scope| scope == permission))
+    }
+
+    /// Role permission check, narrowed to the key's scopes for API-key requests.
+    pub(crate) async fn has_permission(
+        &self,
+        pool: &Pool,
+        permission: &str,
+    ) -> Result<bool, String> {
+        if !self.has_scope(permission) {
+            return Ok(false);
+        }
+        check_permission(pool, self.id, permission).await
+    }
 }
 
 pub(super) fn query_param(request: &Request<'_>, key: &str) -> Option<String> {
@@ -69,46 +98,51 @@ impl<'r> FromRequest<'r> for SubsonicUser {
             Outcome::Success(pool) => pool,
             _ => return api_err(request, 0, "Server configuration error"),
         };
+        let secret = match request.guard::<&State<AppSecretKey>>().await {
+            Outcome::Success(secret) => secret,
+            _ => return api_err(request, 0, "Server configuration error"),
+        };
 
         // 1. apiKey auth
         if let Some(api_key) = query_param(request, "apiKey")
             && !api_key.is_empty()
         {
-            let key_hash = sha256_hex(&api_key);
             let client = match pool.get().await {
                 Ok(client) => client,
                 Err(_) => return api_err(request, 0, "Database error"),
             };
-            return match client
-                .query_opt(
-                    "SELECT u.id, u.username FROM api_keys ak JOIN users u ON ak.user_id=u.id WHERE ak.key_hash=$1",
-                    &[&key_hash],
-                )
-                .await
+            return match authenticate_api_key(
+                &client,
+                &api_key,
+                request.client_ip(),
+                request.method().as_str(),
+                request.uri().path().as_str(),
+            )
+            .await
             {
-                Ok(Some(row)) => {
-                    let _ = client
-                        .execute(
-                            "UPDATE api_keys SET last_used_at=NOW() WHERE key_hash=$1",
-                            &[&key_hash],
-                        )
-                        .await;
-                    Outcome::Success(SubsonicUser {
-                        id: row.get("id"),
-                        username: row.get("username"),
-                    })
+                Ok(owner) => Outcome::Success(SubsonicUser {
+                    id: owner.user_id,
+                    username: owner.username,
+                    scopes: owner.scopes,
+                }),
+                Err(ApiKeyRejection::AccountDisabled) => {
+                    api_err(request, 40, "Account is disabled or has expired")
                 }
-                Ok(None) => api_err(request, 44, "Invalid API key"),
-                Err(_) => api_err(request, 0, "Database error"),
+                Err(ApiKeyRejection::Invalid | ApiKeyRejection::Expired) => {
+                    api_err(request, 44, "Invalid API key")
+                }
+                Err(ApiKeyRejection::IpNotAllowed) => {
+                    api_err(request, 50, "API key is not allowed from this address")
+                }
+                Err(ApiKeyRejection::Database) => api_err(request, 0, "Database error"),
             };
         }
 
         // 2. Check conflicting auth
-        let has_u = query_param(request, "u").is_some();
         let has_p = query_param(request, "p").is_some();
         let has_t = query_param(request, "t").is_some();
         let has_s = query_param(request, "s").is_some();
-        if (has_t || has_s) && (has_u || has_p) {
+        if (has_t || has_s) && has_p {
             return api_err(
                 request,
                 43,
@@ -116,20 +150,52 @@ impl<'r> FromRequest<'r> for SubsonicUser {
             );
         }
 
-        // 3. t+s (not supported with argon2)
-        if has_t || has_s {
-            return api_err(
-                request,
-                41,
-                "Token authentication not supported. Use an API key.",
-            );
-        }
-
-        // 4. u+p
         let username = match query_param(request, "u") {
             Some(username) if !username.is_empty() => username,
             _ => return api_err(request, 10, "Required parameter 'u' is missing"),
         };
+
+        // 3. u+t+s, checked against the user's Subsonic app passwords
+        if has_t || has_s {
+            let (token, salt) = match (query_param(request, "t"), query_param(request, "s")) {
+                (Some(token), Some(salt)) if !token.is_empty() && !salt.is_empty() => (token, salt),
+                (_, None) => return api_err(request, 10, "Required parameter 's' is missing"),
+                _ => return api_err(request, 10, "Required parameter 't' is missing"),
+            };
+            let client = match pool.get().await {
+                Ok(client) => client,
+                Err(_) => return api_err(request, 0, "Database error"),
+            };
+            let row = match client
+                .query_opt(
+                    &format!(
+                        "SELECT u.id, u.username FROM users u WHERE u.username=$1 AND {ACTIVE_USER_SQL}"
+                    ),
+                    &[&username],
+                )
+                .await
+            {
+                Ok(Some(row)) => row,
+                Ok(None) => return api_err(request, 40, "Wrong username or password"),
+                Err(_) => return api_err(request, 0, "Database error"),
+            };
+            let user_id: Uuid = row.get("id");
+            return match verify_app_password(&client, secret, user_id, |password| {
+                subsonic_token(password, &salt).eq_ignore_ascii_case(&token)
+            })
+            .await
+            {
+                Ok(true) => Outcome::Success(SubsonicUser {
+                    id: user_id,
+                    username: row.get("username"),
+                    scopes: None,
+                }),
+                Ok(false) => api_err(request, 40, "Wrong username or password"),
+                Err(_) => api_err(request, 0, "Database error"),
+            };
+        }
+
+        // 4. u+p, with either the account password or an app password
         let password = match query_param(request, "p") {
             Some(password) if !password.is_empty() => password,
             _ => return api_err(request, 10, "Required parameter 'p' is missing"),
@@ -149,7 +215,10 @@ impl<'r> FromRequest<'r> for SubsonicUser {
         };
         let row = match client
             .query_opt(
-                "SELECT id,username,password_hash FROM users WHERE username=$1",
+                &format!(
+                    "SELECT u.id, u.username, u.password_hash FROM users u
+                     WHERE u.username=$1 AND {ACTIVE_USER_SQL}"
+                ),
                 &[&username],
             )
             .await
@@ -167,12 +236,21 @@ impl<'r> FromRequest<'r> for SubsonicUser {
             .verify_password(password.as_bytes(), &parsed)
             .is_err()
         {
-            return api_err(request, 40, "Wrong username or password");
+            match verify_app_password(&client, secret, row.get("id"), |app_password| {
+                app_password == password
+            })
+            .await
+            {
+                Ok(true) => {}
+                Ok(false) => return api_err(request, 40, "Wrong username or password"),
+                Err(_) => return api_err(request, 0, "Database error"),
+            }
         }
 
         Outcome::Success(SubsonicUser {
             id: row.get("id"),
             username: row.get("username"),
+            scopes: None,
         })
     }
 }
@@ -248,6 +326,24 @@ mod tests {
         Client::tracked(rocket).expect("test Rocket should launch")
     }
 
+    #[test]
+    fn scoped_keys_only_exercise_their_scopes() {
+        let user = SubsonicUser {
+            id: Uuid::nil(),
+            username: "test".into(),
+            scopes: Some(vec!["share_music".into()]),
+        };
+        assert!(user.has_scope("share_music"));
+        assert!(!user.has_scope("manage_radio"));
+        assert!(
+            SubsonicUser {
+                scopes: None,
+                ..user
+            }
+            .has_scope("manage_radio")
+        );
+    }
+
     #[test]
     fn authentication_failure_returns_open_subsonic_error_with_http_200() {
         let client = test_client();
@@ -0,0 +1,174 @@
+use super::*;
+
+use crate::music::{LyricLine, SongLyrics, lyrics_text, song_lyrics};
+
+// ── Lyrics ──
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct LyricsResponse {
+    pub lyrics: Lyrics,
+}
+
+#[derive(Debug, Serialize, Default)]
+#[serde(crate = "rocket::serde")]
+pub struct Lyrics {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub artist: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct LyricsListResponse {
+    #[serde(rename = "lyricsList")]
+    pub lyrics_list: LyricsList,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct LyricsList {
+    #[serde(rename = "structuredLyrics")]
+    pub structured_lyrics: Vec<StructuredLyrics>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct StructuredLyrics {
+    #[serde(rename = "displayArtist")]
+    pub display_artist: String,
+    #[serde(rename = "displayTitle")]
+    pub display_title: String,
+    pub lang: String,
+    pub offset: i64,
+    pub synced: bool,
+    pub line: Vec<LyricLine>,
+}
+
+fn structured(artist: &str, title: &str, lyrics: SongLyrics) -> StructuredLyrics {
+    StructuredLyrics {
+        display_artist: artist.to_string(),
+        display_title: title.to_string(),
+        lang: lyrics.lang,
+        offset: 0,
+        synced: lyrics.synced,
+        line: lyrics.lines,
+    }
+}
+
+/// Legacy lookup by artist and title among the user's songs; unsynced lyrics are preferred.
+#[get("/getLyrics")]
+pub(crate) async fn get_lyrics(
+    pool: &State<Pool>,
+    user: SubsonicUser,
+    query: SubsonicQuery,
+) -> Json<serde_json::Value> {
+    let artist = query.first("artist").unwrap_or_default();
+    let title = query.first("title").unwrap_or_default();
+    if artist.is_empty() && title.is_empty() {
+        return ok_resp(LyricsResponse {
+            lyrics: Lyrics::default(),
+        });
+    }
+    let Ok(client) = pool.get().await else {
+        return db_err_resp();
+    };
+    let row = match client
+        .query_opt(
+            "SELECT s.artist, s.title, l.lines FROM songs s \
+             JOIN user_songs us ON s.id = us.song_id \
+             JOIN lyrics l ON l.song_id = s.id \
+             WHERE us.user_id = $1 \
+               AND ($2 = '' OR LOWER(s.artist) = LOWER($2)) \
+               AND ($3 = '' OR LOWER(s.title) = LOWER($3)) \
+             ORDER BY l.synced, s.title, s.id \
+             LIMIT 1",
+            &[&user.id, &artist, &title],
+        )
+        .await
+    {
+        Ok(row) => row,
+        Err(_) => return db_err_resp(),
+    };
+    let lyrics = match row {
+        Some(row) => {
+            let lines: Vec<LyricLine> =
+                serde_json::from_value(row.get("lines")).unwrap_or_default();
+            Lyrics {
+                artist: Some(row.get("artist")),
+                title: Some(row.get("title")),
+                value: Some(lyrics_text(&lines)),
+            }
+        }
+        None => Lyrics::default(),
+    };
+    ok_resp(LyricsResponse { lyrics })
+}
+
+/// OpenSubsonic `songLyrics`: every set of lyrics of a song, with timed lines when synced.
+#[get("/getLyricsBySongId")]
+pub(crate) async fn get_lyrics_by_song_id(
+    pool: &State<Pool>,
+    user: SubsonicUser,
+    query: SubsonicQuery,
+) -> Json<serde_json::Value> {
+    let Some(id) = query.first("id") else {
+        return param_err("Required parameter 'id' is missing");
+    };
+    let Ok(song_id) = Uuid::parse_str(&id) else {
+        return not_found_resp();
+    };
+    let Ok(client) = pool.get().await else {
+        return db_err_resp();
+    };
+    let row = match client
+        .query_opt(
+            "SELECT s.artist, s.title FROM songs s JOIN user_songs us ON s.id = us.song_id \
+             WHERE us.user_id = $1 AND s.id = $2",
+            &[&user.id, &song_id],
+        )
+        .await
+    {
+        Ok(Some(row)) => row,
+        Ok(None) => return not_found_resp(),
+        Err(_) => return db_err_resp(),
+    };
+    let Ok(stored) = song_lyrics(&client, song_id).await else {
+        return db_err_resp();
+    };
+    let artist: String = row.get("artist");
+    let title: String = row.get("title");
+    ok_resp(LyricsListResponse {
+        lyrics_list: LyricsList {
+            structured_lyrics: stored
+                .into_iter()
+                .map(|lyrics| structured(&artist, &title, lyrics))
+                .collect(),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn structured_lyrics_omit_start_of_unsynced_lines() {
+        let lyrics = SongLyrics {
+            synced: false,
+            source: "tag",
+            lang: "und".into(),
+            lines: vec![LyricLine {
+                start: None,
+                value: "Hello".into(),
+            }],
+        };
+        let value = serde_json::to_value(structured("Artist", "Song", lyrics)).unwrap_or_default();
+        assert_eq!(value["displayArtist"], "Artist");
+        assert_eq!(value["synced"], false);
+        assert_eq!(value["line"], serde_json::json!([{"value": "Hello"}]));
+    }
+}
@@ -1,5 +1,9 @@
 use super::*;
-use crate::music::{ArtworkError, read_embedded_artwork};
+use crate::music::{
+    ArtworkError, StreamOptions, TranscodePlan, default_transcoding_profiles, encoder,
+    estimated_length, plan_transcode, read_embedded_artwork, seek_cached_transcode,
+    spawn_transcode, transcode_cache_path, transcoding_profiles,
+};
 
 // ── Phase 5: Media & Search response types ──
 
@@ -112,33 +116,14 @@ async fn binary_cover_song(
         .ok_or_else(|| SubsonicBinaryResponse::error(70, "Cover art not found"))
 }
 
-#[allow(non_snake_case)]
-#[allow(clippy::too_many_arguments)]
-#[get("/stream?<id>&<_maxBitRate>&<_format>&<_timeOffset>&<_size>&<_estimateContentLength>")]
-pub(crate) async fn stream(
-    pool: &State<Pool>,
-    range_hdr: RangeHeader,
-    user: SubsonicUser,
-    id: String,
-    _maxBitRate: Option<i32>,
-    _format: Option<String>,
-    _timeOffset: Option<i32>,
-    _size: Option<String>,
-    _estimateContentLength: Option<bool>,
+/// Serve `path` whole or, if the client asked for one, a byte range of it.
+pub(super) async fn serve_file(
+    path: &Path,
+    content_type: String,
+    file_size: u64,
+    range_hdr: &RangeHeader,
 ) -> SubsonicBinaryResponse {
-    let row = match binary_user_song(pool, user.id, &id).await {
-        Ok(row) => row,
-        Err(response) => return response,
-    };
-
-    let file_path: String = row.get("file_path");
-    let format_str: String = row.get("format");
-    let size_bytes: i64 = row.get("size_bytes");
-    let file_size = size_bytes as u64;
-    let content_type = format_to_mime(&format_str).to_string();
-
-    let full_path = Path::new(MUSIC_ROOT).join(&file_path);
-    let mut file = match File::open(&full_path).await {
+    let mut file = match File::open(path).await {
         Ok(f) => f,
         Err(_) => return SubsonicBinaryResponse::error(70, "File not found on disk"),
     };
@@ -173,11 +158,122 @@ pub(crate) async fn stream(
     SubsonicBinaryResponse::Stream(SubsonicStreamResponse {
         reader: Box::new(limited),
         content_type,
-        content_length,
+        content_length: Some(content_length),
         status,
         extra_headers,
     })
 }
+
+async fn stream_plan(
+    pool: &Pool,
+    user_id: Uuid,
+    row: &tokio_postgres::Row,
+    options: &StreamOptions,
+) -> Result<Option<TranscodePlan>, SubsonicBinaryResponse> {
+    let client = pool
+        .get()
+        .await
+        .map_err(|_| SubsonicBinaryResponse::error(0, "Database error"))?;
+    let profiles = transcoding_profiles(&client)
+        .await
+        .map_err(|_| SubsonicBinaryResponse::error(0, "Database error"))?;
+    let defaults = default_transcoding_profiles(&client, user_id)
+        .await
+        .map_err(|_| SubsonicBinaryResponse::error(0, "Database error"))?;
+    let format: Option<String> = row.get("format");
+    Ok(plan_transcode(
+        format.as_deref().unwrap_or_default(),
+        row.get::<_, Option<i16>>("bitrate_kbps").map(i32::from),
+        options,
+        &defaults,
+        &profiles,
+    ))
+}
+
+#[allow(non_snake_case)]
+#[allow(clippy::too_many_arguments)]
+#[get("/stream?<id>&<maxBitRate>&<format>&<timeOffset>&<_size>&<estimateContentLength>")]
+pub(crate) async fn stream(
+    pool: &State<Pool>,
+    range_hdr: RangeHeader,
+    user: SubsonicUser,
+    id: String,
+    maxBitRate: Option<i32>,
+    format: Option<String>,
+    timeOffset: Option<i32>,
+    _size: Option<String>,
+    estimateContentLength: Option<bool>,
+) -> SubsonicBinaryResponse {
+    let row = match binary_user_song(pool, user.id, &id).await {
+        Ok(row) => row,
+        Err(response) => return response,
+    };
+
+    let file_path: String = row.get("file_path");
+    let format_str: String = row.get("format");
+    let size_bytes: i64 = row.get("size_bytes");
+    let full_path = Path::new(MUSIC_ROOT).join(&file_path);
+
+    let options = StreamOptions {
+        max_bitrate: maxBitRate,
+        format,
+        time_offset: timeOffset,
+    };
+    let plan = match stream_plan(pool, user.id, &row, &options).await {
+        Ok(Some(plan)) => plan,
+        Ok(None) => {
+            let content_type = format_to_mime(&format_str).to_string();
+            return serve_file(&full_path, content_type, size_bytes as u64, &range_hdr).await;
+        }
+        Err(response) => return response,
+    };
+    let Some(encoder) = encoder(&plan.target_format) else {
+        return SubsonicBinaryResponse::error(0, "Unsupported transcoding format");
+    };
+
+    // A finished transcode of this exact file version is served like the original.
+    let updated_at: Option<chrono::DateTime<Utc>> = row.get("updated_at");
+    let song_version = format!(
+        "{size_bytes}:{}",
+        updated_at
+            .map(|at| at.timestamp_micros())
+            .unwrap_or_default()
+    );
+    let cache_path = transcode_cache_path(row.get("id"), &song_version, &plan);
+    let spawned = if let Ok(metadata) = tokio::fs::metadata(&cache_path).await {
+        if plan.offset_seconds == 0 {
+            let content_type = encoder.content_type.to_string();
+            return serve_file(&cache_path, content_type, metadata.len(), &range_hdr).await;
+        }
+        seek_cached_transcode(&cache_path, &plan)
+    } else {
+        if !full_path.is_file() {
+            return SubsonicBinaryResponse::error(70, "File not found on disk");
+        }
+        spawn_transcode(&full_path, &plan, cache_path)
+    };
+    let reader = match spawned {
+        Ok(reader) => reader,
+        // The original would ignore the requested format, bitrate, and offset.
+        Err(error) => {
+            eprintln!("Failed to start ffmpeg: {error}");
+            return SubsonicBinaryResponse::error(0, "Transcoding is unavailable, try again later");
+        }
+    };
+    let content_length = if estimateContentLength.unwrap_or(false) {
+        estimated_length(row.get("duration_seconds"), &plan)
+    } else {
+        None
+    };
+
+    SubsonicBinaryResponse::Stream(SubsonicStreamResponse {
+        reader: Box::new(reader),
+        content_type: encoder.content_type.to_string(),
+        content_length,
+        status: Status::Ok,
+        extra_headers: vec![("Accept-Ranges".into(), "none".into())],
+    })
+}
 #[get("/download?<id>")]
 pub(crate) async fn subsonic_download(
     pool: &State<Pool>,
@@ -211,7 +307,7 @@ pub(crate) async fn subsonic_download(
     SubsonicBinaryResponse::Stream(SubsonicStreamResponse {
         reader: Box::new(file),
         content_type,
-        content_length: size_bytes as u64,
+        content_length: Some(size_bytes as u64),
         status: Status::Ok,
         extra_headers: vec![(
             "Content-Disposition".into(),
@@ -258,7 +354,7 @@ pub(crate) async fn get_cover_art(
     SubsonicBinaryResponse::Stream(SubsonicStreamResponse {
         reader: Box::new(std::io::Cursor::new(artwork.bytes)),
         content_type: artwork.content_type,
-        content_length,
+        content_length: Some(content_length),
         status: Status::Ok,
         extra_headers: vec![(
             "Cache-Control".into(),
@@ -274,7 +370,8 @@ pub(crate) async fn get_cover_art(
 pub(crate) struct SubsonicStreamResponse {
     pub reader: Box<dyn tokio::io::AsyncRead + Send + Unpin>,
     pub content_type: String,
-    pub content_length: u64,
+    /// Omitted while transcoding, unless the client asked for an estimate.
+    pub content_length: Option<u64>,
     pub status: Status,
     pub extra_headers: Vec<(String, String)>,
 }
@@ -284,11 +381,10 @@ impl<'r> rocket::response::Responder<'r, 'static> for SubsonicStreamResponse {
         let mut resp = rocket::Response::build();
         resp.status(self.status)
             .header(Header::new("Accept-Ranges", "bytes"))
-            .header(Header::new("Content-Type", self.content_type))
-            .header(Header::new(
-                "Content-Length",
-                self.content_length.to_string(),
-            ));
+            .header(Header::new("Content-Type", self.content_type));
+        if let Some(content_length) = self.content_length {
+            resp.header(Header::new("Content-Length", content_length.to_string()));
+        }
         for (k, v) in &self.extra_headers {
             resp.header(Header::new(k.clone(), v.clone()));
         }
@@ -362,10 +458,12 @@ async fn search(
 
     let artists = client
         .query(
-            "SELECT DISTINCT s.artist FROM songs s \
-             JOIN user_songs us ON s.id = us.song_id \
-             WHERE us.user_id = $1 AND s.artist ILIKE $2 \
-             ORDER BY s.artist OFFSET $3 LIMIT $4",
+            &format!(
+                "SELECT la.artist, {} FROM ({}) la WHERE la.artist ILIKE $2 \
+                 ORDER BY la.artist OFFSET $3 LIMIT $4",
+                artist_rating_columns("$1", "la.artist"),
+                library_artists("$1")
+            ),
             &[&user.id, &pattern, &artist_offset, &artist_count],
         )
         .await
@@ -377,18 +475,22 @@ async fn search(
                     ArtistRef {
                         id: artist_id(&name),
                         name,
+                        rating: Rating::from_row(row),
                     }
                 })
                 .collect()
         });
     let albums = client
         .query(
-            "SELECT DISTINCT s.artist, s.album, s.year, s.genre, \
-                    BOOL_OR(s.has_cover_art) AS has_cover \
-             FROM songs s JOIN user_songs us ON s.id = us.song_id \
-             WHERE us.user_id = $1 AND s.album ILIKE $2 \
-             GROUP BY s.artist, s.album, s.year, s.genre \
-             ORDER BY s.artist, s.album OFFSET $3 LIMIT $4",
+            &format!(
+                "SELECT DISTINCT s.artist, s.album, s.year, s.genre, \
+                        BOOL_OR(s.has_cover_art) AS has_cover, {} \
+                 FROM songs s JOIN user_songs us ON s.id = us.song_id \
+                 WHERE us.user_id = $1 AND s.album ILIKE $2 \
+                 GROUP BY s.artist, s.album, s.year, s.genre \
+                 ORDER BY s.artist, s.album OFFSET $3 LIMIT $4",
+                album_rating_columns("$1", "s.artist", "s.album")
+            ),
             &[&user.id, &pattern, &album_offset, &album_count],
         )
         .await
@@ -404,10 +506,14 @@ async fn search(
         });
     let songs = client
         .query(
-            "SELECT s.* FROM songs s JOIN user_songs us ON s.id = us.song_id \
-             WHERE us.user_id = $1 \
-               AND (s.title ILIKE $2 OR s.artist ILIKE $2 OR s.album ILIKE $2) \
-             ORDER BY s.title OFFSET $3 LIMIT $4",
+            &format!(
+                "SELECT s.*, {}, {} FROM songs s JOIN user_songs us ON s.id = us.song_id \
+                 WHERE us.user_id = $1 \
+                   AND (s.title ILIKE $2 OR s.artist ILIKE $2 OR s.album ILIKE $2) \
+                 ORDER BY s.title OFFSET $3 LIMIT $4",
+                song_rating_columns("$1", "s.id"),
+                song_credit_columns("s.id")
+            ),
             &[&user.id, &pattern, &song_offset, &song_count],
         )
         .await
@@ -462,12 +568,17 @@ pub(crate) async fn get_random_songs(
     let count = size.unwrap_or(10).min(500) as i64;
     let Ok(rows) = client
         .query(
-            "SELECT s.* FROM songs s JOIN user_songs us ON s.id = us.song_id \
-             WHERE us.user_id = $1 \
-               AND ($2::TEXT IS NULL OR s.genre = $2) \
-               AND ($3::INTEGER IS NULL OR s.year >= $3) \
-               AND ($4::INTEGER IS NULL OR s.year <= $4) \
-             ORDER BY RANDOM() LIMIT $5",
+            &format!(
+                "SELECT s.*, {}, {} FROM songs s JOIN user_songs us ON s.id = us.song_id \
+                 WHERE us.user_id = $1 \
+                   AND ($2::TEXT IS NULL OR {}) \
+                   AND ($3::INTEGER IS NULL OR s.year >= $3) \
+                   AND ($4::INTEGER IS NULL OR s.year <= $4) \
+                 ORDER BY RANDOM() LIMIT $5",
+                song_rating_columns("$1", "s.id"),
+                song_credit_columns("s.id"),
+                song_has_genre("s.id", "$2")
+            ),
             &[&user.id, &genre, &fromYear, &toYear, &count],
         )
         .await
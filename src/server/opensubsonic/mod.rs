@@ -1,5 +1,5 @@
 // Re-export shared infrastructure for submodules.
-pub(crate) use crate::shared::{MUSIC_ROOT, sha256_hex, url_decode};
+pub(crate) use crate::shared::{MUSIC_ROOT, url_decode};
 pub(crate) use argon2::PasswordVerifier;
 pub(crate) use chrono::Utc;
 pub(crate) use deadpool_postgres::Pool;
@@ -19,17 +19,24 @@ pub(crate) use uuid::Uuid;
 // Submodules
 pub(crate) mod bookmarks;
 pub(crate) mod browse;
+pub(crate) mod credits;
 pub(crate) mod envelope;
 pub(crate) mod guards;
+pub(crate) mod lyrics;
 pub(crate) mod media;
+pub(crate) mod play_queue;
 pub(crate) mod playlists;
+pub(crate) mod radio;
+pub(crate) mod ratings;
 pub(crate) mod scrobble_api;
 pub(crate) mod shared;
+pub(crate) mod shares;
 pub(crate) mod starred;
 pub(crate) mod system;
 
 // Re-exports for parent (main.rs)
 pub(crate) use {
-    bookmarks::*, browse::*, envelope::*, guards::*, media::*, playlists::*, scrobble_api::*,
-    shared::*, starred::*, system::*,
+    bookmarks::*, browse::*, credits::*, envelope::*, guards::*, lyrics::*, media::*,
+    play_queue::*, playlists::*, radio::*, ratings::*, scrobble_api::*, shared::*, shares::*,
+    starred::*, system::*,
 };
@@ -0,0 +1,372 @@
+use super::*;
+
+use chrono::DateTime;
+
+// ── Play queue ──
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct PlayQueueResponse {
+    #[serde(rename = "playQueue")]
+    pub play_queue: PlayQueue,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct PlayQueue {
+    pub entry: Vec<SongEntry>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<i64>,
+    pub username: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub changed: Option<String>,
+    #[serde(rename = "changedBy", skip_serializing_if = "Option::is_none")]
+    pub changed_by: Option<String>,
+}
+
+/// OpenSubsonic `indexBasedQueue`: the current item is an index, so repeated songs are unambiguous.
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct PlayQueueByIndexResponse {
+    #[serde(rename = "playQueueByIndex")]
+    pub play_queue: PlayQueueByIndex,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct PlayQueueByIndex {
+    pub entry: Vec<SongEntry>,
+    #[serde(rename = "currentIndex", skip_serializing_if = "Option::is_none")]
+    pub current_index: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<i64>,
+    pub username: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub changed: Option<String>,
+    #[serde(rename = "changedBy", skip_serializing_if = "Option::is_none")]
+    pub changed_by: Option<String>,
+}
+
+/// A saved queue as the user can still play it.
+struct SavedQueue {
+    songs: Vec<tokio_postgres::Row>,
+    current_index: Option<usize>,
+    position_ms: i64,
+    changed_at: DateTime<Utc>,
+    changed_by: Option<String>,
+}
+
+/// Index of the current item among the entries still visible, and the position within it.
+///
+/// Entries of songs since deleted or removed from the library are skipped; if the
+/// current one is among them, the next remaining entry becomes current from its start.
+fn current_entry(
+    positions: &[i32],
+    current: Option<i32>,
+    position_ms: i64,
+) -> (Option<usize>, i64) {
+    let Some(current) = current else {
+        return (None, position_ms);
+    };
+    match positions.iter().position(|position| *position >= current) {
+        Some(index) if positions[index] == current => (Some(index), position_ms),
+        Some(index) => (Some(index), 0),
+        None => (None, 0),
+    }
+}
+
+async fn load_queue(
+    client: &deadpool_postgres::Object,
+    user_id: Uuid,
+) -> Result<Option<SavedQueue>, tokio_postgres::Error> {
+    let Some(queue) = client
+        .query_opt(
+            "SELECT current_position, position_ms, changed_by, changed_at \
+             FROM play_queues WHERE user_id = $1",
+            &[&user_id],
+        )
+        .await?
+    else {
+        return Ok(None);
+    };
+    let songs = client
+        .query(
+            &format!(
+                "SELECT s.*, e.position AS queue_position, {}, {} FROM play_queue_entries e \
+                 JOIN songs s ON s.id = e.song_id \
+                 JOIN user_songs us ON us.song_id = e.song_id AND us.user_id = e.user_id \
+                 WHERE e.user_id = $1 ORDER BY e.position",
+                song_rating_columns("$1", "s.id"),
+                song_credit_columns("s.id")
+            ),
+            &[&user_id],
+        )
+        .await?;
+    let positions: Vec<i32> = songs.iter().map(|row| row.get("queue_position")).collect();
+    let (current_index, position_ms) = current_entry(
+        &positions,
+        queue.get("current_position"),
+        queue.get("position_ms"),
+    );
+    Ok(Some(SavedQueue {
+        songs,
+        current_index,
+        position_ms,
+        changed_at: queue.get("changed_at"),
+        changed_by: queue.get("changed_by"),
+    }))
+}
+
+/// Replace the user's queue; `current` indexes into `songs`. An empty queue clears it.
+async fn store_queue(
+    client: &mut deadpool_postgres::Object,
+    user_id: Uuid,
+    songs: &[Uuid],
+    current: Option<usize>,
+    position_ms: i64,
+    changed_by: Option<String>,
+) -> Result<(), tokio_postgres::Error> {
+    let transaction = client.transaction().await?;
+    transaction
+        .execute("DELETE FROM play_queues WHERE user_id = $1", &[&user_id])
+        .await?;
+    if !songs.is_empty() {
+        transaction
+            .execute(
+                "INSERT INTO play_queues (user_id, current_position, position_ms, changed_by) \
+                 VALUES ($1, $2, $3, $4)",
+                &[
+                    &user_id,
+                    &current.map(|index| index as i32),
+                    &position_ms,
+                    &changed_by,
+                ],
+            )
+            .await?;
+        let positions: Vec<i32> = (0..songs.len() as i32).collect();
+        transaction
+            .execute(
+                "INSERT INTO play_queue_entries (user_id, position, song_id) \
+                 SELECT $1, entry.position, entry.song_id \
+                 FROM UNNEST($2::INTEGER[], $3::UUID[]) AS entry(position, song_id)",
+                &[&user_id, &positions, &songs],
+            )
+            .await?;
+    }
+    transaction.commit().await
+}
+
+/// Songs from the repeated `id` parameters, in order; every one must be in the personal library.
+async fn queued_songs(
+    client: &deadpool_postgres::Object,
+    user_id: Uuid,
+    query: &SubsonicQuery,
+) -> Result<Vec<Uuid>, Json<serde_json::Value>> {
+    let mut songs = Vec::new();
+    for id in query.all("id") {
+        let Ok(song) = Uuid::parse_str(&id) else {
+            return Err(not_found_resp());
+        };
+        songs.push(song);
+    }
+    let distinct: HashSet<Uuid> = songs.iter().copied().collect();
+    let present = personal_song_ids(client, user_id, &songs)
+        .await
+        .map_err(|_| db_err_resp())?;
+    if present.len() != distinct.len() {
+        return Err(not_found_resp());
+    }
+    Ok(songs)
+}
+
+fn position_param(query: &SubsonicQuery) -> Result<i64, Json<serde_json::Value>> {
+    match query.first("position") {
+        None => Ok(0),
+        Some(value) => value
+            .parse::<i64>()
+            .ok()
+            .filter(|ms| *ms >= 0)
+            .ok_or_else(|| {
+                param_err("Parameter 'position' must be a non-negative number of milliseconds")
+            }),
+    }
+}
+
+async fn save_queue(
+    pool: &Pool,
+    user: &SubsonicUser,
+    query: &SubsonicQuery,
+    current: impl FnOnce(&[Uuid]) -> Result<Option<usize>, Json<serde_json::Value>>,
+) -> Json<serde_json::Value> {
+    let position_ms = match position_param(query) {
+        Ok(position_ms) => position_ms,
+        Err(response) => return response,
+    };
+    let Ok(mut client) = pool.get().await else {
+        return db_err_resp();
+    };
+    let songs = match queued_songs(&client, user.id, query).await {
+        Ok(songs) => songs,
+        Err(response) => return response,
+    };
+    let current = match current(&songs) {
+        Ok(current) => current,
+        Err(response) => return response,
+    };
+    match store_queue(
+        &mut client,
+        user.id,
+        &songs,
+        current,
+        position_ms,
+        query.first("c"),
+    )
+    .await
+    {
+        Ok(()) => ok_empty_resp(),
+        Err(_) => db_err_resp(),
+    }
+}
+
+#[get("/getPlayQueue")]
+pub(crate) async fn get_play_queue(
+    pool: &State<Pool>,
+    user: SubsonicUser,
+) -> Json<serde_json::Value> {
+    let Ok(client) = pool.get().await else {
+        return db_err_resp();
+    };
+    let Ok(queue) = load_queue(&client, user.id).await else {
+        return db_err_resp();
+    };
+    let play_queue = match queue {
+        Some(queue) => PlayQueue {
+            current: queue
+                .current_index
+                .map(|index| queue.songs[index].get::<_, Uuid>("id").to_string()),
+            position: queue.current_index.map(|_| queue.position_ms),
+            entry: queue.songs.iter().map(row_to_song_entry).collect(),
+            username: user.username,
+            changed: Some(queue.changed_at.to_rfc3339()),
+            changed_by: queue.changed_by,
+        },
+        None => PlayQueue {
+            entry: Vec::new(),
+            current: None,
+            position: None,
+            username: user.username,
+            changed: None,
+            changed_by: None,
+        },
+    };
+    ok_resp(PlayQueueResponse { play_queue })
+}
+
+#[get("/savePlayQueue")]
+pub(crate) async fn save_play_queue(
+    pool: &State<Pool>,
+    user: SubsonicUser,
+    query: SubsonicQuery,
+) -> Json<serde_json::Value> {
+    let current_id = query.first("current");
+    save_queue(pool, &user, &query, |songs| {
+        let Some(current_id) = current_id else {
+            return Ok(None);
+        };
+        Uuid::parse_str(&current_id)
+            .ok()
+            .and_then(|current| songs.iter().position(|song| *song == current))
+            .map(Some)
+            .ok_or_else(|| param_err("Parameter 'current' must be one of the queued songs"))
+    })
+    .await
+}
+
+#[get("/getPlayQueueByIndex")]
+pub(crate) async fn get_play_queue_by_index(
+    pool: &State<Pool>,
+    user: SubsonicUser,
+) -> Json<serde_json::Value> {
+    let Ok(client) = pool.get().await else {
+        return db_err_resp();
+    };
+    let Ok(queue) = load_queue(&client, user.id).await else {
+        return db_err_resp();
+    };
+    let play_queue = match queue {
+        Some(queue) => PlayQueueByIndex {
+            current_index: queue.current_index,
+            position: queue.current_index.map(|_| queue.position_ms),
+            entry: queue.songs.iter().map(row_to_song_entry).collect(),
+            username: user.username,
+            changed: Some(queue.changed_at.to_rfc3339()),
+            changed_by: queue.changed_by,
+        },
+        None => PlayQueueByIndex {
+            entry: Vec::new(),
+            current_index: None,
+            position: None,
+            username: user.username,
+            changed: None,
+            changed_by: None,
+        },
+    };
+    ok_resp(PlayQueueByIndexResponse { play_queue })
+}
+
+#[get("/savePlayQueueByIndex")]
+pub(crate) async fn save_play_queue_by_index(
+    pool: &State<Pool>,
+    user: SubsonicUser,
+    query: SubsonicQuery,
+) -> Json<serde_json::Value> {
+    let current_index = query.first("currentIndex");
+    save_queue(pool, &user, &query, |songs| {
+        let Some(current_index) = current_index else {
+            return Ok(None);
+        };
+        current_index
+            .parse::<usize>()
+            .ok()
+            .filter(|index| *index < songs.len())
+            .map(Some)
+            .ok_or_else(|| param_err("Parameter 'currentIndex' is out of range"))
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn current_entry_skips_to_the_next_remaining_song() {
+        assert_eq!(current_entry(&[0, 1, 2], Some(1), 5_000), (Some(1), 5_000));
+        // Entry 1 was removed: entry 2 (now index 1) starts from the beginning.
+        assert_eq!(current_entry(&[0, 2, 3], Some(1), 5_000), (Some(1), 0));
+        assert_eq!(current_entry(&[0], Some(4), 5_000), (None, 0));
+        assert_eq!(current_entry(&[0, 1], None, 0), (None, 0));
+    }
+
+    #[test]
+    fn index_based_queue_uses_versioned_field_names() {
+        let response = ok_resp(PlayQueueByIndexResponse {
+            play_queue: PlayQueueByIndex {
+                entry: Vec::new(),
+                current_index: Some(2),
+                position: Some(1_000),
+                username: "test".into(),
+                changed: None,
+                changed_by: Some("phone".into()),
+            },
+        })
+        .into_inner();
+        let queue = &response["subsonic-response"]["playQueueByIndex"];
+
+        assert_eq!(queue["currentIndex"], 2);
+        assert_eq!(queue["changedBy"], "phone");
+        assert!(queue.get("changed").is_none());
+    }
+}
@@ -145,9 +145,13 @@ pub(crate) async fn get_playlist(
 
     let song_rows = match client
         .query(
-            "SELECT s.* FROM playlist_songs ps JOIN songs s ON ps.song_id = s.id \
-             WHERE ps.playlist_id = $1 ORDER BY ps.position",
-            &[&pid],
+            &format!(
+                "SELECT s.*, {}, {} FROM playlist_songs ps JOIN songs s ON ps.song_id = s.id \
+                 WHERE ps.playlist_id = $1 ORDER BY ps.position",
+                song_rating_columns("$2", "s.id"),
+                song_credit_columns("s.id")
+            ),
+            &[&pid, &user.id],
         )
         .await
     {
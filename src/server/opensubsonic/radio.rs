@@ -0,0 +1,207 @@
+use super::*;
+
+use crate::audit::{AuditActor, record_audit};
+use crate::music::{
+    insert_radio_station, radio_created_event, radio_deleted_event, radio_stations,
+    radio_updated_event, remove_radio_station, replace_radio_station, validate_radio_station,
+};
+
+// ── Internet radio ──
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct InternetRadioStationsResponse {
+    #[serde(rename = "internetRadioStations")]
+    pub internet_radio_stations: InternetRadioStationList,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct InternetRadioStationList {
+    #[serde(rename = "internetRadioStation")]
+    pub internet_radio_station: Vec<InternetRadioStation>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct InternetRadioStation {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "streamUrl")]
+    pub stream_url: String,
+    #[serde(rename = "homePageUrl", skip_serializing_if = "Option::is_none")]
+    pub home_page_url: Option<String>,
+}
+
+/// Subsonic error 50 unless the user may manage stations.
+async fn require_radio_admin(
+    pool: &Pool,
+    user: &SubsonicUser,
+) -> Result<(), Json<serde_json::Value>> {
+    match user.has_permission(pool, "manage_radio").await {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(unauthorized_resp()),
+        Err(_) => Err(db_err_resp()),
+    }
+}
+
+fn station_param(query: &SubsonicQuery) -> Result<Uuid, Json<serde_json::Value>> {
+    let id = query
+        .first("id")
+        .ok_or_else(|| param_err("Required parameter 'id' is missing"))?;
+    Uuid::parse_str(&id).map_err(|_| not_found_resp())
+}
+
+fn station_fields(
+    query: &SubsonicQuery,
+) -> Result<crate::music::RadioStationInput, Json<serde_json::Value>> {
+    let stream_url = query
+        .first("streamUrl")
+        .ok_or_else(|| param_err("Required parameter 'streamUrl' is missing"))?;
+    let name = query
+        .first("name")
+        .ok_or_else(|| param_err("Required parameter 'name' is missing"))?;
+    validate_radio_station(&name, &stream_url, query.first("homepageUrl").as_deref())
+        .map_err(|message| param_err(&message))
+}
+
+#[get("/getInternetRadioStations")]
+pub(crate) async fn get_internet_radio_stations(
+    pool: &State<Pool>,
+    _user: SubsonicUser,
+) -> Json<serde_json::Value> {
+    let Ok(client) = pool.get().await else {
+        return db_err_resp();
+    };
+    let Ok(stations) = radio_stations(&client).await else {
+        return db_err_resp();
+    };
+    ok_resp(InternetRadioStationsResponse {
+        internet_radio_stations: InternetRadioStationList {
+            internet_radio_station: stations
+                .into_iter()
+                .map(|station| InternetRadioStation {
+                    id: station.id.to_string(),
+                    name: station.name,
+                    stream_url: station.stream_url,
+                    home_page_url: station.homepage_url,
+                })
+                .collect(),
+        },
+    })
+}
+
+#[get("/createInternetRadioStation")]
+pub(crate) async fn create_internet_radio_station(
+    pool: &State<Pool>,
+    user: SubsonicUser,
+    query: SubsonicQuery,
+) -> Json<serde_json::Value> {
+    if let Err(response) = require_radio_admin(pool, &user).await {
+        return response;
+    }
+    let input = match station_fields(&query) {
+        Ok(input) => input,
+        Err(response) => return response,
+    };
+    let Ok(client) = pool.get().await else {
+        return db_err_resp();
+    };
+    let Ok(station) = insert_radio_station(&client, &input).await else {
+        return db_err_resp();
+    };
+    record_audit(
+        pool,
+        AuditActor::user(user.id, &user.username),
+        radio_created_event(&station),
+    )
+    .await;
+    ok_empty_resp()
+}
+
+#[get("/updateInternetRadioStation")]
+pub(crate) async fn update_internet_radio_station(
+    pool: &State<Pool>,
+    user: SubsonicUser,
+    query: SubsonicQuery,
+) -> Json<serde_json::Value> {
+    if let Err(response) = require_radio_admin(pool, &user).await {
+        return response;
+    }
+    let (id, input) = match station_param(&query).and_then(|id| Ok((id, station_fields(&query)?))) {
+        Ok(parsed) => parsed,
+        Err(response) => return response,
+    };
+    let Ok(client) = pool.get().await else {
+        return db_err_resp();
+    };
+    match replace_radio_station(&client, id, &input).await {
+        Ok(Some((before, after))) => {
+            record_audit(
+                pool,
+                AuditActor::user(user.id, &user.username),
+                radio_updated_event(&before, &after),
+            )
+            .await;
+            ok_empty_resp()
+        }
+        Ok(None) => not_found_resp(),
+        Err(_) => db_err_resp(),
+    }
+}
+
+#[get("/deleteInternetRadioStation")]
+pub(crate) async fn delete_internet_radio_station(
+    pool: &State<Pool>,
+    user: SubsonicUser,
+    query: SubsonicQuery,
+) -> Json<serde_json::Value> {
+    if let Err(response) = require_radio_admin(pool, &user).await {
+        return response;
+    }
+    let id = match station_param(&query) {
+        Ok(id) => id,
+        Err(response) => return response,
+    };
+    let Ok(client) = pool.get().await else {
+        return db_err_resp();
+    };
+    match remove_radio_station(&client, id).await {
+        Ok(Some(station)) => {
+            record_audit(
+                pool,
+                AuditActor::user(user.id, &user.username),
+                radio_deleted_event(&station),
+            )
+            .await;
+            ok_empty_resp()
+        }
+        Ok(None) => not_found_resp(),
+        Err(_) => db_err_resp(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stations_use_subsonic_field_names() {
+        let response = ok_resp(InternetRadioStationsResponse {
+            internet_radio_stations: InternetRadioStationList {
+                internet_radio_station: vec![InternetRadioStation {
+                    id: Uuid::nil().to_string(),
+                    name: "Jazz FM".into(),
+                    stream_url: "https://stream.example/jazz".into(),
+                    home_page_url: None,
+                }],
+            },
+        })
+        .into_inner();
+        let station =
+            &response["subsonic-response"]["internetRadioStations"]["internetRadioStation"][0];
+
+        assert_eq!(station["streamUrl"], "https://stream.example/jazz");
+        assert!(station.get("homePageUrl").is_none());
+    }
+}
@@ -0,0 +1,254 @@
+use super::*;
+
+// ── Ratings ──
+
+/// `userRating` and `averageRating` of a song, album or artist entry.
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(crate = "rocket::serde")]
+pub struct Rating {
+    #[serde(rename = "userRating", skip_serializing_if = "Option::is_none")]
+    pub user_rating: Option<i16>,
+    #[serde(rename = "averageRating", skip_serializing_if = "Option::is_none")]
+    pub average_rating: Option<f64>,
+}
+
+impl Rating {
+    /// Read the `user_rating` / `average_rating` columns; queries without them give no rating.
+    pub(super) fn from_row(row: &tokio_postgres::Row) -> Self {
+        Self {
+            user_rating: row.try_get("user_rating").ok().flatten(),
+            average_rating: row.try_get("average_rating").ok().flatten(),
+        }
+    }
+}
+
+// The column helpers take SQL expressions: `user` is the rating user's id (usually `$1`),
+// the others the rated song id, artist name or album name of the current row.
+
+pub(super) fn song_rating_columns(user: &str, song: &str) -> String {
+    format!(
+        "(SELECT r.rating FROM song_ratings r \
+          WHERE r.song_id = {song} AND r.user_id = {user}) AS user_rating, \
+         (SELECT AVG(r.rating)::FLOAT8 FROM song_ratings r \
+          WHERE r.song_id = {song}) AS average_rating"
+    )
+}
+
+pub(super) fn album_rating_columns(user: &str, artist: &str, album: &str) -> String {
+    format!(
+        "(SELECT r.rating FROM album_ratings r \
+          WHERE r.artist_name = {artist} AND r.album_name = {album} \
+            AND r.user_id = {user}) AS user_rating, \
+         (SELECT AVG(r.rating)::FLOAT8 FROM album_ratings r \
+          WHERE r.artist_name = {artist} AND r.album_name = {album}) AS average_rating"
+    )
+}
+
+pub(super) fn artist_rating_columns(user: &str, artist: &str) -> String {
+    format!(
+        "(SELECT r.rating FROM artist_ratings r \
+          WHERE r.artist_name = {artist} AND r.user_id = {user}) AS user_rating, \
+         (SELECT AVG(r.rating)::FLOAT8 FROM artist_ratings r \
+          WHERE r.artist_name = {artist}) AS average_rating"
+    )
+}
+
+/// Rating of one album, for responses that do not build it from a row.
+pub(super) async fn album_rating(
+    client: &deadpool_postgres::Object,
+    user_id: Uuid,
+    artist: &str,
+    album: &str,
+) -> Result<Rating, tokio_postgres::Error> {
+    let row = client
+        .query_one(
+            &format!("SELECT {}", album_rating_columns("$1", "$2", "$3")),
+            &[&user_id, &artist, &album],
+        )
+        .await?;
+    Ok(Rating::from_row(&row))
+}
+
+/// Rating of one artist, for responses that do not build it from a row.
+pub(super) async fn artist_rating(
+    client: &deadpool_postgres::Object,
+    user_id: Uuid,
+    artist: &str,
+) -> Result<Rating, tokio_postgres::Error> {
+    let row = client
+        .query_one(
+            &format!("SELECT {}", artist_rating_columns("$1", "$2")),
+            &[&user_id, &artist],
+        )
+        .await?;
+    Ok(Rating::from_row(&row))
+}
+
+/// `rating` is 1 to 5, or 0 to remove the rating.
+fn rating_param(query: &SubsonicQuery) -> Result<Option<i16>, Json<serde_json::Value>> {
+    let value = query
+        .first("rating")
+        .ok_or_else(|| param_err("Required parameter 'rating' is missing"))?;
+    match value.trim().parse::<i16>() {
+        Ok(0) => Ok(None),
+        Ok(rating @ 1..=5) => Ok(Some(rating)),
+        _ => Err(param_err("Parameter 'rating' must be between 0 and 5")),
+    }
+}
+
+/// Whether the user has any song of the album `artist`/`album`, or without an album any
+/// song crediting `artist`, as the artist pages list them.
+async fn in_library(
+    client: &deadpool_postgres::Object,
+    user_id: Uuid,
+    artist: &str,
+    album: Option<&str>,
+) -> Result<bool, tokio_postgres::Error> {
+    let artist_matches = match album {
+        Some(_) => "s.artist = $2".to_string(),
+        None => song_credits_artist("s.id", "$2"),
+    };
+    Ok(client
+        .query_opt(
+            &format!(
+                "SELECT 1 FROM songs s JOIN user_songs us ON s.id = us.song_id \
+                 WHERE us.user_id = $1 AND {artist_matches} \
+                   AND ($3::TEXT IS NULL OR s.album = $3) \
+                 LIMIT 1"
+            ),
+            &[&user_id, &artist, &album],
+        )
+        .await?
+        .is_some())
+}
+
+/// Store or clear one rating; `Ok(false)` if the item is not in the user's personal library.
+async fn store_rating(
+    client: &deadpool_postgres::Object,
+    user_id: Uuid,
+    id: &str,
+    rating: Option<i16>,
+) -> Result<bool, tokio_postgres::Error> {
+    if let Some((artist, album)) = decode_album_id(id) {
+        if !in_library(client, user_id, &artist, Some(&album)).await? {
+            return Ok(false);
+        }
+        match rating {
+            Some(rating) => {
+                client
+                    .execute(
+                        "INSERT INTO album_ratings (user_id, artist_name, album_name, rating) \
+                         VALUES ($1, $2, $3, $4) \
+                         ON CONFLICT (user_id, artist_name, album_name) \
+                         DO UPDATE SET rating = EXCLUDED.rating, updated_at = NOW()",
+                        &[&user_id, &artist, &album, &rating],
+                    )
+                    .await?
+            }
+            None => {
+                client
+                    .execute(
+                        "DELETE FROM album_ratings \
+                         WHERE user_id = $1 AND artist_name = $2 AND album_name = $3",
+                        &[&user_id, &artist, &album],
+                    )
+                    .await?
+            }
+        };
+    } else if let Some(artist) = decode_artist_id(id) {
+        if !in_library(client, user_id, &artist, None).await? {
+            return Ok(false);
+        }
+        match rating {
+            Some(rating) => {
+                client
+                    .execute(
+                        "INSERT INTO artist_ratings (user_id, artist_name, rating) \
+                         VALUES ($1, $2, $3) \
+                         ON CONFLICT (user_id, artist_name) \
+                         DO UPDATE SET rating = EXCLUDED.rating, updated_at = NOW()",
+                        &[&user_id, &artist, &rating],
+                    )
+                    .await?
+            }
+            None => {
+                client
+                    .execute(
+                        "DELETE FROM artist_ratings WHERE user_id = $1 AND artist_name = $2",
+                        &[&user_id, &artist],
+                    )
+                    .await?
+            }
+        };
+    } else {
+        let Ok(song) = Uuid::parse_str(id) else {
+            return Ok(false);
+        };
+        if !in_personal_library(client, user_id, song).await? {
+            return Ok(false);
+        }
+        match rating {
+            Some(rating) => {
+                client
+                    .execute(
+                        "INSERT INTO song_ratings (user_id, song_id, rating) VALUES ($1, $2, $3) \
+                         ON CONFLICT (user_id, song_id) \
+                         DO UPDATE SET rating = EXCLUDED.rating, updated_at = NOW()",
+                        &[&user_id, &song, &rating],
+                    )
+                    .await?
+            }
+            None => {
+                client
+                    .execute(
+                        "DELETE FROM song_ratings WHERE user_id = $1 AND song_id = $2",
+                        &[&user_id, &song],
+                    )
+                    .await?
+            }
+        };
+    }
+    Ok(true)
+}
+
+#[get("/setRating")]
+pub(crate) async fn set_rating(
+    pool: &State<Pool>,
+    user: SubsonicUser,
+    query: SubsonicQuery,
+) -> Json<serde_json::Value> {
+    let Some(id) = query.first("id") else {
+        return param_err("Required parameter 'id' is missing");
+    };
+    let rating = match rating_param(&query) {
+        Ok(rating) => rating,
+        Err(response) => return response,
+    };
+    let Ok(client) = pool.get().await else {
+        return db_err_resp();
+    };
+    match store_rating(&client, user.id, &id, rating).await {
+        Ok(true) => ok_empty_resp(),
+        Ok(false) => not_found_resp(),
+        Err(_) => db_err_resp(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ratings_are_omitted_until_set() {
+        let unrated = serde_json::to_value(Rating::default()).unwrap_or_default();
+        assert_eq!(unrated, serde_json::json!({}));
+
+        let rated = serde_json::to_value(Rating {
+            user_rating: Some(4),
+            average_rating: Some(3.5),
+        })
+        .unwrap_or_default();
+        assert_eq!(rated["userRating"], 4);
+        assert_eq!(rated["averageRating"], 3.5);
+    }
+}
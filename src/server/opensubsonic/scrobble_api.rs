@@ -79,18 +79,22 @@ pub(crate) async fn scrobble(
 #[get("/getNowPlaying")]
 pub(crate) async fn get_now_playing(
     pool: &State<Pool>,
-    _user: SubsonicUser,
+    user: SubsonicUser,
 ) -> Json<serde_json::Value> {
     let Ok(client) = pool.get().await else {
         return db_err_resp();
     };
     let rows = match client
         .query(
-            "SELECT s.*, sc.played_at, u.username \
-             FROM scrobbles sc JOIN songs s ON sc.song_id = s.id \
-             JOIN users u ON sc.user_id = u.id \
-             WHERE sc.submission = false ORDER BY sc.played_at DESC LIMIT 10",
-            &[],
+            &format!(
+                "SELECT s.*, sc.played_at, u.username, {}, {} \
+                 FROM scrobbles sc JOIN songs s ON sc.song_id = s.id \
+                 JOIN users u ON sc.user_id = u.id \
+                 WHERE sc.submission = false ORDER BY sc.played_at DESC LIMIT 10",
+                song_rating_columns("$1", "s.id"),
+                song_credit_columns("s.id")
+            ),
+            &[&user.id],
         )
         .await
     {
@@ -38,6 +38,12 @@ pub(super) fn row_to_song_entry(row: &tokio_postgres::Row) -> SongEntry {
         content_type: format_to_mime(&format).to_string(),
         is_dir: false,
         is_video: false,
+        sampling_rate: row.try_get("sample_rate").ok(),
+        bit_depth: row.try_get("bit_depth").ok(),
+        channel_count: row.try_get("channel_count").ok(),
+        rating: Rating::from_row(row),
+        replay_gain: ReplayGain::from_row(row),
+        credits: EntryCredits::from_row(row),
     }
 }
 
@@ -127,6 +133,16 @@ pub(super) fn not_found_resp() -> Json<serde_json::Value> {
     )
 }
 
+pub(super) fn unauthorized_resp() -> Json<serde_json::Value> {
+    Json(
+        serde_json::to_value(SubsonicResponse::<EmptyResponse>::error(
+            50,
+            "User is not authorized for the given operation",
+        ))
+        .unwrap_or_default(),
+    )
+}
+
 pub(super) fn param_err(msg: &str) -> Json<serde_json::Value> {
     Json(
         serde_json::to_value(SubsonicResponse::<EmptyResponse>::error(10, msg)).unwrap_or_default(),
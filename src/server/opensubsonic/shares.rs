@@ -0,0 +1,566 @@
+use super::*;
+
+use crate::audit::{AuditActor, AuditEvent, record_audit};
+use crate::auth::{ACTIVE_USER_SQL, check_permission};
+use crate::shared::{
+    ApiError, db_error, get_client, not_found, public_url, random_hex, server_error, sha256_hex,
+};
+use chrono::DateTime;
+
+// ── Shares ──
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct SharesResponse {
+    pub shares: ShareList,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ShareList {
+    pub share: Vec<ShareEntry>,
+}
+
+/// Only the hash of a share's token is stored, so `url` is known only when it is created.
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ShareEntry {
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub username: String,
+    pub created: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires: Option<String>,
+    #[serde(rename = "lastVisited", skip_serializing_if = "Option::is_none")]
+    pub last_visited: Option<String>,
+    #[serde(rename = "visitCount")]
+    pub visit_count: i32,
+    pub entry: Vec<SongEntry>,
+}
+
+/// GET /api/public/shares/<token> - What the public share page shows.
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct PublicShare {
+    pub description: Option<String>,
+    pub username: String,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub songs: Vec<PublicShareSong>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct PublicShareSong {
+    pub id: Uuid,
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+    pub duration_seconds: Option<f32>,
+}
+
+const SHARE_COLUMNS: &str =
+    "sh.id, sh.description, sh.expires_at, sh.created_at, sh.last_visited_at, sh.visit_count";
+
+/// Parse `expires` (milliseconds since the epoch); `0` means the share never expires.
+fn parse_expiry(value: &str) -> Option<Option<DateTime<Utc>>> {
+    match value.trim().parse::<i64>().ok()? {
+        0 => Some(None),
+        ms if ms > 0 => DateTime::from_timestamp_millis(ms).map(Some),
+        _ => None,
+    }
+}
+
+fn expiry_param(
+    query: &SubsonicQuery,
+) -> Result<Option<Option<DateTime<Utc>>>, Json<serde_json::Value>> {
+    match query.first("expires") {
+        None => Ok(None),
+        Some(value) => parse_expiry(&value).map(Some).ok_or_else(|| {
+            param_err("Parameter 'expires' must be milliseconds since the epoch, or 0")
+        }),
+    }
+}
+
+fn share_url(token: &str) -> String {
+    format!("{}/share/{token}", public_url())
+}
+
+/// Songs behind the repeated `id` parameters, in order and without repeats.
+///
+/// Each id is a song, an album or a playlist the user can see; only songs in the
+/// user's personal library are shared.
+async fn shared_songs(
+    client: &deadpool_postgres::Object,
+    user_id: Uuid,
+    ids: &[String],
+) -> Result<Vec<Uuid>, Json<serde_json::Value>> {
+    let mut songs = Vec::new();
+    for id in ids {
+        let rows = if let Some((artist, album)) = decode_album_id(id) {
+            client
+                .query(
+                    "SELECT s.id FROM songs s JOIN user_songs us ON s.id = us.song_id \
+                     WHERE us.user_id = $1 AND s.artist = $2 AND s.album = $3 \
+                     ORDER BY s.disc_number NULLS LAST, s.track_number NULLS LAST, s.title",
+                    &[&user_id, &artist, &album],
+                )
+                .await
+                .map_err(|_| db_err_resp())?
+        } else {
+            let Ok(id) = Uuid::parse_str(id) else {
+                return Err(not_found_resp());
+            };
+            if in_personal_library(client, user_id, id)
+                .await
+                .map_err(|_| db_err_resp())?
+            {
+                songs.push(id);
+                continue;
+            }
+            let visible = client
+                .query_opt(
+                    "SELECT 1 FROM playlists WHERE id = $1 AND (user_id = $2 OR public = TRUE)",
+                    &[&id, &user_id],
+                )
+                .await
+                .map_err(|_| db_err_resp())?
+                .is_some();
+            if !visible {
+                return Err(not_found_resp());
+            }
+            client
+                .query(
+                    "SELECT ps.song_id AS id FROM playlist_songs ps \
+                     JOIN user_songs us ON us.song_id = ps.song_id AND us.user_id = $2 \
+                     WHERE ps.playlist_id = $1 ORDER BY ps.position",
+                    &[&id, &user_id],
+                )
+                .await
+                .map_err(|_| db_err_resp())?
+        };
+        songs.extend(rows.iter().map(|row| row.get::<_, Uuid>("id")));
+    }
+    let mut seen = HashSet::new();
+    songs.retain(|song| seen.insert(*song));
+    if songs.is_empty() {
+        return Err(not_found_resp());
+    }
+    Ok(songs)
+}
+
+/// Songs of a share that its owner can still play, in share order.
+async fn share_songs(
+    client: &deadpool_postgres::Object,
+    share_id: Uuid,
+) -> Result<Vec<tokio_postgres::Row>, tokio_postgres::Error> {
+    client
+        .query(
+            &format!(
+                "SELECT s.*, {}, {} FROM share_entries e \
+                 JOIN shares sh ON sh.id = e.share_id \
+                 JOIN songs s ON s.id = e.song_id \
+                 JOIN user_songs us ON us.song_id = e.song_id AND us.user_id = sh.user_id \
+                 WHERE e.share_id = $1 ORDER BY e.position",
+                song_rating_columns("sh.user_id", "s.id"),
+                song_credit_columns("s.id")
+            ),
+            &[&share_id],
+        )
+        .await
+}
+
+async fn share_entry(
+    client: &deadpool_postgres::Object,
+    row: &tokio_postgres::Row,
+    username: &str,
+    url: Option<String>,
+) -> Result<ShareEntry, tokio_postgres::Error> {
+    let id: Uuid = row.get("id");
+    let songs = share_songs(client, id).await?;
+    Ok(ShareEntry {
+        id: id.to_string(),
+        url,
+        description: row.get("description"),
+        username: username.to_string(),
+        created: row.get::<_, DateTime<Utc>>("created_at").to_rfc3339(),
+        expires: row
+            .get::<_, Option<DateTime<Utc>>>("expires_at")
+            .map(|at| at.to_rfc3339()),
+        last_visited: row
+            .get::<_, Option<DateTime<Utc>>>("last_visited_at")
+            .map(|at| at.to_rfc3339()),
+        visit_count: row.get("visit_count"),
+        entry: songs.iter().map(row_to_song_entry).collect(),
+    })
+}
+
+fn share_param(query: &SubsonicQuery) -> Result<Uuid, Json<serde_json::Value>> {
+    let id = query
+        .first("id")
+        .ok_or_else(|| param_err("Required parameter 'id' is missing"))?;
+    Uuid::parse_str(&id).map_err(|_| not_found_resp())
+}
+
+#[get("/getShares")]
+pub(crate) async fn get_shares(pool: &State<Pool>, user: SubsonicUser) -> Json<serde_json::Value> {
+    let Ok(client) = pool.get().await else {
+        return db_err_resp();
+    };
+    let Ok(rows) = client
+        .query(
+            &format!(
+                "SELECT {SHARE_COLUMNS} FROM shares sh \
+                 WHERE sh.user_id = $1 ORDER BY sh.created_at DESC"
+            ),
+            &[&user.id],
+        )
+        .await
+    else {
+        return db_err_resp();
+    };
+    let mut share = Vec::with_capacity(rows.len());
+    for row in &rows {
+        match share_entry(&client, row, &user.username, None).await {
+            Ok(entry) => share.push(entry),
+            Err(_) => return db_err_resp(),
+        }
+    }
+    ok_resp(SharesResponse {
+        shares: ShareList { share },
+    })
+}
+
+#[get("/createShare")]
+pub(crate) async fn create_share(
+    pool: &State<Pool>,
+    user: SubsonicUser,
+    query: SubsonicQuery,
+) -> Json<serde_json::Value> {
+    match user.has_permission(pool, "share_music").await {
+        Ok(true) => {}
+        Ok(false) => return unauthorized_resp(),
+        Err(_) => return db_err_resp(),
+    }
+    let ids = query.all("id");
+    if ids.is_empty() {
+        return param_err("Required parameter 'id' is missing");
+    }
+    let expires_at = match expiry_param(&query) {
+        Ok(expires_at) => expires_at.flatten(),
+        Err(response) => return response,
+    };
+    if expires_at.is_some_and(|at| at <= Utc::now()) {
+        return param_err("Parameter 'expires' must be in the future");
+    }
+    let description = query
+        .first("description")
+        .map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty());
+
+    let Ok(mut client) = pool.get().await else {
+        return db_err_resp();
+    };
+    let songs = match shared_songs(&client, user.id, &ids).await {
+        Ok(songs) => songs,
+        Err(response) => return response,
+    };
+    let token = random_hex::<32>();
+    let Ok(transaction) = client.transaction().await else {
+        return db_err_resp();
+    };
+    let Ok(row) = transaction
+        .query_one(
+            &format!(
+                "INSERT INTO shares AS sh (token_hash, user_id, description, expires_at) \
+                 VALUES ($1, $2, $3, $4) RETURNING {SHARE_COLUMNS}"
+            ),
+            &[&sha256_hex(&token), &user.id, &description, &expires_at],
+        )
+        .await
+    else {
+        return db_err_resp();
+    };
+    let share_id: Uuid = row.get("id");
+    let positions: Vec<i32> = (0..songs.len() as i32).collect();
+    if transaction
+        .execute(
+            "INSERT INTO share_entries (share_id, position, song_id) \
+             SELECT $1, entry.position, entry.song_id \
+             FROM UNNEST($2::INTEGER[], $3::UUID[]) AS entry(position, song_id)",
+            &[&share_id, &positions, &songs],
+        )
+        .await
+        .is_err()
+        || transaction.commit().await.is_err()
+    {
+        return db_err_resp();
+    }
+    record_audit(
+        pool,
+        AuditActor::user(user.id, &user.username),
+        AuditEvent::new("share.create", "share", share_id).after(serde_json::json!({
+            "description": description,
+            "expires_at": expires_at,
+            "songs": songs,
+        })),
+    )
+    .await;
+
+    match share_entry(&client, &row, &user.username, Some(share_url(&token))).await {
+        Ok(entry) => ok_resp(SharesResponse {
+            shares: ShareList { share: vec![entry] },
+        }),
+        Err(_) => db_err_resp(),
+    }
+}
+
+#[get("/updateShare")]
+pub(crate) async fn update_share(
+    pool: &State<Pool>,
+    user: SubsonicUser,
+    query: SubsonicQuery,
+) -> Json<serde_json::Value> {
+    let id = match share_param(&query) {
+        Ok(id) => id,
+        Err(response) => return response,
+    };
+    let expires_at = match expiry_param(&query) {
+        Ok(expires_at) => expires_at,
+        Err(response) => return response,
+    };
+    let description = query
+        .first("description")
+        .map(|text| text.trim().to_string());
+    let Ok(client) = pool.get().await else {
+        return db_err_resp();
+    };
+    let Ok(before) = client
+        .query_opt(
+            &format!("SELECT {SHARE_COLUMNS} FROM shares sh WHERE sh.id = $1 AND sh.user_id = $2"),
+            &[&id, &user.id],
+        )
+        .await
+    else {
+        return db_err_resp();
+    };
+    let Some(before) = before else {
+        return not_found_resp();
+    };
+    let description = match description {
+        Some(text) => (!text.is_empty()).then_some(text),
+        None => before.get("description"),
+    };
+    let expires_at: Option<DateTime<Utc>> = expires_at.unwrap_or_else(|| before.get("expires_at"));
+    if client
+        .execute(
+            "UPDATE shares SET description = $1, expires_at = $2 WHERE id = $3",
+            &[&description, &expires_at, &id],
+        )
+        .await
+        .is_err()
+    {
+        return db_err_resp();
+    }
+    record_audit(
+        pool,
+        AuditActor::user(user.id, &user.username),
+        AuditEvent::new("share.update", "share", id)
+            .before(serde_json::json!({
+                "description": before.get::<_, Option<String>>("description"),
+                "expires_at": before.get::<_, Option<DateTime<Utc>>>("expires_at"),
+            }))
+            .after(serde_json::json!({
+                "description": description,
+                "expires_at": expires_at,
+            })),
+    )
+    .await;
+    ok_empty_resp()
+}
+
+#[get("/deleteShare")]
+pub(crate) async fn delete_share(
+    pool: &State<Pool>,
+    user: SubsonicUser,
+    query: SubsonicQuery,
+) -> Json<serde_json::Value> {
+    let id = match share_param(&query) {
+        Ok(id) => id,
+        Err(response) => return response,
+    };
+    let Ok(client) = pool.get().await else {
+        return db_err_resp();
+    };
+    match client
+        .query_opt(
+            "DELETE FROM shares WHERE id = $1 AND user_id = $2 RETURNING description",
+            &[&id, &user.id],
+        )
+        .await
+    {
+        Ok(Some(row)) => {
+            record_audit(
+                pool,
+                AuditActor::user(user.id, &user.username),
+                AuditEvent::new("share.delete", "share", id).before(serde_json::json!({
+                    "description": row.get::<_, Option<String>>("description"),
+                })),
+            )
+            .await;
+            ok_empty_resp()
+        }
+        Ok(None) => not_found_resp(),
+        Err(_) => db_err_resp(),
+    }
+}
+
+/// The unexpired share behind `token`, while its owner can still sign in and share music.
+async fn live_share(
+    pool: &Pool,
+    client: &deadpool_postgres::Object,
+    token: &str,
+) -> Result<tokio_postgres::Row, ApiError> {
+    let share = client
+        .query_opt(
+            &format!(
+                "SELECT sh.id, sh.user_id, sh.description, sh.expires_at, u.username \
+                 FROM shares sh JOIN users u ON u.id = sh.user_id \
+                 WHERE sh.token_hash = $1 AND (sh.expires_at IS NULL OR sh.expires_at > NOW()) \
+                   AND {ACTIVE_USER_SQL}"
+            ),
+            &[&sha256_hex(token)],
+        )
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| not_found("Share is invalid or has expired"))?;
+    match check_permission(pool, share.get("user_id"), "share_music").await {
+        Ok(true) => Ok(share),
+        Ok(false) => Err(not_found("Share is invalid or has expired")),
+        Err(error) => {
+            eprintln!("Failed to check the owner of a share: {error}");
+            Err(server_error())
+        }
+    }
+}
+
+/// GET /api/public/shares/<token> - Open a share without credentials, counting the visit.
+#[get("/public/shares/<token>")]
+pub(crate) async fn get_public_share(
+    pool: &State<Pool>,
+    token: &str,
+) -> Result<Json<PublicShare>, ApiError> {
+    let client = get_client(pool).await?;
+    let share = live_share(pool, &client, token).await?;
+    client
+        .execute(
+            "UPDATE shares SET visit_count = visit_count + 1, last_visited_at = NOW() \
+             WHERE id = $1",
+            &[&share.get::<_, Uuid>("id")],
+        )
+        .await
+        .map_err(db_error)?;
+    let songs = share_songs(&client, share.get("id"))
+        .await
+        .map_err(db_error)?;
+
+    Ok(Json(PublicShare {
+        description: share.get("description"),
+        username: share.get("username"),
+        expires_at: share.get("expires_at"),
+        songs: songs
+            .iter()
+            .map(|row| PublicShareSong {
+                id: row.get("id"),
+                title: row.get("title"),
+                artist: row.get("artist"),
+                album: row.get("album"),
+                duration_seconds: row.get("duration_seconds"),
+            })
+            .collect(),
+    }))
+}
+
+/// GET /api/public/shares/<token>/songs/<id> - Stream one shared song, with range support.
+#[get("/public/shares/<token>/songs/<id>")]
+pub(crate) async fn stream_public_share(
+    pool: &State<Pool>,
+    range_hdr: RangeHeader,
+    token: &str,
+    id: &str,
+) -> Result<SubsonicBinaryResponse, ApiError> {
+    let song_id = Uuid::parse_str(id).map_err(|_| not_found("Song not found"))?;
+    let client = get_client(pool).await?;
+    let share = live_share(pool, &client, token).await?;
+    let row = client
+        .query_opt(
+            "SELECT s.file_path, s.format, s.size_bytes FROM share_entries e \
+             JOIN songs s ON s.id = e.song_id \
+             JOIN user_songs us ON us.song_id = e.song_id AND us.user_id = $1 \
+             WHERE e.share_id = $2 AND e.song_id = $3 \
+             LIMIT 1",
+            &[
+                &share.get::<_, Uuid>("user_id"),
+                &share.get::<_, Uuid>("id"),
+                &song_id,
+            ],
+        )
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| not_found("Song not found"))?;
+
+    let file_path: String = row.get("file_path");
+    let format: String = row.get("format");
+    let size_bytes: i64 = row.get("size_bytes");
+    Ok(serve_file(
+        &Path::new(MUSIC_ROOT).join(file_path),
+        format_to_mime(&format).to_string(),
+        size_bytes as u64,
+        &range_hdr,
+    )
+    .await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expiry_is_epoch_milliseconds_or_zero() {
+        assert_eq!(parse_expiry("0"), Some(None));
+        assert_eq!(
+            parse_expiry("1700000000000"),
+            Some(DateTime::from_timestamp_millis(1_700_000_000_000))
+        );
+        assert_eq!(parse_expiry("-5"), None);
+        assert_eq!(parse_expiry("tomorrow"), None);
+    }
+
+    #[test]
+    fn shares_use_subsonic_field_names() {
+        let response = ok_resp(SharesResponse {
+            shares: ShareList {
+                share: vec![ShareEntry {
+                    id: Uuid::nil().to_string(),
+                    url: None,
+                    description: Some("For Sam".into()),
+                    username: "alice".into(),
+                    created: "2026-01-01T00:00:00+00:00".into(),
+                    expires: None,
+                    last_visited: Some("2026-01-02T00:00:00+00:00".into()),
+                    visit_count: 3,
+                    entry: Vec::new(),
+                }],
+            },
+        })
+        .into_inner();
+        let share = &response["subsonic-response"]["shares"]["share"][0];
+
+        assert_eq!(share["visitCount"], 3);
+        assert_eq!(share["lastVisited"], "2026-01-02T00:00:00+00:00");
+        assert!(share.get("url").is_none());
+        assert!(share.get("expires").is_none());
+    }
+}
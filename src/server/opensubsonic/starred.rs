@@ -196,8 +196,11 @@ async fn build_starred(
 ) -> Result<StarredContainer, ()> {
     let artist_rows = client
         .query(
-            "SELECT DISTINCT artist_name FROM starred \
-             WHERE user_id = $1 AND song_id IS NULL AND album_name IS NULL",
+            &format!(
+                "SELECT DISTINCT st.artist_name, {} FROM starred st \
+                 WHERE st.user_id = $1 AND st.song_id IS NULL AND st.album_name IS NULL",
+                artist_rating_columns("$1", "st.artist_name")
+            ),
             &[&user_id],
         )
         .await
@@ -210,6 +213,7 @@ async fn build_starred(
                 ArtistRef {
                     id: artist_id(&name),
                     name,
+                    rating: Rating::from_row(r),
                 }
             })
             .collect::<Vec<_>>(),
@@ -217,14 +221,17 @@ async fn build_starred(
 
     let album_rows = client
         .query(
-            "SELECT st.artist_name, st.album_name, MIN(s.year) AS year, MIN(s.genre) AS genre, \
-                    BOOL_OR(s.has_cover_art) AS has_cover, COUNT(*) AS cnt, \
-                    COALESCE(SUM(s.duration_seconds), 0) AS dur \
-             FROM starred st JOIN songs s ON s.artist = st.artist_name AND s.album = st.album_name \
-             JOIN user_songs us ON us.song_id = s.id AND us.user_id = $1 \
-             WHERE st.user_id = $1 AND st.song_id IS NULL AND st.album_name IS NOT NULL \
-             GROUP BY st.artist_name, st.album_name \
-             ORDER BY st.artist_name, st.album_name",
+            &format!(
+                "SELECT st.artist_name, st.album_name, MIN(s.year) AS year, MIN(s.genre) AS genre, \
+                        BOOL_OR(s.has_cover_art) AS has_cover, COUNT(*) AS cnt, \
+                        COALESCE(SUM(s.duration_seconds), 0) AS dur, {} \
+                 FROM starred st JOIN songs s ON s.artist = st.artist_name AND s.album = st.album_name \
+                 JOIN user_songs us ON us.song_id = s.id AND us.user_id = $1 \
+                 WHERE st.user_id = $1 AND st.song_id IS NULL AND st.album_name IS NOT NULL \
+                 GROUP BY st.artist_name, st.album_name \
+                 ORDER BY st.artist_name, st.album_name",
+                album_rating_columns("$1", "st.artist_name", "st.album_name")
+            ),
             &[&user_id],
         )
         .await
@@ -242,9 +249,13 @@ async fn build_starred(
 
     let song_rows = client
         .query(
-            "SELECT s.* FROM starred st JOIN songs s ON st.song_id = s.id \
-             JOIN user_songs us ON us.song_id = s.id AND us.user_id = $1 \
-             WHERE st.user_id = $1 AND st.song_id IS NOT NULL ORDER BY st.created_at",
+            &format!(
+                "SELECT s.*, {}, {} FROM starred st JOIN songs s ON st.song_id = s.id \
+                 JOIN user_songs us ON us.song_id = s.id AND us.user_id = $1 \
+                 WHERE st.user_id = $1 AND st.song_id IS NOT NULL ORDER BY st.created_at",
+                song_rating_columns("$1", "s.id"),
+                song_credit_columns("s.id")
+            ),
             &[&user_id],
         )
         .await
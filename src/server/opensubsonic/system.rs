@@ -32,6 +32,18 @@ pub(crate) fn get_open_subsonic_extensions(_user: SubsonicUser) -> Json<serde_js
                     name: "songTitle".into(),
                     versions: vec![1],
                 },
+                ExtensionInfo {
+                    name: "indexBasedQueue".into(),
+                    versions: vec![1],
+                },
+                ExtensionInfo {
+                    name: "transcodeOffset".into(),
+                    versions: vec![1],
+                },
+                ExtensionInfo {
+                    name: "songLyrics".into(),
+                    versions: vec![1],
+                },
             ],
         }))
         .unwrap_or_default(),
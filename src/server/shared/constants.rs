@@ -1,3 +1,4 @@
 pub(crate) const STORAGE_ROOT: &str = "storage/files";
 pub(crate) const MUSIC_ROOT: &str = "storage/music";
+pub(crate) const TRANSCODE_CACHE_ROOT: &str = "storage/cache/transcode";
 pub(crate) const BUILD_ROOT: &str = "dist";
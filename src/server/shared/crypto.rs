@@ -13,3 +13,119 @@ pub(crate) fn sha256_hex(value: &str) -> String {
     hasher.update(value.as_bytes());
     hex::encode(hasher.finalize())
 }
+
+const NONCE_LEN: usize = 12;
+
+/// Shortest `JWT_SECRET` accepted, as the README asks.
+const MIN_JWT_SECRET_LEN: usize = 32;
+
+/// Key for secrets the server must be able to read back, such as Subsonic app passwords.
+///
+/// Resolved once at startup and kept in managed state.
+#[derive(Clone, Copy)]
+pub(crate) struct AppSecretKey([u8; 32]);
+
+impl AppSecretKey {
+    /// Read `APP_SECRET_KEY` (64 hex characters), or derive the key from `JWT_SECRET`.
+    pub(crate) fn from_env() -> Result<Self, String> {
+        Self::resolve(
+            std::env::var("APP_SECRET_KEY").ok().as_deref(),
+            std::env::var("JWT_SECRET").ok().as_deref(),
+        )
+    }
+
+    fn resolve(app_secret_key: Option<&str>, jwt_secret: Option<&str>) -> Result<Self, String> {
+        let app_secret_key = app_secret_key
+            .map(str::trim)
+            .filter(|value| !value.is_empty());
+        let jwt_secret = jwt_secret.filter(|value| !value.is_empty());
+        if let Some(secret) = jwt_secret
+            && secret.chars().count() < MIN_JWT_SECRET_LEN
+        {
+            return Err(format!(
+                "JWT_SECRET must be at least {MIN_JWT_SECRET_LEN} characters"
+            ));
+        }
+        if let Some(value) = app_secret_key {
+            return hex::decode(value)
+                .ok()
+                .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+                .map(Self)
+                .ok_or_else(|| "APP_SECRET_KEY must be 64 hex characters".to_string());
+        }
+        let Some(secret) = jwt_secret else {
+            return Err("Set APP_SECRET_KEY or JWT_SECRET".to_string());
+        };
+        let mut hasher = Sha256::new();
+        hasher.update(b"blackfiles-app-secret:");
+        hasher.update(secret.as_bytes());
+        Ok(Self(hasher.finalize().into()))
+    }
+
+    /// A random key, for tests.
+    #[cfg(test)]
+    pub(crate) fn generated() -> Self {
+        Self(random())
+    }
+}
+
+/// Encrypt `plaintext` with AES-256-GCM, returning `nonce || ciphertext`.
+pub(crate) fn seal_secret(key: &AppSecretKey, plaintext: &str) -> Result<Vec<u8>, String> {
+    use aes_gcm::aead::{Aead, KeyInit};
+    use aes_gcm::{Aes256Gcm, Nonce};
+
+    let nonce = random::<[u8; NONCE_LEN]>();
+    let ciphertext = Aes256Gcm::new((&key.0).into())
+        .encrypt(Nonce::from_slice(&nonce), plaintext.as_bytes())
+        .map_err(|error| error.to_string())?;
+    Ok([nonce.as_slice(), &ciphertext].concat())
+}
+
+/// Reverse of [`seal_secret`].
+pub(crate) fn open_secret(key: &AppSecretKey, sealed: &[u8]) -> Result<String, String> {
+    use aes_gcm::aead::{Aead, KeyInit};
+    use aes_gcm::{Aes256Gcm, Nonce};
+
+    if sealed.len() < NONCE_LEN {
+        return Err("sealed secret is truncated".to_string());
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    let plaintext = Aes256Gcm::new((&key.0).into())
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|error| error.to_string())?;
+    String::from_utf8(plaintext).map_err(|error| error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sealed_secrets_round_trip_and_reject_the_wrong_key() {
+        let key = AppSecretKey([7u8; 32]);
+        let sealed = seal_secret(&key, "app-password").expect("secret should seal");
+
+        assert_ne!(&sealed[NONCE_LEN..], b"app-password");
+        assert_eq!(open_secret(&key, &sealed).as_deref(), Ok("app-password"));
+        assert!(open_secret(&AppSecretKey([8u8; 32]), &sealed).is_err());
+        assert!(open_secret(&key, &sealed[..4]).is_err());
+    }
+
+    #[test]
+    fn app_secret_key_is_required_and_validated() {
+        let jwt_secret = "a".repeat(MIN_JWT_SECRET_LEN);
+        let hex_key = "ab".repeat(32);
+
+        assert!(AppSecretKey::resolve(None, None).is_err());
+        assert!(AppSecretKey::resolve(Some(""), Some("")).is_err());
+        assert!(AppSecretKey::resolve(None, Some("too-short")).is_err());
+        assert!(AppSecretKey::resolve(Some("not-hex"), Some(&jwt_secret)).is_err());
+        assert!(AppSecretKey::resolve(Some("abcd"), Some(&jwt_secret)).is_err());
+
+        let explicit = AppSecretKey::resolve(Some(&hex_key), None).expect("hex key should load");
+        assert_eq!(explicit.0, [0xab; 32]);
+        let derived =
+            AppSecretKey::resolve(Some(""), Some(&jwt_secret)).expect("key should derive");
+        assert_ne!(derived.0, [0u8; 32]);
+    }
+}
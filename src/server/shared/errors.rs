@@ -4,12 +4,24 @@ use rocket::serde::json::Json;
 
 pub(crate) type ApiError = (Status, Json<serde_json::Value>);
 
+/// Request-local flag set when a guard rejects a missing or mismatched CSRF token.
+pub(crate) struct CsrfRejected(pub bool);
+
 pub(crate) fn server_error() -> ApiError {
     status_error(Status::InternalServerError, "Server error")
 }
 
 #[catch(default)]
-pub(crate) fn api_error(status: Status, _: &Request<'_>) -> ApiError {
+pub(crate) fn api_error(status: Status, request: &Request<'_>) -> ApiError {
+    if status == Status::Forbidden && request.local_cache(|| CsrfRejected(false)).0 {
+        return structured_error(
+            status,
+            "csrf_failed",
+            "Missing or invalid CSRF token",
+            serde_json::Value::Null,
+        );
+    }
+
     let message = if status == Status::InternalServerError {
         "Server error"
     } else {
@@ -42,3 +54,16 @@ pub(crate) fn conflict(message: &str) -> ApiError {
 pub(crate) fn status_error(status: Status, message: &str) -> ApiError {
     (status, Json(serde_json::json!({"error": message})))
 }
+
+/// An error with a machine-readable `code` and per-field `details` alongside the usual message.
+pub(crate) fn structured_error(
+    status: Status,
+    code: &str,
+    message: &str,
+    details: serde_json::Value,
+) -> ApiError {
+    (
+        status,
+        Json(serde_json::json!({"error": message, "code": code, "details": details})),
+    )
+}
@@ -0,0 +1,73 @@
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+
+/// SMTP settings read from the environment. Mail is disabled unless `SMTP_HOST`
+/// and `SMTP_FROM` are both set.
+pub(crate) struct MailConfig {
+    host: String,
+    port: u16,
+    credentials: Option<Credentials>,
+    from: Mailbox,
+}
+
+impl MailConfig {
+    pub(crate) fn from_env() -> Option<Self> {
+        let host = std::env::var("SMTP_HOST").ok().filter(|v| !v.is_empty())?;
+        let from = std::env::var("SMTP_FROM").ok()?.parse::<Mailbox>().ok()?;
+        let port = std::env::var("SMTP_PORT")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(587);
+        let credentials = std::env::var("SMTP_USERNAME")
+            .ok()
+            .filter(|v| !v.is_empty())
+            .map(|username| {
+                Credentials::new(username, std::env::var("SMTP_PASSWORD").unwrap_or_default())
+            });
+
+        Some(Self {
+            host,
+            port,
+            credentials,
+            from,
+        })
+    }
+
+    pub(crate) async fn send(&self, to: &str, subject: &str, body: String) -> Result<(), String> {
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(to.parse::<Mailbox>().map_err(|e| e.to_string())?)
+            .subject(subject)
+            .body(body)
+            .map_err(|e| e.to_string())?;
+
+        // Port 465 speaks TLS from the first byte; everything else upgrades with STARTTLS.
+        let builder = if self.port == 465 {
+            AsyncSmtpTransport::<Tokio1Executor>::relay(&self.host)
+        } else {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&self.host)
+        }
+        .map_err(|e| e.to_string())?
+        .port(self.port);
+        let builder = match &self.credentials {
+            Some(credentials) => builder.credentials(credentials.clone()),
+            None => builder,
+        };
+
+        builder
+            .build()
+            .send(message)
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Base URL of the web UI used in links sent out of band, without a trailing slash.
+pub(crate) fn public_url() -> String {
+    std::env::var("PUBLIC_URL")
+        .unwrap_or_else(|_| "http://localhost:4000".to_string())
+        .trim_end_matches('/')
+        .to_string()
+}
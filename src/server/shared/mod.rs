@@ -4,6 +4,7 @@ mod db;
 mod encoding;
 mod errors;
 mod files;
+mod mail;
 mod pagination;
 mod tus;
 
@@ -13,5 +14,6 @@ pub(crate) use db::*;
 pub(crate) use encoding::*;
 pub(crate) use errors::*;
 pub(crate) use files::*;
+pub(crate) use mail::*;
 pub(crate) use pagination::*;
 pub(crate) use tus::*;
@@ -196,10 +196,13 @@ pub(crate) struct UploadProgress {
     pub(crate) offset: u64,
 }
 
+/// Where partial uploads for `storage_root` are kept until they complete.
+pub(crate) fn temporary_directory(storage_root: &str) -> PathBuf {
+    Path::new(storage_root).join(TEMP_DIRECTORY)
+}
+
 pub(crate) fn temporary_path(storage_root: &str, id: Uuid) -> PathBuf {
-    Path::new(storage_root)
-        .join(TEMP_DIRECTORY)
-        .join(format!("{id}.part"))
+    temporary_directory(storage_root).join(format!("{id}.part"))
 }
 
 pub(crate) fn filename_from_metadata(metadata: &str) -> Result<PathBuf, ApiError> {
@@ -322,7 +325,7 @@ pub(crate) async fn prepare_temporary_upload(
             .await
             .map_err(|_| server_error())?;
     }
-    fs::create_dir_all(Path::new(storage_root).join(TEMP_DIRECTORY))
+    fs::create_dir_all(temporary_directory(storage_root))
         .await
         .map_err(|_| server_error())?;
 
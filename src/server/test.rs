@@ -1,9 +1,9 @@
 #[cfg(test)]
 mod tests {
-    use crate::OpenSubsonicViewCompatibility;
     use crate::frontend::frontend_fallback;
     use crate::shared::api_error;
-    use rocket::http::{ContentType, Status};
+    use crate::{OpenSubsonicViewCompatibility, rocket_config};
+    use rocket::http::{ContentType, Header, Status};
     use rocket::local::blocking::Client;
 
     #[get("/bare-status")]
@@ -16,6 +16,11 @@ mod tests {
         c.to_owned()
     }
 
+    #[get("/client-ip")]
+    fn client_ip(ip: Option<std::net::IpAddr>) -> String {
+        ip.map(|ip| ip.to_string()).unwrap_or_default()
+    }
+
     fn test_rocket() -> rocket::Rocket<rocket::Build> {
         rocket::build()
             .attach(OpenSubsonicViewCompatibility)
@@ -61,4 +66,17 @@ mod tests {
             Some(serde_json::json!({"error": "Forbidden"}))
         );
     }
+
+    #[test]
+    fn client_address_ignores_forwarded_headers_by_default() {
+        let rocket = rocket::custom(rocket_config()).mount("/", routes![client_ip]);
+        let client = Client::tracked(rocket).expect("test Rocket should launch");
+        let response = client
+            .get("/client-ip")
+            .header(Header::new("X-Real-IP", "203.0.113.7"))
+            .remote("192.0.2.1:40000".parse().expect("address should parse"))
+            .dispatch();
+
+        assert_eq!(response.into_string().as_deref(), Some("192.0.2.1"));
+    }
 }